@@ -0,0 +1,118 @@
+//! Baselines for the pairing-heavy paths:
+//!
+//! ```text
+//! cargo bench --bench syra
+//! ```
+//!
+//! Every benchmark processes one item per iteration, so criterion's
+//! `thrpt` column reads directly as operations per second.
+
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_ff::UniformRand;
+use ark_serialize::Compress;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use syra_login::credential::verify_user_key;
+use syra_login::curve::{derive_user_key, sample_issuer_keys};
+use syra_login::jwt_proof_verifier::{IssuerConfig, Jwk, SubEncoding, Verifier, GOOGLE_ISSUER, RSA_CIRCUIT_ID};
+use syra_login::keys::{s_from_sub, Bp, IvkBundle};
+use syra_login::proof::proof_to_base64_compressed;
+use syra_login::test_circuit::TestCircuit;
+
+const SUB: &str = "113048723091228773641";
+const KID: &str = "bench-kid";
+
+struct Fixture {
+    bp: Bp,
+    isk: Fr,
+    s: Fr,
+    ivk: IvkBundle,
+}
+
+fn fixture() -> Fixture {
+    let mut rng = StdRng::seed_from_u64(0);
+    let bp = Bp::default();
+    let keys = sample_issuer_keys::<Bls12_381, _>(bp.g1, bp.g2, &mut rng);
+    let ivk = IvkBundle {
+        version: 1,
+        bp: bp.clone(),
+        ivk_hat: keys.ivk_hat,
+        W: keys.W,
+        W_hat: keys.W_hat,
+        issuer_id: "bench-issuer".into(),
+    };
+    Fixture { bp, isk: keys.isk, s: Fr::rand(&mut rng), ivk }
+}
+
+fn issuance(c: &mut Criterion) {
+    let f = fixture();
+    let mut group = c.benchmark_group("issuance");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("s_from_sub", |b| b.iter(|| s_from_sub(black_box(SUB))));
+
+    // generate_user_key's derivation: one inversion, one G1 and one G2
+    // scalar multiplication.
+    group.bench_function("derive_user_key", |b| {
+        b.iter(|| derive_user_key::<Bls12_381>(f.bp.g1, f.bp.g2, black_box(f.isk), black_box(f.s)))
+    });
+    group.finish();
+}
+
+fn verification(c: &mut Criterion) {
+    let f = fixture();
+    let (usk, _) = derive_user_key::<Bls12_381>(f.bp.g1, f.bp.g2, f.isk, f.s).expect("s + isk != 0");
+    let mut group = c.benchmark_group("verification");
+    group.throughput(Throughput::Elements(1));
+
+    // Two pairings.
+    group.bench_function("verify_user_key", |b| {
+        b.iter(|| verify_user_key(&f.ivk, black_box(f.s), black_box(&usk)))
+    });
+    group.finish();
+}
+
+/// `Verifier::verify` end to end with the JWK already cached, so no request
+/// leaves the process: decoding the proof, building the public inputs from
+/// the modulus, and the Groth16 check.
+fn proof_verification(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().expect("runtime");
+    let circuit = TestCircuit::rsa();
+    let n = URL_SAFE_NO_PAD.encode([0xa5u8; 256]);
+    let proof = proof_to_base64_compressed(&circuit.prove_rsa(SUB, &n).expect("modulus fits"))
+        .expect("proof serializes");
+    let issuers = IssuerConfig::default();
+    let jwks_uri = issuers.jwks_uri(GOOGLE_ISSUER).expect("Google is configured").to_string();
+    let verifier = rt
+        .block_on(Verifier::new(issuers))
+        .and_then(|v| v.with_circuit(RSA_CIRCUIT_ID, &circuit.vk_json()))
+        .expect("valid vk");
+    rt.block_on(verifier.cache_jwks(&jwks_uri, vec![Jwk::rsa(KID, n, "AQAB")]));
+    let verify = || verifier.verify(GOOGLE_ISSUER, SUB, KID, SubEncoding::Decimal, black_box(&proof), None);
+    assert!(rt.block_on(verify()).expect("verifies"), "bench proof must be valid");
+
+    let mut group = c.benchmark_group("proof");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("verify_cached_jwk", |b| b.iter(|| rt.block_on(verify())));
+    group.finish();
+}
+
+fn encoding(c: &mut Criterion) {
+    let f = fixture();
+    let bytes = f.ivk.to_bytes().expect("serializable");
+    let mut group = c.benchmark_group("ivk_bundle");
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function("to_bytes", |b| b.iter(|| black_box(&f.ivk).to_bytes()));
+    group.bench_function("to_bytes_uncompressed", |b| {
+        b.iter(|| black_box(&f.ivk).to_bytes_with(Compress::No))
+    });
+    // Decoding checks subgroup membership of every point, so it dominates.
+    group.bench_function("from_bytes", |b| b.iter(|| IvkBundle::from_bytes(black_box(&bytes))));
+    group.finish();
+}
+
+criterion_group!(benches, issuance, verification, proof_verification, encoding);
+criterion_main!(benches);
@@ -1,45 +1,114 @@
 use ark_bls12_381::{Fr, G1Affine};
 use ark_ec::{CurveGroup, Group};
 use ark_ff::{Field, UniformRand, Zero};
-use ark_serialize::CanonicalSerialize;
-use blake2::Blake2b512;
-use dock_crypto_utils::hashing_utils::affine_group_elem_from_try_and_incr;
+use ark_std::rand::{rngs::{OsRng, StdRng}, RngCore, SeedableRng};
+use anyhow::{anyhow, bail};
+use clap::{error::ErrorKind, CommandFactory, Parser, ValueEnum};
+use futures_util::{SinkExt, StreamExt};
 use reqwest::Client;
-use serde::Serialize;
 use std::collections::HashMap;
 use std::time::Duration;
-use hex;
+use syra_login::codec::{fr_to_hex, g1_to_hex};
+use syra_login::constants::G1_GENERATOR_TAG;
+use syra_login::dkg_recovery::lagrange_interpolate_at_zero;
+use syra_login::dkg_wire::{DkgPointMessage, DkgWsMessage};
+use syra_login::keys::g1_from_tag;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, http::header::AUTHORIZATION, Message};
 
-#[derive(Serialize)]
-struct DkgPointMessage {
+/// SyRA DKG coordinator: deals Shamir shares of a fresh issuer secret to `n`
+/// issuers.
+#[derive(Parser, Debug)]
+#[command(name = "dkg")]
+struct Args {
+    /// Total number of issuers (n).
+    #[arg(long, default_value_t = 5)]
+    parties: usize,
+
+    /// Number of shares needed to reconstruct the secret (t).
+    #[arg(long, default_value_t = 3)]
+    threshold: usize,
+
+    /// Session id sent with every share.
+    #[arg(long, default_value = "syra-session-001")]
     sid: String,
-    A: String,
-    f_i: String,
-    Ai_all: Vec<String>,
+
+    /// Issuer base URL; repeat once per party, in party-index order.
+    #[arg(long = "peer", required = true)]
+    peers: Vec<String>,
+
+    /// Bearer token the issuers expect on `/admin/*`.
+    #[arg(long, env = "SYRA_ADMIN_TOKEN", hide_env_values = true)]
+    admin_token: String,
+
+    /// Tag hashed to the generator `g`; must match the issuers' `--g1-tag`.
+    #[arg(long, env = "SYRA_G1_TAG", default_value = G1_GENERATOR_TAG)]
+    g1_tag: String,
+
+    /// How shares reach the issuers: one `POST /admin/receive_dkg` each, or
+    /// the `/admin/dkg_ws` channel, which waits for every issuer's ack and
+    /// answers complaints.
+    #[arg(long, value_enum, default_value_t = Transport::Http)]
+    transport: Transport,
+
+    /// Sample from a seeded PRNG instead of the OS RNG. Makes the issuer
+    /// secret predictable: tests only.
+    #[arg(long)]
+    deterministic_seed: Option<u64>,
+}
+
+impl Args {
+    fn validate(&self) -> Result<(), String> {
+        if self.threshold == 0 || self.threshold > self.parties {
+            return Err(format!(
+                "--threshold must be between 1 and --parties ({}), got {}",
+                self.parties, self.threshold
+            ));
+        }
+        if self.peers.len() != self.parties {
+            return Err(format!(
+                "expected one --peer per party ({}), got {}",
+                self.parties,
+                self.peers.len()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Transport {
+    Http,
+    Ws,
 }
 
 
 /// # Workflow
-/// 1. Derive generator `g ∈ G1` via try-and-increment.  
-/// 2. Sample secret `α ∈ Fr` and compute public `A = g^α`.  
+/// 0. Parse `--parties`, `--threshold`, `--sid`, `--peer` and `--admin-token` flags; exit if
+///    `t > n` or the number of peers differs from `n`.
+/// 1. Derive generator `g ∈ G1` from `--g1-tag` via try-and-increment (the issuers' `g1`).  
+/// 2. Sample secret `α ∈ Fr` (OS RNG unless `--deterministic-seed`) and compute public `A = g^α`.  
 /// 3. Build random polynomial `f(x)` of degree `t−1` with `f(0)=α`.  
 /// 4. For each i in 1..=n:  
 ///    - Evaluate share `f_i = f(i)`.  
 ///    - Compute commitment `A_i = g^{f_i}`.  
-/// 5. Serialize and hex-encode `A`, each `f_i`, and the list of all `A_i`.  
-/// 6. For each peer URL, construct a `DkgPointMessage { sid, A, f_i, Ai_all }`  
-///    and send it via `POST /admin/receive_dkg`.  
-/// 7. Log success or failure for each peer, sleeping 100 ms between requests.  
-/// 8. Print completion confirmation when done.
+///    Then check that `f_1..f_t` interpolate back to `α` (`dkg_recovery`).  
+/// 5. Compute the Feldman coefficient commitments `C_j = g^{coeffs[j]}`.  
+/// 6. Serialize and hex-encode `A`, each `f_i`, the list of all `A_i` and all `C_j`.  
+/// 7. For each peer URL, construct a `DkgPointMessage { sid, i, n, A, f_i, Ai_all, C_all }`  
+///    and send it with `Authorization: Bearer <admin token>`: via `POST /admin/receive_dkg`
+///    (`--transport http`), or over `/admin/dkg_ws` (`--transport ws`), waiting for the
+///    issuer's ack and justifying the share once if it complains.  
+/// 8. Log success or failure for each peer (sleeping 100 ms between HTTP requests).  
+/// 9. Print completion confirmation when done.
 ///
 /// # Pseudocode
 /// ```text
-/// // Setup parameters
-/// n ← 5; t ← 3; sid ← "syra-session-001"
-/// peer_urls ← ["http://127.0.0.1:9000"]
+/// // Setup parameters (from the command line)
+/// n ← --parties; t ← --threshold; sid ← --sid
+/// peer_urls ← [--peer …]
 ///
 /// // Generator in G1
-/// g ← hash_to_G1("syra-generator")
+/// g ← hash_to_G1(--g1-tag)   // the issuers' g1, "syra-generator-1" by default
 ///
 /// // Sample secret and compute public A
 /// α ← random_Fr()
@@ -55,21 +124,33 @@ struct DkgPointMessage {
 ///     A_i ← g^f_i
 ///     store f_i in alpha_i_map[i]
 ///     append A_i to Ai_list
+/// assert lagrange_interpolate_at_zero([(1, f_1) … (t, f_t)], t) == α
+///
+/// // Feldman commitments to the coefficients
+/// C_list ← [g^c for c in coeffs]
 ///
 /// // Hex-encode values
 /// A_hex ← hex_encode(A)
 /// Ai_all_hex ← [hex_encode(A_i) for A_i in Ai_list]
+/// C_all_hex ← [hex_encode(C_j) for C_j in C_list]
 ///
 /// // Broadcast to peers
 /// for (index, url) in peer_urls:
 ///     f_i_hex ← hex_encode(alpha_i_map[index+1])
-///     msg ← { sid, A: A_hex, f_i: f_i_hex, Ai_all: Ai_all_hex }
-///     res ← HTTP_POST(url + "/admin/receive_dkg", json=msg)
-///     if res.status is success:
-///         log("✓ Sent DKG point to Issuer {} (200 OK)", index+1)
+///     msg ← { sid, i: index+1, n, A: A_hex, f_i: f_i_hex, Ai_all: Ai_all_hex, C_all: C_all_hex }
+///     if transport == http:
+///         res ← HTTP_POST(url + "/admin/receive_dkg", json=msg, bearer=admin_token)
+///         if res.status is success:
+///             log("✓ Sent DKG point to Issuer {} (200 OK)", index+1)
+///         else:
+///             log("⚠️ Issuer {} responded: {}", index+1, res.status)
+///         sleep(100 ms)
 ///     else:
-///         log("⚠️ Issuer {} responded: {}", index+1, res.status)
-///     sleep(100 ms)
+///         ws ← WS_CONNECT(url + "/admin/dkg_ws", bearer=admin_token)
+///         ws.send(share(msg))
+///         if ws.recv() is complaint:
+///             ws.send(justify(sid, index+1, f_i_hex))
+///         log(ack ? "✓ acknowledged" : "❌ complaint stands")
 ///
 /// log("✔ DKG complete and distributed to all issuers.")
 /// ```
@@ -78,27 +159,30 @@ struct DkgPointMessage {
 /// Returns an error if any cryptographic operation, serialization, or HTTP request fails.
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let n = 5;             // Total parties
-    let t = 3;             // Threshold
-    let sid = "syra-session-001".to_string();
+    let args = Args::parse();
+    if let Err(msg) = args.validate() {
+        Args::command().error(ErrorKind::ValueValidation, msg).exit();
+    }
 
-    // Peer URLs
-    let peer_urls = vec![
-        "http://127.0.0.1:9000",
-    ];
+    let n = args.parties;     // Total parties
+    let t = args.threshold;   // Threshold
+    let sid = args.sid;
+    let peer_urls = args.peers;
 
-    // Generator g ∈ G1 via try-and-increment
-    let g = affine_group_elem_from_try_and_incr::<G1Affine, Blake2b512>(b"syra-generator");
+    // Generator g ∈ G1 via try-and-increment; the same g1 the issuers use
+    let g = g1_from_tag(args.g1_tag.as_bytes());
 
-    let mut rng = ark_std::test_rng();
+    if let Some(seed) = args.deterministic_seed {
+        println!("⚠️ Using deterministic seed {seed}; do NOT use these keys in production");
+    }
+    let mut rng = dealer_rng(args.deterministic_seed);
 
     // Sample α ∈ Z_p and compute A = g^α
-    let alpha = Fr::rand(&mut rng);
-    let A = (g * alpha).into_affine();
+    let (alpha, A) = sample_secret(g, &mut *rng);
 
     // Build degree-(t‑1) polynomial f with f(0) = α
     let mut coeffs = vec![alpha];
-    coeffs.extend((1..t).map(|_| Fr::rand(&mut rng)));
+    coeffs.extend((1..t).map(|_| Fr::rand(&mut *rng)));
 
     // Evaluate at i = 1..n
     let mut alpha_i_map = HashMap::new();
@@ -114,48 +198,154 @@ async fn main() -> anyhow::Result<()> {
         Ai_list.push((g * f_i).into_affine());
     }
 
-    // Helper to serialize & hex‑encode any CanonicalSerialize type
-    fn to_hex<T: CanonicalSerialize>(t: &T) -> String {
-        let mut buf = Vec::new();
-        t.serialize_compressed(&mut buf).unwrap();
-        hex::encode(buf)
-    }
-
-    let A_hex = to_hex(&A);
-    let Ai_all_hex = Ai_list.iter().map(to_hex).collect::<Vec<_>>();
+    // Any t shares must interpolate back to α before they leave this process
+    let first_t: Vec<(u64, Fr)> = (1..=t).map(|i| (i as u64, alpha_i_map[&i])).collect();
+    let recovered = lagrange_interpolate_at_zero(&first_t, t).expect("t distinct non-zero indices");
+    assert_eq!(recovered, alpha, "shares do not interpolate to the dealt secret");
 
-    let client = Client::new();
+    // Feldman commitments C_j = g^{coeffs[j]}, so C_0 = A
+    let C_list = coeffs.iter().map(|&c| (g * c).into_affine()).collect::<Vec<_>>();
 
-    // Broadcast to each peer
-    for (i, &url) in peer_urls.iter().enumerate() {
-        let idx = i + 1;
-        let f_i = alpha_i_map.get(&idx).unwrap();
-        let f_i_hex = to_hex(f_i);
+    let A_hex = g1_to_hex(&A);
+    let Ai_all_hex = Ai_list.iter().map(g1_to_hex).collect::<Vec<_>>();
+    let C_all_hex = C_list.iter().map(g1_to_hex).collect::<Vec<_>>();
 
-        let msg = DkgPointMessage {
+    let msgs = (1..=n).map(|idx| {
+        DkgPointMessage {
             sid: sid.clone(),
+            i: idx as u64,
+            n: n as u64,
             A: A_hex.clone(),
-            f_i: f_i_hex,
+            f_i: fr_to_hex(alpha_i_map.get(&idx).unwrap()),
             Ai_all: Ai_all_hex.clone(),
-        };
-
-        let res = client
-            .post(&format!("{}/admin/receive_dkg", url))
-            .json(&msg)
-            .send()
-            .await;
+            C_all: C_all_hex.clone(),
+        }
+    });
 
-        match res {
-            Ok(r) if r.status().is_success() => {
-                println!("✓ Sent DKG point to Issuer {} (200 OK)", idx)
+    match args.transport {
+        Transport::Http => {
+            let client = Client::new();
+            for (url, msg) in peer_urls.iter().zip(msgs) {
+                send_http(&client, url, &args.admin_token, &msg).await;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        }
+        Transport::Ws => {
+            for (url, msg) in peer_urls.iter().zip(msgs) {
+                let idx = msg.i;
+                match send_ws(url, &args.admin_token, msg).await {
+                    Ok(()) => println!("✓ Issuer {idx} acknowledged its DKG share"),
+                    Err(e) => println!("❌ Issuer {idx}: {e:#}"),
+                }
             }
-            Ok(r) => println!("⚠️ Issuer {} responded: {}", idx, r.status()),
-            Err(e) => println!("❌ Failed to contact Issuer {}: {}", idx, e),
         }
-
-        tokio::time::sleep(Duration::from_millis(100)).await;
     }
 
     println!("\n✔ DKG complete and distributed to all issuers.");
     Ok(())
 }
+
+/// The dealer's randomness: the OS RNG, or a `StdRng` seeded with `seed`.
+fn dealer_rng(seed: Option<u64>) -> Box<dyn RngCore> {
+    match seed {
+        Some(seed) => Box::new(StdRng::seed_from_u64(seed)),
+        None => Box::new(OsRng),
+    }
+}
+
+/// The issuer secret `α` and its public `A = g^α`.
+fn sample_secret(g: G1Affine, rng: &mut dyn RngCore) -> (Fr, G1Affine) {
+    let alpha = Fr::rand(rng);
+    (alpha, (g * alpha).into_affine())
+}
+
+/// Fire-and-forget delivery of one share via `POST /admin/receive_dkg`.
+async fn send_http(client: &Client, url: &str, admin_token: &str, msg: &DkgPointMessage) {
+    let res = client
+        .post(format!("{url}/admin/receive_dkg"))
+        .bearer_auth(admin_token)
+        .json(msg)
+        .send()
+        .await;
+
+    match res {
+        Ok(r) if r.status().is_success() => {
+            println!("✓ Sent DKG point to Issuer {} (200 OK)", msg.i)
+        }
+        Ok(r) => println!("⚠️ Issuer {} responded: {}", msg.i, r.status()),
+        Err(e) => println!("❌ Failed to contact Issuer {}: {}", msg.i, e),
+    }
+}
+
+/// Deliver one share over `/admin/dkg_ws`: send it, and if the issuer
+/// complains, justify it once by revealing `f_i` again.
+///
+/// # Errors
+/// Fails if the connection fails, the issuer answers out of protocol, or
+/// the complaint stands after the justification.
+async fn send_ws(url: &str, admin_token: &str, msg: DkgPointMessage) -> anyhow::Result<()> {
+    let ws_url = match url.split_once("://") {
+        Some(("https", rest)) => format!("wss://{rest}/admin/dkg_ws"),
+        Some(("http", rest)) => format!("ws://{rest}/admin/dkg_ws"),
+        _ => bail!("peer URL {url:?} is not http(s)"),
+    };
+    let mut req = ws_url.into_client_request()?;
+    req.headers_mut().insert(AUTHORIZATION, format!("Bearer {admin_token}").parse()?);
+    let (mut ws, _) = tokio_tungstenite::connect_async(req).await?;
+
+    let (sid, i, f_i) = (msg.sid.clone(), msg.i, msg.f_i.clone());
+
+    // round 1: the share
+    match exchange(&mut ws, &DkgWsMessage::Share(msg)).await? {
+        DkgWsMessage::Ack { .. } => return Ok(ws.close(None).await?),
+        DkgWsMessage::Complaint { reason, .. } => {
+            println!("⚠️ Issuer {i} complained ({reason}); justifying");
+        }
+        other => return Err(unexpected(other)),
+    }
+
+    // round 2: justify the complained-about share
+    match exchange(&mut ws, &DkgWsMessage::Justify { sid, i, f_i }).await? {
+        DkgWsMessage::Ack { .. } => Ok(ws.close(None).await?),
+        DkgWsMessage::Complaint { reason, .. } => bail!("complaint stands after justification: {reason}"),
+        other => Err(unexpected(other)),
+    }
+}
+
+type WsStream = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Send `frame` and wait for the issuer's next text frame.
+async fn exchange(ws: &mut WsStream, frame: &DkgWsMessage) -> anyhow::Result<DkgWsMessage> {
+    ws.send(Message::Text(serde_json::to_string(frame)?)).await?;
+    loop {
+        match ws.next().await.ok_or_else(|| anyhow!("issuer closed the connection"))?? {
+            Message::Text(text) => return Ok(serde_json::from_str(&text)?),
+            Message::Close(_) => bail!("issuer closed the connection"),
+            _ => continue,
+        }
+    }
+}
+
+fn unexpected(reply: DkgWsMessage) -> anyhow::Error {
+    match reply {
+        DkgWsMessage::Error { message } => anyhow!("issuer reported: {message}"),
+        _ => anyhow!("unexpected frame from issuer"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Without `--deterministic-seed` every run deals a fresh secret; with
+    /// one, runs repeat each other.
+    #[test]
+    fn only_a_seed_makes_runs_repeat() {
+        let g = g1_from_tag(G1_GENERATOR_TAG.as_bytes());
+        let run = |seed| sample_secret(g, &mut *dealer_rng(seed)).1;
+
+        assert_ne!(run(None), run(None));
+        assert_eq!(run(Some(7)), run(Some(7)));
+        assert_ne!(run(Some(7)), run(Some(8)));
+    }
+}
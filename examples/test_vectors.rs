@@ -0,0 +1,14 @@
+//! Prints cross-implementation test vectors as JSON:
+//!
+//! ```text
+//! cargo run --example test_vectors > test_vectors.json
+//! ```
+//!
+//! The checked-in `test_vectors.json` is this output; see
+//! [`syra_login::test_vectors`].
+
+fn main() -> anyhow::Result<()> {
+    let vectors = syra_login::test_vectors::vectors();
+    println!("{}", serde_json::to_string_pretty(&vectors)?);
+    Ok(())
+}
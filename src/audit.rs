@@ -0,0 +1,107 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use blake2::{Blake2b512, Digest};
+use serde::Serialize;
+use syra_login::constants::AUDIT_USER_TAG;
+
+use crate::lock::LockExt;
+
+/// The audit log could not record an issuance and is configured to fail
+/// closed.
+#[derive(thiserror::Error, Debug)]
+#[error("{0}")]
+pub struct AuditUnavailable(pub String);
+
+/// One line of the audit log. Never holds the raw `user_id` or key material.
+#[derive(Serialize)]
+struct AuditEntry<'a> {
+    /// Seconds since the epoch.
+    timestamp: u64,
+    /// `Blake2b("syra-audit-user" ∥ user_id)`, first 32 bytes, hex.
+    sub_hash: String,
+    /// The token's key id; `null` for `/admin/recover_user_key`.
+    kid: Option<&'a str>,
+    issuer_version: u32,
+}
+
+enum Sink {
+    Disabled,
+    File(Mutex<File>),
+    /// The file could not be opened at startup; the reason.
+    Unavailable(String),
+}
+
+/// Append-only JSON-lines record of every issued credential, for
+/// compliance. Each entry is synced to disk before the key is returned.
+pub struct AuditLog {
+    sink: Sink,
+    fail_closed: bool,
+}
+
+impl AuditLog {
+    /// Open `path` for appending, or a no-op log without one. A file that
+    /// cannot be opened is logged; with `fail_closed` every issuance is then
+    /// refused, otherwise issuance goes on unrecorded.
+    pub fn open(path: Option<&Path>, fail_closed: bool) -> Self {
+        let sink = match path {
+            None => Sink::Disabled,
+            Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+                Ok(file) => {
+                    tracing::info!(path = %path.display(), fail_closed, "audit log opened");
+                    Sink::File(Mutex::new(file))
+                }
+                Err(e) => {
+                    tracing::error!(path = %path.display(), error = %e, fail_closed, "failed to open audit log");
+                    Sink::Unavailable(format!("{}: {e}", path.display()))
+                }
+            },
+        };
+        AuditLog { sink, fail_closed }
+    }
+
+    /// Record that `user_id` was issued a key under issuer key `version`.
+    ///
+    /// # Errors
+    /// [`AuditUnavailable`] if the entry could not be written and the log
+    /// fails closed; the caller must not hand out the key.
+    pub fn record(&self, user_id: &str, kid: Option<&str>, version: u32) -> Result<(), AuditUnavailable> {
+        let result = match &self.sink {
+            Sink::Disabled => return Ok(()),
+            Sink::Unavailable(reason) => Err(reason.clone()),
+            Sink::File(file) => {
+                let entry = AuditEntry {
+                    timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+                    sub_hash: sub_hash(user_id),
+                    kid,
+                    issuer_version: version,
+                };
+                let mut line = serde_json::to_vec(&entry).expect("AuditEntry serializes");
+                line.push(b'\n');
+                let mut file = file.lock_or_recover();
+                file.write_all(&line).and_then(|()| file.sync_data()).map_err(|e| e.to_string())
+            }
+        };
+        match result {
+            Ok(()) => Ok(()),
+            Err(reason) if self.fail_closed => Err(AuditUnavailable(reason)),
+            Err(reason) => {
+                tracing::warn!(error = %reason, "issuance not recorded in the audit log");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// `Blake2b("syra-audit-user" ∥ user_id)`, first 32 bytes, hex: lets an
+/// auditor who knows a `user_id` find its entries.
+fn sub_hash(user_id: &str) -> String {
+    let digest = Blake2b512::new()
+        .chain_update(AUDIT_USER_TAG)
+        .chain_update(user_id.as_bytes())
+        .finalize();
+    hex::encode(&digest[..32])
+}
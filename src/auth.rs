@@ -0,0 +1,115 @@
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, Error,
+};
+use blake2::{Blake2b512, Digest};
+use subtle::ConstantTimeEq;
+
+use crate::error::ApiError;
+use crate::AppState;
+
+/// Middleware for `middleware::from_fn`, wrapped around the `/admin` scope:
+/// rejects requests whose bearer token does not match
+/// `AppState::admin_token`. It does not look at the path; what it guards is
+/// decided by where the router mounts it. The `401` is rendered here rather
+/// than returned as an `Err`, so it is a response to every outer layer.
+pub async fn require_admin_token(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let expected = &req
+        .app_data::<web::Data<AppState>>()
+        .expect("AppState is registered")
+        .admin_token;
+    let presented = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match presented {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => {}
+        _ => {
+            tracing::warn!(path = req.path(), "rejected unauthenticated admin request");
+            return Ok(req.error_response(ApiError::Unauthorized).map_into_right_body());
+        }
+    }
+
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
+
+/// Token comparison whose running time depends on neither where the inputs
+/// first differ nor their lengths: both sides are hashed to fixed-size
+/// digests first, which are then compared with `subtle`.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let (a, b) = (Blake2b512::digest(a), Blake2b512::digest(b));
+    a.as_slice().ct_eq(b.as_slice()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::http::{header, StatusCode};
+    use actix_web::test;
+
+    use crate::test_support::{admin_auth, test_app, test_state, MockVerifier};
+
+    /// `GET uri` with `auth` as the `Authorization` header; the status and
+    /// the body, `null` when it is not JSON.
+    async fn get(uri: &str, auth: Option<&str>) -> (StatusCode, serde_json::Value) {
+        let app = test_app!(test_state(Arc::new(MockVerifier::accepting())));
+        let mut req = test::TestRequest::get().uri(uri);
+        if let Some(auth) = auth {
+            req = req.insert_header((header::AUTHORIZATION, auth));
+        }
+        let resp = test::call_service(&app, req.to_request()).await;
+        let status = resp.status();
+        let body = test::read_body(resp).await;
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    #[actix_web::test]
+    async fn missing_header_is_unauthorized() {
+        let (status, body) = get("/admin/dkg_status", None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["code"], "UNAUTHORIZED");
+    }
+
+    #[actix_web::test]
+    async fn wrong_token_is_unauthorized() {
+        for auth in ["Bearer not-the-token", "Bearer ", "Basic dGVzdC1hZG1pbi10b2tlbg=="] {
+            let (status, body) = get("/admin/dkg_status", Some(auth)).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{auth}");
+            assert_eq!(body["code"], "UNAUTHORIZED");
+        }
+    }
+
+    #[actix_web::test]
+    async fn correct_token_reaches_the_handler() {
+        let (status, body) = get("/admin/dkg_status", Some(&admin_auth().1)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["complete"], false);
+    }
+
+    /// The router decodes `%61` to `a` before matching, so this still lands
+    /// on `/admin/dkg_status` and must still need the token.
+    #[actix_web::test]
+    async fn percent_encoded_prefix_is_still_guarded() {
+        for uri in ["/%61dmin/dkg_status", "/admin/%64kg_status"] {
+            let (status, _) = get(uri, None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{uri}");
+            let (status, _) = get(uri, Some(&admin_auth().1)).await;
+            assert_eq!(status, StatusCode::OK, "{uri}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn public_routes_need_no_token() {
+        let (status, _) = get("/healthz", None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
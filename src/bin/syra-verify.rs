@@ -0,0 +1,97 @@
+//! Offline credential check: `syra-verify --ivk <hex> --s <hex> --usk <hex>`.
+//!
+//! Prints `VALID` and exits 0, or `INVALID` and exits 1. Malformed input
+//! exits 2 with the parse error on stderr.
+
+use std::process::ExitCode;
+
+use anyhow::{Context, Result};
+use ark_bls12_381::Fr;
+use clap::{Args, Parser};
+use syra_login::credential::{verify_credential, verify_user_key, NonMembershipWitness};
+use syra_login::codec::{fr_from_hex, g1_from_hex};
+use syra_login::keys::{s_from_sub, IvkBundle};
+
+/// Verify a SyRA user credential against an issuer's IVK bundle.
+#[derive(Parser, Debug)]
+#[command(name = "syra-verify")]
+struct Cli {
+    /// IVK bundle hex, as returned in `ivk` by `/ivk` or `generate_user_key`.
+    #[arg(long)]
+    ivk: String,
+
+    /// The user's `s` (hex of the compressed field element).
+    #[arg(long, required_unless_present = "sub", conflicts_with = "sub")]
+    s: Option<String>,
+
+    /// Derive `s` from the token `sub` instead of passing it.
+    #[arg(long)]
+    sub: Option<String>,
+
+    /// The user key `usk` (hex of the compressed G1 point).
+    #[arg(long)]
+    usk: String,
+
+    #[command(flatten)]
+    revocation: Revocation,
+}
+
+/// From `/revocation_status`; without them revocation is not checked.
+#[derive(Args, Debug)]
+struct Revocation {
+    /// Revocation accumulator `V` (hex).
+    #[arg(long, requires_all = ["witness_c", "witness_d"])]
+    accumulator: Option<String>,
+
+    /// Non-membership witness `C` (hex).
+    #[arg(long, requires_all = ["accumulator", "witness_d"])]
+    witness_c: Option<String>,
+
+    /// Non-membership witness `d` (hex).
+    #[arg(long, requires_all = ["accumulator", "witness_c"])]
+    witness_d: Option<String>,
+}
+
+fn run(cli: &Cli) -> Result<bool> {
+    let ivk = IvkBundle::from_hex_string(&cli.ivk).context("--ivk")?;
+    let s: Fr = match (&cli.s, &cli.sub) {
+        (Some(s), _) => fr_from_hex(s).context("--s")?,
+        (None, Some(sub)) => s_from_sub(sub),
+        (None, None) => unreachable!("clap requires --s or --sub"),
+    };
+    let usk = g1_from_hex(&cli.usk).context("--usk")?;
+
+    let rev = &cli.revocation;
+    match (&rev.accumulator, &rev.witness_c, &rev.witness_d) {
+        (Some(acc), Some(c), Some(d)) => {
+            let accumulator = g1_from_hex(acc).context("--accumulator")?;
+            let witness = NonMembershipWitness {
+                C: g1_from_hex(c).context("--witness-c")?,
+                d: fr_from_hex(d).context("--witness-d")?,
+            };
+            Ok(verify_credential(&ivk, s, &usk, &accumulator, &witness))
+        }
+        _ => {
+            eprintln!("note: no --accumulator / --witness-*; revocation not checked");
+            Ok(verify_user_key(&ivk, s, &usk))
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(true) => {
+            println!("VALID");
+            ExitCode::SUCCESS
+        }
+        Ok(false) => {
+            println!("INVALID");
+            ExitCode::from(1)
+        }
+        Err(e) => {
+            eprintln!("error: {e:#}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use ark_bn254::Fr;
+use ark_std::rand::{rngs::OsRng, RngCore};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::error::ApiError;
+use crate::lock::LockExt;
+use crate::AppState;
+
+/// Why a submitted challenge was refused.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ChallengeError {
+    #[error("challenge is not a decimal u128")]
+    Malformed,
+    #[error("unknown or already used challenge")]
+    Unknown,
+    #[error("challenge expired")]
+    Expired,
+    #[error("a nonce from /challenge is required")]
+    Missing,
+}
+
+/// Server-issued, single-use nonces. A proof bound to one (through the
+/// circuit's `subStatement` input) can be redeemed once, within `ttl`.
+pub struct ChallengeStore {
+    ttl: Duration,
+    issued: Mutex<HashMap<u128, Instant>>,
+}
+
+impl ChallengeStore {
+    pub fn new(ttl: Duration) -> Self {
+        ChallengeStore { ttl, issued: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Draw a fresh 128-bit nonce, remembering when it expires. Expired
+    /// nonces are swept first so the map stays bounded by the issue rate.
+    pub fn issue(&self) -> u128 {
+        let now = Instant::now();
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        let nonce = u128::from_le_bytes(bytes);
+
+        let mut issued = self.issued.lock_or_recover();
+        issued.retain(|_, expires| *expires > now);
+        issued.insert(nonce, now + self.ttl);
+        nonce
+    }
+
+    /// Redeem `nonce`: it is forgotten whether or not it was still valid,
+    /// so a second attempt always fails with [`ChallengeError::Unknown`].
+    pub fn consume(&self, nonce: &str) -> Result<Fr, ChallengeError> {
+        let n = parse_nonce(nonce)?;
+        let expires = self.issued.lock_or_recover().remove(&n).ok_or(ChallengeError::Unknown)?;
+        if expires <= Instant::now() {
+            return Err(ChallengeError::Expired);
+        }
+        Ok(Fr::from(n))
+    }
+
+    /// Like [`consume`](Self::consume) but leaves the nonce redeemable; for
+    /// the `/verify_proof` dry run.
+    pub fn peek(&self, nonce: &str) -> Result<Fr, ChallengeError> {
+        let n = parse_nonce(nonce)?;
+        let expires = *self.issued.lock_or_recover().get(&n).ok_or(ChallengeError::Unknown)?;
+        if expires <= Instant::now() {
+            return Err(ChallengeError::Expired);
+        }
+        Ok(Fr::from(n))
+    }
+}
+
+fn parse_nonce(nonce: &str) -> Result<u128, ChallengeError> {
+    nonce.trim().parse().map_err(|_| ChallengeError::Malformed)
+}
+
+/// `GET /challenge` response.
+#[derive(Serialize, ToSchema)]
+pub struct ChallengeResponse {
+    /// decimal nonce; the proof's `subStatement` input must equal it
+    pub nonce: String,
+    /// seconds until the nonce expires
+    pub expires_in: u64,
+}
+
+/// Issues a single-use nonce for the next `generate_user_key` call.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state; the nonce is stored in `state.challenges`.
+/// * `http_req: HttpRequest` — used for the peer address the rate limiter keys on.
+///
+/// # Returns
+/// - `200 OK` with JSON `{ "nonce": "<decimal>", "expires_in": secs }`.
+/// - `429 Too Many Requests` with `Retry-After` once the caller's IP has used up its bucket.
+#[utoipa::path(
+    get,
+    path = "/challenge",
+    responses(
+        (status = 200, body = ChallengeResponse),
+        (status = 429, description = "rate limited; see Retry-After", body = crate::error::ErrorBody),
+    ),
+)]
+#[get("/challenge")]
+pub async fn challenge(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+) -> Result<HttpResponse, ApiError> {
+    // Each nonce is held until it expires; don't let one client fill the map.
+    if let Some(addr) = http_req.peer_addr() {
+        state.rate_limiter.check(addr.ip()).map_err(ApiError::RateLimited)?;
+    }
+
+    let nonce = state.challenges.issue();
+    Ok(HttpResponse::Ok().json(ChallengeResponse {
+        nonce: nonce.to_string(),
+        expires_in: state.challenges.ttl().as_secs(),
+    }))
+}
@@ -0,0 +1,198 @@
+//! Hex encodings of BLS12-381 scalars and points, as they appear on the
+//! wire (`/ivk`, `generate_user_key`, `/revocation_status`, DKG messages).
+//!
+//! Every encoding is arkworks' canonical one, hex-encoded without a prefix:
+//!
+//! | type       | byte order           | compressed | uncompressed |
+//! |------------|----------------------|------------|--------------|
+//! | `Fr`       | little-endian        | 32         | 32           |
+//! | `G1Affine` | big-endian `x` (+`y`)| 48         | 96           |
+//! | `G2Affine` | big-endian `x` (+`y`)| 96         | 192          |
+//!
+//! A compressed point is its `x` coordinate with the sign of `y` and the
+//! point-at-infinity flag in the top bits of the first byte. Decoding takes
+//! exactly these lengths and rejects non-canonical scalars (`≥ r`), points
+//! off the curve and points outside the prime-order subgroup.
+
+use anyhow::{ensure, Context, Result};
+use ark_bls12_381::{Fr, G1Affine, G2Affine};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+
+/// Bytes of an `Fr` in either form.
+pub const FR_BYTES: usize = 32;
+
+/// Bytes of a compressed / uncompressed `G1Affine`.
+pub const G1_BYTES: (usize, usize) = (48, 96);
+
+/// Bytes of a compressed / uncompressed `G2Affine`.
+pub const G2_BYTES: (usize, usize) = (96, 192);
+
+/// `s` as 32 little-endian bytes, hex.
+pub fn fr_to_hex(s: &Fr) -> String {
+    encode(s, Compress::Yes)
+}
+
+/// Inverse of [`fr_to_hex`].
+///
+/// # Errors
+/// Fails on invalid hex, a length other than 32 bytes, or an integer that is
+/// not below the scalar modulus.
+pub fn fr_from_hex(s: &str) -> Result<Fr> {
+    decode(s, Compress::Yes, FR_BYTES, "Fr")
+}
+
+/// `p` compressed (48 bytes), hex.
+pub fn g1_to_hex(p: &G1Affine) -> String {
+    g1_to_hex_with(p, Compress::Yes)
+}
+
+/// `p` in the chosen form (48 or 96 bytes), hex.
+pub fn g1_to_hex_with(p: &G1Affine, compress: Compress) -> String {
+    encode(p, compress)
+}
+
+/// Inverse of [`g1_to_hex`].
+///
+/// # Errors
+/// Fails on invalid hex, a length other than 48 bytes, or bytes that are
+/// not a point of the G1 subgroup.
+pub fn g1_from_hex(s: &str) -> Result<G1Affine> {
+    g1_from_hex_with(s, Compress::Yes)
+}
+
+/// Inverse of [`g1_to_hex_with`].
+pub fn g1_from_hex_with(s: &str, compress: Compress) -> Result<G1Affine> {
+    decode(s, compress, pick(G1_BYTES, compress), "G1")
+}
+
+/// `p` compressed (96 bytes), hex.
+pub fn g2_to_hex(p: &G2Affine) -> String {
+    g2_to_hex_with(p, Compress::Yes)
+}
+
+/// `p` in the chosen form (96 or 192 bytes), hex.
+pub fn g2_to_hex_with(p: &G2Affine, compress: Compress) -> String {
+    encode(p, compress)
+}
+
+/// Inverse of [`g2_to_hex`].
+///
+/// # Errors
+/// Fails on invalid hex, a length other than 96 bytes, or bytes that are
+/// not a point of the G2 subgroup.
+pub fn g2_from_hex(s: &str) -> Result<G2Affine> {
+    g2_from_hex_with(s, Compress::Yes)
+}
+
+/// Inverse of [`g2_to_hex_with`].
+pub fn g2_from_hex_with(s: &str, compress: Compress) -> Result<G2Affine> {
+    decode(s, compress, pick(G2_BYTES, compress), "G2")
+}
+
+fn pick((compressed, uncompressed): (usize, usize), compress: Compress) -> usize {
+    match compress {
+        Compress::Yes => compressed,
+        Compress::No => uncompressed,
+    }
+}
+
+fn encode<T: CanonicalSerialize>(t: &T, compress: Compress) -> String {
+    let mut buf = Vec::with_capacity(t.serialized_size(compress));
+    t.serialize_with_mode(&mut buf, compress)
+        .expect("serializing into a Vec cannot fail");
+    hex::encode(buf)
+}
+
+fn decode<T: CanonicalDeserialize>(s: &str, compress: Compress, len: usize, what: &str) -> Result<T> {
+    let bytes = hex::decode(s.trim()).context("invalid hex")?;
+    ensure!(bytes.len() == len, "{what} must be {len} bytes, got {}", bytes.len());
+    T::deserialize_with_mode(&bytes[..], compress, Validate::Yes).with_context(|| format!("invalid {what}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{g1, g2};
+    use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_ff::{BigInteger, One, PrimeField};
+    use ark_std::UniformRand;
+
+    use super::*;
+
+    /// A point on the curve of `P` but outside its prime-order subgroup.
+    fn outside_subgroup<P: SWCurveConfig>() -> Affine<P> {
+        (1u64..)
+            .filter_map(|x| Affine::<P>::get_point_from_x_unchecked(P::BaseField::from(x), true))
+            .find(|p| !p.is_in_correct_subgroup_assuming_on_curve())
+            .unwrap()
+    }
+
+    /// `p` with `y + 1`: not on the curve at all.
+    fn off_curve<P: SWCurveConfig>(p: Affine<P>) -> Affine<P> {
+        Affine::new_unchecked(p.x, p.y + P::BaseField::one())
+    }
+
+    #[test]
+    fn scalars_and_points_round_trip_at_their_documented_lengths() {
+        let mut rng = ark_std::test_rng();
+        for s in [Fr::from(0u64), -Fr::one(), Fr::rand(&mut rng)] {
+            let hex = fr_to_hex(&s);
+            assert_eq!(hex.len(), 2 * FR_BYTES);
+            assert_eq!(fr_from_hex(&hex).unwrap(), s);
+        }
+        assert_eq!(fr_to_hex(&Fr::from(1u64)), format!("01{}", "00".repeat(31)));
+
+        let g1_points = [G1Affine::zero(), (G1Affine::generator() * Fr::rand(&mut rng)).into_affine()];
+        let g2_points = [G2Affine::zero(), (G2Affine::generator() * Fr::rand(&mut rng)).into_affine()];
+        for compress in [Compress::Yes, Compress::No] {
+            for p in g1_points {
+                let hex = g1_to_hex_with(&p, compress);
+                assert_eq!(hex.len(), 2 * pick(G1_BYTES, compress));
+                assert_eq!(g1_from_hex_with(&hex, compress).unwrap(), p);
+            }
+            for p in g2_points {
+                let hex = g2_to_hex_with(&p, compress);
+                assert_eq!(hex.len(), 2 * pick(G2_BYTES, compress));
+                assert_eq!(g2_from_hex_with(&hex, compress).unwrap(), p);
+            }
+        }
+        assert_eq!(g1_from_hex(&format!(" {} ", g1_to_hex(&g1_points[1]))).unwrap(), g1_points[1]);
+        assert_eq!(g2_from_hex(&g2_to_hex(&g2_points[1])).unwrap(), g2_points[1]);
+    }
+
+    #[test]
+    fn bad_hex_and_wrong_lengths_are_rejected() {
+        let g1 = g1_to_hex(&G1Affine::generator());
+        for bad in ["zz", "0", &format!("0x{g1}"), &g1[1..]] {
+            assert_eq!(format!("{:#}", g1_from_hex(bad).unwrap_err()).split(':').next(), Some("invalid hex"), "{bad:?}");
+        }
+        assert_eq!(g1_from_hex(&g1[2..]).unwrap_err().to_string(), "G1 must be 48 bytes, got 47");
+        assert_eq!(g1_from_hex_with(&g1, Compress::No).unwrap_err().to_string(), "G1 must be 96 bytes, got 48");
+        let g2 = g2_to_hex(&G2Affine::generator());
+        assert_eq!(g2_from_hex(&format!("{g2}00")).unwrap_err().to_string(), "G2 must be 96 bytes, got 97");
+        assert_eq!(fr_from_hex("").unwrap_err().to_string(), "Fr must be 32 bytes, got 0");
+    }
+
+    #[test]
+    fn non_canonical_scalars_and_points_are_rejected() {
+        // r itself, little-endian, and the largest 32-byte integer.
+        let modulus = hex::encode(Fr::MODULUS.to_bytes_le());
+        for bad in [modulus, "ff".repeat(32)] {
+            assert_eq!(fr_from_hex(&bad).unwrap_err().to_string(), "invalid Fr");
+        }
+
+        // Compressed, a point is only its `x`, so only the uncompressed form
+        // can be off the curve.
+        let off_g1 = encode(&off_curve(G1Affine::generator()), Compress::No);
+        assert_eq!(g1_from_hex_with(&off_g1, Compress::No).unwrap_err().to_string(), "invalid G1");
+        let off_g2 = encode(&off_curve(G2Affine::generator()), Compress::No);
+        assert_eq!(g2_from_hex_with(&off_g2, Compress::No).unwrap_err().to_string(), "invalid G2");
+
+        for compress in [Compress::Yes, Compress::No] {
+            let hex = encode(&outside_subgroup::<g1::Config>(), compress);
+            assert_eq!(g1_from_hex_with(&hex, compress).unwrap_err().to_string(), "invalid G1");
+            let hex = encode(&outside_subgroup::<g2::Config>(), compress);
+            assert_eq!(g2_from_hex_with(&hex, compress).unwrap_err().to_string(), "invalid G2");
+        }
+    }
+}
@@ -0,0 +1,368 @@
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, ValueEnum};
+use serde::Deserialize;
+
+use syra_login::constants::{CHUNK_BITS, G1_GENERATOR_TAG, G2_GENERATOR_TAG};
+use syra_login::jwt_proof_verifier::{HttpConfig, IssuerConfig};
+use syra_login::keys::{Bp, SchemeParams};
+
+/// Runtime configuration; every flag can also be set through its env var.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "syra-login", about = "SyRA issuer service")]
+pub struct Config {
+    /// Address to listen on; use `0.0.0.0` inside containers.
+    #[arg(long = "bind", env = "BIND_ADDR", default_value = "127.0.0.1")]
+    pub bind_addr: IpAddr,
+
+    /// Port to listen on.
+    #[arg(long, env = "PORT", default_value_t = 9000)]
+    pub port: u16,
+
+    /// How long in-flight requests get to finish after SIGINT / SIGTERM
+    /// before their workers are stopped, in seconds.
+    #[arg(long, env = "SYRA_SHUTDOWN_TIMEOUT_SECS", default_value_t = 30)]
+    pub shutdown_timeout_secs: u64,
+
+    /// Compress responses (gzip / deflate / br / zstd) for clients that send
+    /// `Accept-Encoding`. Off by default: key responses carry secrets next to
+    /// client-chosen input, which compression can leak (BREACH).
+    #[arg(long, env = "SYRA_COMPRESS_RESPONSES")]
+    pub compress_responses: bool,
+
+    /// Largest JSON body `/admin/generate_user_key` and `/verify_proof`
+    /// accept, in bytes; longer bodies get `413` before any of it is parsed.
+    /// A proof, even as a SnarkJS object, is well under 2 KiB.
+    #[arg(long, env = "SYRA_MAX_PROOF_BYTES", default_value_t = 8192)]
+    pub max_proof_bytes: usize,
+
+    /// Origins allowed by CORS, comma-separated; `*` allows any origin
+    /// (development only).
+    #[arg(long = "allowed-origin", env = "ALLOWED_ORIGINS", value_delimiter = ',', value_parser = parse_origin, default_value = "http://localhost:8080")]
+    pub allowed_origins: Vec<String>,
+
+    /// Where issuer keys are persisted and reloaded from on startup.
+    #[arg(long, env = "ISSUER_KEYS_PATH", default_value = "issuer_keys.bin")]
+    pub issuer_keys_path: PathBuf,
+
+    /// Passphrase the issuer key file is encrypted with (Argon2id +
+    /// ChaCha20-Poly1305); the file is written in the clear when unset.
+    #[arg(long, env = "SYRA_KEYS_PASSPHRASE", hide_env_values = true)]
+    pub keys_passphrase: Option<String>,
+
+    /// Where key sets retired by `/admin/rotate_keys` are persisted and
+    /// reloaded from on startup; encrypted like the key file.
+    #[arg(long, env = "RETIRED_KEYS_PATH", default_value = "retired_issuer_keys.bin")]
+    pub retired_keys_path: PathBuf,
+
+    /// Where revoked user elements are persisted and reloaded from on startup.
+    #[arg(long, env = "REVOCATION_PATH", default_value = "revoked.bin")]
+    pub revocation_path: PathBuf,
+
+    /// This issuer's 1-based x-coordinate in the DKG.
+    #[arg(long, env = "SYRA_PARTY_INDEX", default_value_t = 1)]
+    pub party_index: u64,
+
+    /// Report not-ready on `/readyz`, and refuse to issue keys, until a DKG
+    /// share has been accepted.
+    #[arg(long, env = "SYRA_REQUIRE_DKG")]
+    pub require_dkg: bool,
+
+    /// Accepted OIDC provider as `<issuer_url>=<jwks_uri>`; repeatable.
+    /// Defaults to Google when none are given.
+    #[arg(long = "oidc-provider", env = "OIDC_PROVIDERS", value_delimiter = ',', value_parser = parse_provider)]
+    pub oidc_providers: Vec<(String, String)>,
+
+    /// RSA modulus size of a provider's keys as `<issuer_url>=<bits>`;
+    /// repeatable. Proofs for that issuer must use a circuit with exactly
+    /// `⌈bits / chunk_bits⌉` modulus limbs.
+    #[arg(long = "provider-modulus-bits", env = "SYRA_PROVIDER_MODULUS_BITS", value_delimiter = ',', value_parser = parse_modulus_bits)]
+    pub provider_modulus_bits: Vec<(String, usize)>,
+
+    /// SnarkJS verifying key of the RSA circuit; the embedded key when unset.
+    #[arg(long, env = "VK_PATH")]
+    pub vk_path: Option<PathBuf>,
+
+    /// SnarkJS verifying key of the ES256 circuit; enables P-256 JWKs.
+    #[arg(long, env = "EC_VK_PATH")]
+    pub ec_vk_path: Option<PathBuf>,
+
+    /// Additional verifying key as `<circuit_id>=<path>`; repeatable.
+    /// Requests select one with `circuit_id`.
+    #[arg(long = "circuit-vk", env = "SYRA_CIRCUIT_VKS", value_delimiter = ',', value_parser = parse_circuit_vk)]
+    pub circuit_vks: Vec<(String, PathBuf)>,
+
+    /// Bits per limb the circuits split the RSA modulus and EC coordinates
+    /// into; must match the Circom input generator.
+    #[arg(long, env = "SYRA_CHUNK_BITS", default_value_t = CHUNK_BITS)]
+    pub chunk_bits: usize,
+
+    /// Limit on connecting to a JWKS endpoint, in seconds.
+    #[arg(long, env = "SYRA_JWKS_CONNECT_TIMEOUT_SECS", default_value_t = 5)]
+    pub jwks_connect_timeout_secs: u64,
+
+    /// Limit on one JWKS request including its body, in seconds. Fetches are
+    /// tried up to three times before answering 503.
+    #[arg(long, env = "SYRA_JWKS_TIMEOUT_SECS", default_value_t = 10)]
+    pub jwks_timeout_secs: u64,
+
+    /// Idle keep-alive connections kept per JWKS host.
+    #[arg(long, env = "SYRA_JWKS_POOL_SIZE", default_value_t = 8)]
+    pub jwks_pool_size: usize,
+
+    /// JWKS fetches in flight at once; more wait for a free slot. `0` is
+    /// taken as 1.
+    #[arg(long, env = "SYRA_JWKS_MAX_CONCURRENT_FETCHES", default_value_t = 4)]
+    pub jwks_max_concurrent_fetches: usize,
+
+    /// How long fetched JWK sets are cached, in seconds, overriding the
+    /// provider's `Cache-Control: max-age`. Unset follows `max-age` (five
+    /// minutes without one).
+    #[arg(long, env = "SYRA_JWKS_CACHE_TTL_SECS")]
+    pub jwks_cache_ttl_secs: Option<u64>,
+
+    /// Clock skew tolerated when checking JWT `exp` / `nbf` / `iat`, in seconds.
+    #[arg(long, env = "CLOCK_SKEW_SECS", default_value_t = 60)]
+    pub clock_skew_secs: u64,
+
+    /// Requests per second each client IP may make to
+    /// `/admin/generate_user_key` and `/verify_proof`; `0` disables rate limiting.
+    #[arg(long, env = "SYRA_RATE_LIMIT", default_value_t = 5)]
+    pub rate_limit: u32,
+
+    /// How long a nonce from `/challenge` stays redeemable, in seconds.
+    #[arg(long, env = "SYRA_CHALLENGE_TTL_SECS", default_value_t = 120)]
+    pub challenge_ttl_secs: u64,
+
+    /// Refuse `generate_user_key` requests that carry no nonce. Needs a
+    /// circuit whose `subStatement` input is free for the nonce.
+    #[arg(long, env = "SYRA_REQUIRE_CHALLENGE")]
+    pub require_challenge: bool,
+
+    /// Accept bare RS256 ID tokens on `/admin/generate_user_key_jwt`,
+    /// verified without a proof. The server then sees the subject.
+    #[arg(long, env = "SYRA_ALLOW_NATIVE_JWT", requires = "allowed_audiences")]
+    pub allow_native_jwt: bool,
+
+    /// OAuth client id those ID tokens must be issued to (`aud`, and `azp`
+    /// when present); repeatable. Required with `--allow-native-jwt`.
+    #[arg(long = "allowed-audience", env = "SYRA_ALLOWED_AUDIENCES", value_delimiter = ',', value_parser = parse_audience)]
+    pub allowed_audiences: Vec<String>,
+
+    /// Tag hashed to the G1 generator `g1`; must match the DKG coordinator's
+    /// `--g1-tag`.
+    #[arg(long, env = "SYRA_G1_TAG", default_value = G1_GENERATOR_TAG)]
+    pub g1_tag: String,
+
+    /// Tag hashed to the G2 generator `g2`.
+    #[arg(long, env = "SYRA_G2_TAG", default_value = G2_GENERATOR_TAG)]
+    pub g2_tag: String,
+
+    /// JSON file `{ "g1": "<hex>", "g2": "<hex>" }` with compressed
+    /// generators from a published trusted setup; replaces `--g1-tag` /
+    /// `--g2-tag` for issuer keys and DKG shares.
+    #[arg(long, env = "GENERATORS_PATH")]
+    pub generators_path: Option<PathBuf>,
+
+    /// Identifier published in the IVK bundle and on `/ivk`, so verifiers
+    /// in a federation can tell issuers apart.
+    #[arg(long, env = "SYRA_ISSUER_ID", default_value = "")]
+    pub issuer_id: String,
+
+    /// Append a JSON line `{ timestamp, sub_hash, kid, issuer_version }` here
+    /// for every issued key; no audit log when unset.
+    #[arg(long, env = "SYRA_AUDIT_LOG_PATH")]
+    pub audit_log_path: Option<PathBuf>,
+
+    /// Keep issuing when the audit log cannot be written, instead of
+    /// refusing with 503.
+    #[arg(long, env = "SYRA_AUDIT_FAIL_OPEN")]
+    pub audit_fail_open: bool,
+
+    /// `pretty` for human-readable lines, `json` for one JSON object per
+    /// event, for log aggregators.
+    #[arg(long, env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Pretty)]
+    pub log_format: LogFormat,
+
+    /// Bearer token required on every `/admin/*` route; must not be blank,
+    /// or `Authorization: Bearer ` alone would pass.
+    #[arg(long, env = "SYRA_ADMIN_TOKEN", hide_env_values = true, value_parser = parse_admin_token)]
+    pub admin_token: String,
+}
+
+/// Contents of `--generators-path`.
+#[derive(Deserialize)]
+struct GeneratorsFile {
+    g1: String,
+    g2: String,
+}
+
+/// How log events are written to stdout.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Pretty,
+    /// Event fields, and those of the enclosing request span under `span`,
+    /// as JSON keys.
+    Json,
+}
+
+impl Config {
+    pub fn listen_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.bind_addr, self.port)
+    }
+
+    /// The scheme parameters new issuer keys and DKG shares use. The tags are
+    /// empty when `--generators-path` is set, as they no longer describe the
+    /// generators.
+    pub fn scheme(&self) -> SchemeParams {
+        match self.generators_path {
+            Some(_) => SchemeParams::new("", ""),
+            None => SchemeParams::new(&self.g1_tag, &self.g2_tag),
+        }
+    }
+
+    /// `(g1, g2)`: loaded from `--generators-path` if set, otherwise hashed
+    /// from the tags.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or parsed, or either point is not a
+    /// non-identity element of its prime-order subgroup.
+    pub fn generators(&self) -> Result<Bp> {
+        let Some(path) = &self.generators_path else {
+            return Ok(self.scheme().generators());
+        };
+        let json = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let file: GeneratorsFile = serde_json::from_str(&json).with_context(|| format!("parsing {}", path.display()))?;
+        Bp::from_hex(&file.g1, &file.g2).with_context(|| path.display().to_string())
+    }
+
+    pub fn http_config(&self) -> HttpConfig {
+        HttpConfig {
+            connect_timeout: Duration::from_secs(self.jwks_connect_timeout_secs),
+            timeout: Duration::from_secs(self.jwks_timeout_secs),
+            pool_max_idle_per_host: self.jwks_pool_size,
+            max_concurrent_fetches: self.jwks_max_concurrent_fetches,
+        }
+    }
+
+    pub fn issuer_config(&self) -> IssuerConfig {
+        let mut config = IssuerConfig::default();
+        if !self.oidc_providers.is_empty() {
+            config.providers = self.oidc_providers.clone();
+        }
+        config.modulus_bits = self.provider_modulus_bits.iter().cloned().collect();
+        config.audiences = self.allowed_audiences.clone();
+        config
+    }
+}
+
+fn parse_origin(s: &str) -> Result<String> {
+    let origin = s.trim().trim_end_matches('/');
+    if origin.is_empty() {
+        return Err(anyhow!("empty origin"));
+    }
+    Ok(origin.to_string())
+}
+
+fn parse_admin_token(s: &str) -> Result<String> {
+    // Never echo the value: it is a secret even when malformed.
+    if s.trim().is_empty() {
+        return Err(anyhow!("admin token is empty"));
+    }
+    Ok(s.to_string())
+}
+
+fn parse_audience(s: &str) -> Result<String> {
+    let aud = s.trim();
+    if aud.is_empty() {
+        return Err(anyhow!("empty audience"));
+    }
+    Ok(aud.to_string())
+}
+
+fn parse_provider(s: &str) -> Result<(String, String)> {
+    let (iss, jwks_uri) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected <issuer_url>=<jwks_uri>, got {s:?}"))?;
+    Ok((iss.trim().to_string(), jwks_uri.trim().to_string()))
+}
+
+fn parse_modulus_bits(s: &str) -> Result<(String, usize)> {
+    let (iss, bits) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected <issuer_url>=<bits>, got {s:?}"))?;
+    let bits: usize = bits.trim().parse().with_context(|| format!("modulus bits in {s:?}"))?;
+    if bits == 0 {
+        return Err(anyhow!("modulus bits must be positive in {s:?}"));
+    }
+    Ok((iss.trim().to_string(), bits))
+}
+
+fn parse_circuit_vk(s: &str) -> Result<(String, PathBuf)> {
+    let (id, path) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected <circuit_id>=<path>, got {s:?}"))?;
+    let id = id.trim();
+    if id.is_empty() {
+        return Err(anyhow!("empty circuit id in {s:?}"));
+    }
+    Ok((id.to_string(), PathBuf::from(path.trim())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_admin_token_is_rejected_at_startup() {
+        for token in ["", "   "] {
+            let err = Config::try_parse_from(["syra-login", "--admin-token", token]).unwrap_err();
+            assert!(err.to_string().contains("admin token is empty"), "{err}");
+        }
+        let config = Config::try_parse_from(["syra-login", "--admin-token", "s3cret"]).unwrap();
+        assert_eq!(config.admin_token, "s3cret");
+    }
+
+    #[test]
+    fn native_jwt_needs_an_audience() {
+        let args = ["syra-login", "--admin-token", "t", "--allow-native-jwt"];
+        assert!(Config::try_parse_from(args).is_err());
+        let config = Config::try_parse_from(args.into_iter().chain(["--allowed-audience", "a.apps, b.apps"])).unwrap();
+        assert_eq!(config.issuer_config().audiences, ["a.apps", "b.apps"]);
+    }
+
+    #[test]
+    fn allowed_origins_are_split_and_trimmed() {
+        let parse = |origins: &str| Config::try_parse_from(["syra-login", "--admin-token", "t", "--allowed-origin", origins]);
+        let config = parse(" https://app.example.com/ ,http://localhost:3000,  *").unwrap();
+        assert_eq!(config.allowed_origins, ["https://app.example.com", "http://localhost:3000", "*"]);
+
+        let config = Config::try_parse_from(["syra-login", "--admin-token", "t"]).unwrap();
+        assert_eq!(config.allowed_origins, ["http://localhost:8080"]);
+
+        for origins in ["", "https://a.example.com,,https://b.example.com", " / "] {
+            let err = parse(origins).unwrap_err();
+            assert!(err.to_string().contains("empty origin"), "{origins:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn malformed_bind_addresses_are_rejected() {
+        let parse = |args: &[&str]| Config::try_parse_from(["syra-login", "--admin-token", "t"].iter().chain(args));
+        for bind in ["", "localhost", "0.0.0.0:9000", "256.0.0.1", "1.2.3", "::1::2"] {
+            let err = parse(&["--bind", bind]).unwrap_err();
+            assert!(err.to_string().contains("invalid value"), "{bind:?}: {err}");
+        }
+        for port in ["-1", "65536", "http"] {
+            assert!(parse(&["--port", port]).is_err(), "{port:?}");
+        }
+
+        let config = parse(&["--bind", "::", "--port", "8443"]).unwrap();
+        assert_eq!(config.listen_addr().to_string(), "[::]:8443");
+        let config = parse(&[]).unwrap();
+        assert_eq!(config.listen_addr().to_string(), "127.0.0.1:9000");
+    }
+}
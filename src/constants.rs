@@ -0,0 +1,110 @@
+//! Domain-separation tags and scheme constants, in one place so the issuer
+//! service, the DKG coordinator (`dkg/`) and verifiers cannot drift apart.
+//! The issuer and the coordinator must hash the same tag to the same
+//! generator (both go through [`g1_from_tag`](crate::keys::g1_from_tag)),
+//! otherwise the DKG's `A = g^α` is not a power of the issuer's `g1`.
+//!
+//! Tags are hashed with data appended and no length prefix, so they must be
+//! prefix-free; that is checked at compile time below.
+//!
+//! Deployments that run several issuer groups side by side can override the
+//! tags (`--g1-tag` / `--g2-tag`); every member of a group, and its
+//! coordinator, must use the same values.
+
+/// Default tag hashed to the G1 generator `g1` (also the DKG's `g`).
+pub const G1_GENERATOR_TAG: &str = "syra-generator-1";
+
+/// Default tag hashed to the G2 generator `g2`.
+pub const G2_GENERATOR_TAG: &str = "syra-generator-2";
+
+/// Tag prefixed to the NFC `sub` before hashing it to `s`.
+pub const USER_ID_TAG: &str = "syra-user-id";
+
+/// Tag prefixed to a signing context before hashing it to the G2 point a
+/// pseudonym is taken against.
+pub const PSEUDONYM_TAG: &str = "syra-pseudonym";
+
+/// Tag of the Fiat–Shamir challenge in a SyRA signature.
+pub const SIGNATURE_TAG: &str = "syra-signature";
+
+/// Tag of the audit log's `sub_hash`, `Blake2b(tag ∥ user_id)`.
+pub const AUDIT_USER_TAG: &str = "syra-audit-user";
+
+/// Tag of the hashed `user` field in log lines, `Blake2b(tag ∥ user_id)`.
+pub const LOG_USER_TAG: &str = "syra-log-user";
+
+/// Default limb width, in bits, of the RSA modulus / EC coordinates as
+/// public inputs; matches the Circom input generator of the embedded
+/// circuit. Other circuits set theirs with `--chunk-bits`.
+pub const CHUNK_BITS: usize = 121;
+
+/// Widest limb that is always below the BN254 scalar modulus, so no limb is
+/// reduced when it becomes a public input.
+pub const MAX_CHUNK_BITS: usize = 253;
+
+const TAGS: [&str; 7] = [
+    G1_GENERATOR_TAG,
+    G2_GENERATOR_TAG,
+    USER_ID_TAG,
+    PSEUDONYM_TAG,
+    SIGNATURE_TAG,
+    AUDIT_USER_TAG,
+    LOG_USER_TAG,
+];
+
+const _: () = assert!(prefix_free(&TAGS), "domain-separation tags must be prefix-free");
+const _: () = assert!(CHUNK_BITS <= MAX_CHUNK_BITS);
+
+/// No tag is a prefix of another (or equal to it).
+const fn prefix_free(tags: &[&str]) -> bool {
+    let mut i = 0;
+    while i < tags.len() {
+        let mut j = 0;
+        while j < tags.len() {
+            if i != j && starts_with(tags[j].as_bytes(), tags[i].as_bytes()) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn starts_with(s: &[u8], prefix: &[u8]) -> bool {
+    if prefix.len() > s.len() {
+        return false;
+    }
+    let mut k = 0;
+    while k < prefix.len() {
+        if s[k] != prefix[k] {
+            return false;
+        }
+        k += 1;
+    }
+    true
+}
+
+/// Pairing curve of the issuer and user keys.
+pub const CURVE: &str = "BLS12-381";
+
+/// Digest that tags (and signing contexts) are hashed to group elements
+/// with, by try-and-increment. Blake2b-512 by default, as in the reference
+/// SyRA implementation (docknetwork/crypto); the `sha256-generators` feature
+/// switches to SHA-256 for interop with stacks that lack Blake2b. The choice
+/// changes `g1` and `g2`, so issuers, the DKG coordinator and verifiers must
+/// all be built with the same one. `s` is hashed with Blake2b-512 either way.
+#[cfg(not(feature = "sha256-generators"))]
+pub type GroupHash = blake2::Blake2b512;
+
+/// See the Blake2b-512 default.
+#[cfg(feature = "sha256-generators")]
+pub type GroupHash = sha2::Sha256;
+
+/// How tags are hashed to generators, published as `scheme.hash`.
+#[cfg(not(feature = "sha256-generators"))]
+pub const HASH_METHOD: &str = "blake2b512-try-and-increment";
+
+/// How tags are hashed to generators, published as `scheme.hash`.
+#[cfg(feature = "sha256-generators")]
+pub const HASH_METHOD: &str = "sha256-try-and-increment";
@@ -0,0 +1,74 @@
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::pairing::Pairing;
+use ark_ec::CurveGroup;
+use ark_ff::Zero;
+
+use crate::curve;
+use crate::keys::IvkBundle;
+
+/// Proof that `s` is not in the accumulator: with `f(x) = Π_i (s_i + x)`,
+/// `d = f(-s) = Π_i (s_i - s)` is non-zero and `C = g1^{(f(isk) - d)/(s + isk)}`.
+#[derive(Clone, Debug)]
+pub struct NonMembershipWitness {
+    pub C: G1Affine,
+    pub d: Fr,
+}
+
+/// Check a user key against the published issuer key: with
+/// `usk = g1^{1/(s+isk)}` and `ivk_hat = g2^isk`,
+///
+/// ```text
+/// e(usk, ivk_hat · g2^s) == e(g1, g2)
+/// ```
+///
+/// `s` is the user's field element, i.e. [`s_from_sub`](crate::keys::s_from_sub)
+/// of their `sub`. Keys derived from a DKG share only verify against the
+/// bundle that issuer published for its share (`ivk_hat = g2^{isk_i}`), not
+/// against its local `ivk_hat`.
+///
+/// `accumulator` and `witness` come from `/revocation_status`; the credential
+/// is rejected unless `witness` shows `s` has not been revoked.
+pub fn verify_credential(
+    ivk: &IvkBundle,
+    s: Fr,
+    usk: &G1Affine,
+    accumulator: &G1Affine,
+    witness: &NonMembershipWitness,
+) -> bool {
+    verify_user_key(ivk, s, usk) && verify_non_membership(ivk, accumulator, s, witness)
+}
+
+/// The pairing half of [`verify_credential`]: `usk` was issued for `s` under
+/// `ivk`, without any revocation check.
+pub fn verify_user_key(ivk: &IvkBundle, s: Fr, usk: &G1Affine) -> bool {
+    curve::verify_user_key::<Bls12_381>(ivk.bp.g1, ivk.bp.g2, ivk.ivk_hat, s, *usk)
+}
+
+/// [`verify_user_key`] for `usk`, plus `e(usk, g2) == e(g1, usk_hat)` so
+/// `usk_hat` is known to share its exponent `1/(s+isk)`. The bundle only
+/// publishes `ivk_hat` in G2, so `usk_hat` is verified through `usk` rather
+/// than by a pairing of its own.
+pub fn verify_credential_pair(ivk: &IvkBundle, s: Fr, usk: &G1Affine, usk_hat: &G2Affine) -> bool {
+    verify_user_key(ivk, s, usk)
+        && curve::same_exponent::<Bls12_381>(ivk.bp.g1, ivk.bp.g2, *usk, *usk_hat)
+}
+
+/// Check that `s` is not accumulated in `accumulator`:
+///
+/// ```text
+/// d != 0  and  e(C, ivk_hat · g2^s) == e(V · g1^{-d}, g2)
+/// ```
+pub fn verify_non_membership(
+    ivk: &IvkBundle,
+    accumulator: &G1Affine,
+    s: Fr,
+    witness: &NonMembershipWitness,
+) -> bool {
+    if witness.d.is_zero() {
+        return false;
+    }
+    let ivk_s = (G2Projective::from(ivk.ivk_hat) + ivk.bp.g2 * s).into_affine();
+    let v_minus_d = (G1Projective::from(*accumulator) - ivk.bp.g1 * witness.d).into_affine();
+
+    Bls12_381::pairing(witness.C, ivk_s) == Bls12_381::pairing(v_minus_d, ivk.bp.g2)
+}
@@ -0,0 +1,159 @@
+//! The SyRA key algebra over any pairing engine.
+//!
+//! Issuance and verification only need a pairing-friendly curve with hashing
+//! to both groups, so the arithmetic is written against [`SyraCurve`] rather
+//! than BLS12-381 directly. [`Bls12_381`] is the one implementation today;
+//! the service, [`IvkBundle`](crate::keys::IvkBundle) and its wire format
+//! are still BLS12-381 only.
+
+use ark_bls12_381::Bls12_381;
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{BigInteger, PrimeField, UniformRand};
+use ark_std::rand::Rng;
+use dock_crypto_utils::hashing_utils::affine_group_elem_from_try_and_incr;
+use zeroize::Zeroizing;
+
+use crate::constants::{GroupHash, CURVE};
+
+/// A pairing engine SyRA keys can live on.
+pub trait SyraCurve: Pairing {
+    /// Published as `curve` in [`SchemeParams`](crate::keys::SchemeParams).
+    const NAME: &'static str;
+
+    /// `(g1, g2)` hashed from the two tags by try-and-increment.
+    fn generators(g1_tag: &[u8], g2_tag: &[u8]) -> (Self::G1Affine, Self::G2Affine) {
+        (
+            affine_group_elem_from_try_and_incr::<Self::G1Affine, GroupHash>(g1_tag),
+            affine_group_elem_from_try_and_incr::<Self::G2Affine, GroupHash>(g2_tag),
+        )
+    }
+}
+
+impl SyraCurve for Bls12_381 {
+    const NAME: &'static str = CURVE;
+}
+
+/// Freshly sampled issuer keys, before they are versioned and stored.
+pub struct IssuerKeyMaterial<E: SyraCurve> {
+    pub isk: E::ScalarField,
+    pub ivk_hat: E::G2Affine,
+    pub W: E::G1Affine,
+    pub W_hat: E::G2Affine,
+}
+
+/// Sample `isk` and publish `ivk_hat = g2^isk`, with `W = g1^r`,
+/// `W_hat = g2^r` for one shared `r`, so that `e(W, g2) == e(g1, W_hat)`.
+pub fn sample_issuer_keys<E: SyraCurve, R: Rng>(
+    g1: E::G1Affine,
+    g2: E::G2Affine,
+    rng: &mut R,
+) -> IssuerKeyMaterial<E> {
+    let isk = E::ScalarField::rand(rng);
+    let r = Zeroizing::new(E::ScalarField::rand(rng));
+    IssuerKeyMaterial {
+        isk,
+        ivk_hat: (g2 * isk).into_affine(),
+        W: (g1 * *r).into_affine(),
+        W_hat: (g2 * *r).into_affine(),
+    }
+}
+
+/// `usk = g1^{1/(s+isk)}`, `usk_hat = g2^{1/(s+isk)}`, or `None` when
+/// `s + isk = 0`. The sum and its inverse are wiped before returning.
+pub fn derive_user_key<E: SyraCurve>(
+    g1: E::G1Affine,
+    g2: E::G2Affine,
+    isk: E::ScalarField,
+    s: E::ScalarField,
+) -> Option<(E::G1Affine, E::G2Affine)> {
+    let sum = Zeroizing::new(s + isk);
+    let inv = Zeroizing::new(ct_inverse(&*sum)?);
+    Some(((g1 * *inv).into_affine(), (g2 * *inv).into_affine()))
+}
+
+/// `e(usk, ivk_hat · g2^s) == e(g1, g2)`.
+pub fn verify_user_key<E: SyraCurve>(
+    g1: E::G1Affine,
+    g2: E::G2Affine,
+    ivk_hat: E::G2Affine,
+    s: E::ScalarField,
+    usk: E::G1Affine,
+) -> bool {
+    let ivk_s = (ivk_hat.into_group() + g2 * s).into_affine();
+    E::pairing(usk, ivk_s) == E::pairing(g1, g2)
+}
+
+/// `e(usk, g2) == e(g1, usk_hat)`: both halves of a user key carry the same
+/// exponent. `usk_hat` cannot be checked against `ivk_hat` on its own (that
+/// would need `g1^isk`), so this is how it is tied to a verified `usk`.
+pub fn same_exponent<E: SyraCurve>(
+    g1: E::G1Affine,
+    g2: E::G2Affine,
+    usk: E::G1Affine,
+    usk_hat: E::G2Affine,
+) -> bool {
+    E::pairing(usk, g2) == E::pairing(g1, usk_hat)
+}
+
+/// `x⁻¹ = x^{p-2}` (Fermat), or `None` for zero. The exponent is public, so
+/// the square-and-multiply sequence is the same for every `x`, unlike
+/// `Field::inverse`, whose extended-Euclid loop depends on the (secret) input.
+pub fn ct_inverse<F: PrimeField>(x: &F) -> Option<F> {
+    if x.is_zero() {
+        return None;
+    }
+    let mut exp = F::MODULUS;
+    exp.sub_with_borrow(&F::BigInt::from(2u64));
+    Some(x.pow(exp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Fr, G1Projective, G2Projective};
+    use ark_ff::{Field, One, Zero};
+
+    use crate::constants::{G1_GENERATOR_TAG, G2_GENERATOR_TAG};
+    use crate::keys::{s_from_sub, Bp};
+
+    /// The generic path, instantiated at `Bls12_381`, against the same
+    /// arithmetic written out on the concrete types.
+    #[test]
+    fn bls12_381_through_the_trait_matches_the_concrete_arithmetic() {
+        let (g1, g2) = Bls12_381::generators(G1_GENERATOR_TAG.as_bytes(), G2_GENERATOR_TAG.as_bytes());
+        let bp = Bp::default();
+        assert_eq!((g1, g2), (bp.g1, bp.g2));
+        assert_eq!(<Bls12_381 as SyraCurve>::NAME, CURVE);
+
+        let keys = sample_issuer_keys::<Bls12_381, _>(g1, g2, &mut ark_std::test_rng());
+        assert_eq!(keys.ivk_hat, (G2Projective::from(g2) * keys.isk).into_affine());
+        assert_eq!(Bls12_381::pairing(keys.W, g2), Bls12_381::pairing(g1, keys.W_hat));
+
+        let s = s_from_sub("alice");
+        let (usk, usk_hat) = derive_user_key::<Bls12_381>(g1, g2, keys.isk, s).unwrap();
+        let inv = (s + keys.isk).inverse().unwrap();
+        assert_eq!(usk, (G1Projective::from(g1) * inv).into_affine());
+        assert_eq!(usk_hat, (G2Projective::from(g2) * inv).into_affine());
+
+        assert!(verify_user_key::<Bls12_381>(g1, g2, keys.ivk_hat, s, usk));
+        assert!(!verify_user_key::<Bls12_381>(g1, g2, keys.ivk_hat, s_from_sub("bob"), usk));
+        assert!(same_exponent::<Bls12_381>(g1, g2, usk, usk_hat));
+    }
+
+    #[test]
+    fn ct_inverse_agrees_with_field_inverse() {
+        let mut rng = ark_std::test_rng();
+        for x in [Fr::one(), -Fr::one(), Fr::rand(&mut rng), Fr::rand(&mut rng)] {
+            assert_eq!(ct_inverse(&x), x.inverse());
+        }
+        assert_eq!(ct_inverse(&Fr::zero()), None);
+    }
+
+    #[test]
+    fn derive_user_key_refuses_s_equal_to_minus_isk() {
+        let bp = Bp::default();
+        let isk = Fr::from(42u64);
+        assert!(derive_user_key::<Bls12_381>(bp.g1, bp.g2, isk, -isk).is_none());
+    }
+}
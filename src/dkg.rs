@@ -0,0 +1,531 @@
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use serde::Serialize;
+use utoipa::ToSchema;
+use actix_ws::{Message, MessageStream, Session};
+use anyhow::{anyhow, ensure, Context, Result};
+use ark_bls12_381::{Fr, G1Affine, G1Projective};
+use ark_ec::CurveGroup;
+use ark_ff::{One, Zero};
+use zeroize::Zeroize;
+
+use crate::error::ApiError;
+use crate::lock::LockExt;
+use crate::AppState;
+
+use syra_login::codec::{fr_from_hex, g1_from_hex};
+pub use syra_login::dkg_wire::DkgPointMessage;
+use syra_login::dkg_wire::DkgWsMessage;
+
+/// The share this issuer accepted from the coordinator: the sum of one
+/// share per dealer of session `sid` so far, with the dealers' commitments
+/// summed alike.
+pub struct DkgShare {
+    pub sid: String,
+    pub isk_i: Fr,
+    pub A: G1Affine,
+    pub Ai_all: Vec<G1Affine>,
+    pub C_all: Vec<G1Affine>,
+    /// Each summed dealer's own `A`, so a re-sent share is not counted twice.
+    pub dealers: Vec<G1Affine>,
+}
+
+impl DkgShare {
+    /// `n`, the number of parties (and dealers) of the session.
+    pub fn parties(&self) -> usize {
+        self.Ai_all.len()
+    }
+
+    /// `t = |C_all|`, the issuers needed to use the joint key.
+    pub fn threshold(&self) -> usize {
+        self.C_all.len()
+    }
+
+    /// Whether every one of the `n` dealers' shares is summed in, so
+    /// `isk_i` is this issuer's final share of the joint key.
+    pub fn is_complete(&self) -> bool {
+        self.dealers.len() == self.parties()
+    }
+
+    /// Sum `other`, a share of the same session from a dealer not counted
+    /// yet, into this one.
+    ///
+    /// # Errors
+    /// Fails, leaving `self` unchanged, if `other` is from another session,
+    /// has a different `n` or `t`, or comes from a dealer already counted.
+    fn absorb(&mut self, other: &DkgShare) -> Result<()> {
+        ensure!(other.sid == self.sid, "share is in session {:?}, not {:?}", other.sid, self.sid);
+        ensure!(other.parties() == self.parties(), "share has n = {}, not {}", other.parties(), self.parties());
+        ensure!(other.threshold() == self.threshold(), "share has t = {}, not {}", other.threshold(), self.threshold());
+        ensure!(
+            other.dealers.iter().all(|d| !self.dealers.contains(d)),
+            "dealer's share is already counted"
+        );
+
+        let sum = |ours: &[G1Affine], theirs: &[G1Affine]| -> Vec<G1Affine> {
+            let summed: Vec<G1Projective> = ours.iter().zip(theirs).map(|(a, b)| *a + b).collect();
+            G1Projective::normalize_batch(&summed)
+        };
+        self.isk_i += other.isk_i;
+        self.A = (self.A + other.A).into_affine();
+        self.Ai_all = sum(&self.Ai_all, &other.Ai_all);
+        self.C_all = sum(&self.C_all, &other.C_all);
+        self.dealers.extend_from_slice(&other.dealers);
+        Ok(())
+    }
+}
+
+/// Add a verified share to `state.dkg`: it starts over for a new session,
+/// is ignored if its dealers are all counted already (a retried send), and
+/// is summed into the held share otherwise.
+///
+/// # Errors
+/// As [`DkgShare::absorb`], for a share that disagrees with the held one
+/// on `n` or `t`, or mixes counted and new dealers.
+fn store(state: &AppState, share: DkgShare) -> Result<()> {
+    let mut dkg = state.dkg.lock_or_recover();
+    match &mut *dkg {
+        Some(held) if held.sid == share.sid => {
+            if share.dealers.iter().all(|d| held.dealers.contains(d)) {
+                return Ok(());
+            }
+            held.absorb(&share)
+        }
+        _ => {
+            *dkg = Some(share);
+            Ok(())
+        }
+    }
+}
+
+impl Drop for DkgShare {
+    fn drop(&mut self) {
+        self.isk_i.zeroize();
+    }
+}
+
+/// Feldman VSS check for the share of party `i`:
+///
+/// ```text
+/// g^{f_i} == Π_j C_j^{i^j}
+/// ```
+pub fn verify_feldman_share(g: G1Affine, f_i: Fr, i: u64, commitments: &[G1Affine]) -> bool {
+    let x = Fr::from(i);
+    let mut x_pow = Fr::one();
+    let mut rhs = G1Projective::zero();
+    for &c in commitments {
+        rhs += c * x_pow;
+        x_pow *= x;
+    }
+    g * f_i == rhs
+}
+
+/// Decode a `DkgPointMessage` and check that it was dealt to this issuer
+/// (`msg.i == party_index`), that `g^{f_i} == Ai_all[i-1]` and that `f_i`
+/// is consistent with the dealer's Feldman commitments at `x = i`, all over
+/// the generator `g` (this issuer's `g1`).
+///
+/// The lists are sized before anything is decoded or indexed: `Ai_all`
+/// must hold exactly `n` commitments and `C_all` between 1 and `n` (the
+/// threshold `t ≤ n`). Every element must decode to a valid compressed G1
+/// point in the prime-order subgroup.
+pub fn verify_share(msg: &DkgPointMessage, party_index: u64, g: G1Affine) -> Result<DkgShare> {
+    ensure!(msg.i >= 1, "party index must be ≥ 1");
+    ensure!(msg.i <= msg.n, "party index {} exceeds n = {}", msg.i, msg.n);
+    ensure!(
+        msg.i == party_index,
+        "share was dealt to party {} but this issuer is party {party_index}",
+        msg.i
+    );
+    let party_index = msg.i;
+    ensure!(
+        msg.Ai_all.len() as u64 == msg.n,
+        "Ai_all has {} commitments, expected one per party (n = {})",
+        msg.Ai_all.len(),
+        msg.n
+    );
+    ensure!(
+        !msg.C_all.is_empty() && msg.C_all.len() as u64 <= msg.n,
+        "C_all has {} commitments, expected the threshold t with 1 ≤ t ≤ n = {}",
+        msg.C_all.len(),
+        msg.n
+    );
+
+    let A = g1_from_hex(&msg.A).context("A")?;
+    let f_i = fr_from_hex(&msg.f_i).context("f_i")?;
+    let Ai_all = msg
+        .Ai_all
+        .iter()
+        .enumerate()
+        .map(|(j, s)| g1_from_hex(s).with_context(|| format!("Ai_all[{j}]")))
+        .collect::<Result<Vec<_>>>()?;
+    let C_all = msg
+        .C_all
+        .iter()
+        .enumerate()
+        .map(|(j, s)| g1_from_hex(s).with_context(|| format!("C_all[{j}]")))
+        .collect::<Result<Vec<_>>>()?;
+    ensure!(C_all.first() == Some(&A), "C_all[0] must equal A");
+
+    let committed = Ai_all
+        .get((party_index - 1) as usize)
+        .ok_or_else(|| anyhow!("no commitment for party {party_index}"))?;
+
+    let expected = (G1Projective::from(g) * f_i).into_affine();
+    ensure!(&expected == committed, "g^f_i does not match Ai_all[{}]", party_index - 1);
+    ensure!(
+        verify_feldman_share(g, f_i, party_index, &C_all),
+        "f_i is inconsistent with the Feldman commitments"
+    );
+
+    Ok(DkgShare {
+        sid: msg.sid.clone(),
+        isk_i: f_i,
+        A,
+        Ai_all,
+        C_all,
+        dealers: vec![A],
+    })
+}
+
+/// Verify one message per dealer and combine them into this issuer's share
+/// of the joint secret: `isk_i = Σ_d f_i^{(d)}`, with `A`, `Ai_all` and
+/// `C_all` the element-wise sums of the dealers' commitments.
+///
+/// Every message must pass [`verify_share`] and agree with the first on
+/// `sid`, `n` and `t`, and no dealer may appear twice; one bad message
+/// fails the whole batch.
+pub fn verify_share_batch(msgs: &[DkgPointMessage], party_index: u64, g: G1Affine) -> Result<DkgShare> {
+    ensure!(!msgs.is_empty(), "empty DKG batch");
+
+    let mut shares = msgs
+        .iter()
+        .enumerate()
+        .map(|(d, msg)| verify_share(msg, party_index, g).with_context(|| format!("dealer {d}")));
+    let mut combined = shares.next().expect("batch is not empty")?;
+    for (d, share) in shares.enumerate() {
+        combined.absorb(&share?).with_context(|| format!("dealer {}", d + 1))?;
+    }
+    Ok(combined)
+}
+
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state; the accepted share is added to `state.dkg`.
+/// * `msg: web::Json<DkgPointMessage>` — hex-encoded `A`, `f_i`, `Ai_all` and `C_all` from the coordinator.
+///
+/// # Returns
+/// - `200 OK` once the share matches its commitment and has been stored.
+/// - `400 Bad Request` if `Ai_all` does not hold exactly `n` commitments or
+///   `C_all` more than `n`, if any element fails to decode to a G1 point,
+///   if `g^{f_i} != Ai_all[i-1]`, or if the Feldman check
+///   `g^{f_i} == Π C_j^{i^j}` fails.
+#[utoipa::path(
+    post,
+    path = "/admin/receive_dkg",
+    request_body = DkgPointMessage,
+    responses(
+        (status = 200, description = "share verified and stored"),
+        (status = 400, description = "share or commitments fail verification", body = crate::error::ErrorBody),
+        (status = 401, description = "invalid admin token", body = crate::error::ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[post("/receive_dkg")]
+pub async fn receive_dkg(
+    state: web::Data<AppState>,
+    msg: web::Json<DkgPointMessage>,
+) -> Result<HttpResponse, ApiError> {
+    let share = verify_share(&msg, state.party_index, state.generators.g1).and_then(|share| {
+        let sid = share.sid.clone();
+        store(&state, share).map(|()| sid)
+    });
+    let sid = share.map_err(|e| {
+        tracing::warn!(error = %format!("{e:#}"), "rejected DKG share");
+        ApiError::InvalidDkgShare(format!("{e:#}"))
+    })?;
+    tracing::info!(%sid, "accepted DKG share");
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Multi-dealer variant of [`receive_dkg`]: one message per dealer, all
+/// verified before this issuer's combined share is stored.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state; the combined share is added to `state.dkg`.
+/// * `msgs: web::Json<Vec<DkgPointMessage>>` — one message per dealer, same `sid`.
+///
+/// # Returns
+/// - `200 OK` once every share verified and `isk_i = Σ f_i` has been stored.
+/// - `400 Bad Request` if the batch is empty, any share fails the checks of
+///   [`receive_dkg`], or the dealers disagree on `sid`, `n` or `t`. Nothing is
+///   stored in that case.
+#[utoipa::path(
+    post,
+    path = "/admin/receive_dkg_batch",
+    request_body = Vec<DkgPointMessage>,
+    responses(
+        (status = 200, description = "every share verified; their sum is stored"),
+        (status = 400, description = "empty batch, or a share fails verification; nothing stored", body = crate::error::ErrorBody),
+        (status = 401, description = "invalid admin token", body = crate::error::ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[post("/receive_dkg_batch")]
+pub async fn receive_dkg_batch(
+    state: web::Data<AppState>,
+    msgs: web::Json<Vec<DkgPointMessage>>,
+) -> Result<HttpResponse, ApiError> {
+    let share = verify_share_batch(&msgs, state.party_index, state.generators.g1).and_then(|share| {
+        let sid = share.sid.clone();
+        store(&state, share).map(|()| sid)
+    });
+    let sid = share.map_err(|e| {
+        tracing::warn!(error = %format!("{e:#}"), "rejected DKG batch");
+        ApiError::InvalidDkgShare(format!("{e:#}"))
+    })?;
+    tracing::info!(%sid, dealers = msgs.len(), "accepted DKG batch");
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Body of `GET /admin/dkg_status`.
+#[derive(Serialize, ToSchema)]
+pub struct DkgStatusResponse {
+    /// Session of the stored share; `null` before one was accepted.
+    pub sid: Option<String>,
+    /// Distinct dealers whose shares of this session are summed into the
+    /// stored share; 0 before any.
+    pub shares_received: usize,
+    /// `n`: dealers the session needs shares from; `null` before a share
+    /// was accepted.
+    pub parties: Option<usize>,
+    /// `t = |C_all|`: issuers needed to use the joint key; `null` before a
+    /// share was accepted.
+    pub threshold: Option<usize>,
+    /// Whether all `n` dealers' shares are in, so this issuer holds its
+    /// final share and issues under it.
+    pub complete: bool,
+}
+
+/// Reports how far DKG got on this issuer.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state; read from `state.dkg`.
+///
+/// # Returns
+/// - `200 OK` with `DkgStatusResponse { sid, shares_received, parties, threshold, complete }`,
+///   also before any share arrived (`complete: false`).
+#[utoipa::path(
+    get,
+    path = "/admin/dkg_status",
+    responses(
+        (status = 200, body = DkgStatusResponse),
+        (status = 401, description = "invalid admin token", body = crate::error::ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[get("/dkg_status")]
+pub async fn dkg_status(state: web::Data<AppState>) -> HttpResponse {
+    let dkg = state.dkg.lock_or_recover();
+    let status = match &*dkg {
+        Some(share) => DkgStatusResponse {
+            sid: Some(share.sid.clone()),
+            shares_received: share.dealers.len(),
+            parties: Some(share.parties()),
+            threshold: Some(share.threshold()),
+            complete: share.is_complete(),
+        },
+        None => DkgStatusResponse {
+            sid: None,
+            shares_received: 0,
+            parties: None,
+            threshold: None,
+            complete: false,
+        },
+    };
+    HttpResponse::Ok().json(status)
+}
+
+/// Streaming variant of [`receive_dkg`] that can answer back: the
+/// coordinator sends the share, this issuer acks it or complains, and a
+/// complaint can be resolved by the dealer justifying the share (see
+/// [`DkgWsMessage`]). Behind the admin bearer token like every `/admin/*`
+/// route.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state; an acked share is added to `state.dkg`.
+/// * `req: HttpRequest`, `body: web::Payload` — the WebSocket upgrade.
+///
+/// # Returns
+/// - `101 Switching Protocols`; every later outcome is a frame on the socket.
+/// - `400 Bad Request` if the request is not a WebSocket upgrade.
+#[utoipa::path(
+    get,
+    path = "/admin/dkg_ws",
+    responses(
+        (status = 101, description = "WebSocket upgraded; frames are JSON `DkgWsMessage`s"),
+        (status = 400, description = "not a WebSocket upgrade"),
+        (status = 401, description = "invalid admin token", body = crate::error::ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[get("/dkg_ws")]
+pub async fn dkg_ws(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    body: web::Payload,
+) -> actix_web::Result<HttpResponse> {
+    let (resp, session, stream) = actix_ws::handle(&req, body)?;
+    actix_web::rt::spawn(dkg_session(state, session, stream));
+    Ok(resp)
+}
+
+/// One coordinator connection: answer every text frame until either side
+/// closes. Pings are answered; other frames are ignored.
+async fn dkg_session(state: web::Data<AppState>, mut session: Session, mut stream: MessageStream) {
+    // The round-1 share, kept so a justification is checked against the
+    // commitments that came with it.
+    let mut dealt: Option<DkgPointMessage> = None;
+    while let Some(Ok(frame)) = stream.recv().await {
+        let text = match frame {
+            Message::Text(text) => text,
+            Message::Ping(bytes) => {
+                if session.pong(&bytes).await.is_err() {
+                    return;
+                }
+                continue;
+            }
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let reply = match serde_json::from_str::<DkgWsMessage>(&text) {
+            Ok(msg) => answer(&state, &mut dealt, msg),
+            Err(e) => DkgWsMessage::Error { message: format!("malformed frame: {e}") },
+        };
+        let reply = serde_json::to_string(&reply).expect("DkgWsMessage serializes");
+        if session.text(reply).await.is_err() {
+            return;
+        }
+    }
+    let _ = session.close(None).await;
+}
+
+/// The reply to one coordinator frame.
+fn answer(state: &AppState, dealt: &mut Option<DkgPointMessage>, msg: DkgWsMessage) -> DkgWsMessage {
+    match msg {
+        DkgWsMessage::Share(msg) => accept(state, dealt.insert(msg)),
+        DkgWsMessage::Justify { sid, i, f_i } => match dealt {
+            Some(msg) if msg.sid == sid && msg.i == i => {
+                msg.f_i = f_i;
+                accept(state, msg)
+            }
+            _ => DkgWsMessage::Error {
+                message: format!("justify for {sid:?} / party {i} without a matching share"),
+            },
+        },
+        _ => DkgWsMessage::Error { message: "issuers only accept share and justify".into() },
+    }
+}
+
+/// Verify `msg` as [`receive_dkg`] does; store the share and ack it, or
+/// complain with the reason.
+fn accept(state: &AppState, msg: &DkgPointMessage) -> DkgWsMessage {
+    match verify_share(msg, state.party_index, state.generators.g1).and_then(|share| store(state, share)) {
+        Ok(()) => {
+            tracing::info!(sid = %msg.sid, "accepted DKG share over WebSocket");
+            DkgWsMessage::Ack { sid: msg.sid.clone(), i: msg.i }
+        }
+        Err(e) => {
+            tracing::warn!(error = %format!("{e:#}"), "complaint against DKG share");
+            DkgWsMessage::Complaint { sid: msg.sid.clone(), i: msg.i, reason: format!("{e:#}") }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_ec::AffineRepr;
+    use syra_login::codec::{fr_to_hex, g1_to_hex};
+    use syra_login::keys::Bp;
+
+    use super::*;
+    use crate::test_support::deal;
+
+    fn g() -> G1Affine {
+        Bp::default().g1
+    }
+
+    fn error_of(msg: &DkgPointMessage) -> String {
+        batch_error(std::slice::from_ref(msg)).trim_start_matches("dealer 0: ").to_string()
+    }
+
+    fn batch_error(msgs: &[DkgPointMessage]) -> String {
+        match verify_share_batch(msgs, 1, g()) {
+            Ok(_) => panic!("tampered share was accepted"),
+            Err(err) => format!("{err:#}"),
+        }
+    }
+
+    #[test]
+    fn a_dealt_share_verifies_and_tampering_with_it_does_not() {
+        let msg = deal("sid", 3, 2, g()).remove(0);
+        let share = verify_share(&msg, 1, g()).unwrap();
+        assert_eq!(fr_to_hex(&share.isk_i), msg.f_i);
+        assert_eq!(share.dealers, vec![share.A]);
+
+        let mut tampered = msg.clone();
+        tampered.f_i = fr_to_hex(&(share.isk_i + Fr::one()));
+        assert_eq!(error_of(&tampered), "g^f_i does not match Ai_all[0]");
+
+        // A share and its Ai_all entry swapped together still fail Feldman.
+        let forged = share.isk_i + Fr::one();
+        tampered.Ai_all[0] = g1_to_hex(&(g() * forged).into_affine());
+        assert_eq!(error_of(&tampered), "f_i is inconsistent with the Feldman commitments");
+
+        let mut tampered = msg.clone();
+        tampered.C_all[1] = g1_to_hex(&(share.C_all[1] + g()).into_affine());
+        assert_eq!(error_of(&tampered), "f_i is inconsistent with the Feldman commitments");
+
+        let mut tampered = msg.clone();
+        tampered.A = g1_to_hex(&G1Affine::generator());
+        assert_eq!(error_of(&tampered), "C_all[0] must equal A");
+
+        let mut tampered = msg.clone();
+        tampered.Ai_all[2] = "00".into();
+        assert!(error_of(&tampered).starts_with("Ai_all[2]"), "{}", error_of(&tampered));
+
+        let other_party = deal("sid", 3, 2, g()).remove(1);
+        assert_eq!(error_of(&other_party), "share was dealt to party 2 but this issuer is party 1");
+    }
+
+    #[test]
+    fn a_batch_sums_its_dealers_unless_one_is_bad() {
+        let msgs: Vec<DkgPointMessage> = (0..3).map(|_| deal("sid", 3, 2, g()).remove(0)).collect();
+        let shares: Vec<DkgShare> = msgs.iter().map(|m| verify_share(m, 1, g()).unwrap()).collect();
+
+        let combined = verify_share_batch(&msgs, 1, g()).unwrap();
+        assert_eq!(combined.isk_i, shares.iter().map(|s| s.isk_i).sum::<Fr>());
+        assert_eq!(combined.A, shares.iter().map(|s| s.A).sum::<G1Projective>().into_affine());
+        assert_eq!(combined.dealers.len(), 3);
+        assert!(combined.is_complete());
+        // The summed commitments still check the summed share.
+        assert!(verify_feldman_share(g(), combined.isk_i, 1, &combined.C_all));
+
+        let mut bad = msgs.clone();
+        bad[1].f_i = fr_to_hex(&(shares[1].isk_i + Fr::one()));
+        assert_eq!(batch_error(&bad), "dealer 1: g^f_i does not match Ai_all[0]");
+
+        let mut bad = msgs.clone();
+        bad[2].C_all[1] = bad[0].C_all[1].clone();
+        assert_eq!(batch_error(&bad), "dealer 2: f_i is inconsistent with the Feldman commitments");
+
+        let repeated = vec![msgs[0].clone(), msgs[1].clone(), msgs[0].clone()];
+        assert_eq!(batch_error(&repeated), "dealer 2: dealer's share is already counted");
+
+        let mut other_t = msgs.clone();
+        other_t[1] = deal("sid", 3, 3, g()).remove(0);
+        assert_eq!(batch_error(&other_t), "dealer 1: share has t = 3, not 2");
+
+        assert_eq!(batch_error(&[]), "empty DKG batch");
+    }
+}
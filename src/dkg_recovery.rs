@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use ark_bls12_381::Fr;
+use ark_ff::{Field, One, Zero};
+
+/// Errors while recombining Shamir shares.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum RecoveryError {
+    #[error("need at least {needed} distinct shares, got {got}")]
+    NotEnoughShares { needed: usize, got: usize },
+    #[error("duplicate x-coordinate {0}")]
+    DuplicateIndex(u64),
+    #[error("x-coordinate 0 is the secret itself, not a share")]
+    ZeroIndex,
+}
+
+/// Recover `f(0)` from points `(i, f(i))` of a polynomial of degree
+/// `threshold - 1`, i.e. the issuer secret `α` from the DKG shares `f_i`.
+///
+/// ```text
+/// f(0) = Σ_i f(x_i) · λ_i,   λ_i = Π_{j≠i} x_j / (x_j − x_i)
+/// ```
+pub fn lagrange_interpolate_at_zero(
+    shares: &[(u64, Fr)],
+    threshold: usize,
+) -> Result<Fr, RecoveryError> {
+    let mut seen = HashSet::new();
+    for &(x, _) in shares {
+        if x == 0 {
+            return Err(RecoveryError::ZeroIndex);
+        }
+        if !seen.insert(x) {
+            return Err(RecoveryError::DuplicateIndex(x));
+        }
+    }
+    let needed = threshold.max(1);
+    if shares.len() < needed {
+        return Err(RecoveryError::NotEnoughShares { needed, got: shares.len() });
+    }
+
+    let xs: Vec<Fr> = shares.iter().map(|&(x, _)| Fr::from(x)).collect();
+    let mut secret = Fr::zero();
+    for (i, &(_, y_i)) in shares.iter().enumerate() {
+        let mut num = Fr::one();
+        let mut den = Fr::one();
+        for (j, &x_j) in xs.iter().enumerate() {
+            if i != j {
+                num *= x_j;
+                den *= x_j - xs[i];
+            }
+        }
+        // den ≠ 0 because the x-coordinates are distinct.
+        secret += y_i * num * den.inverse().expect("distinct x-coordinates");
+    }
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_std::UniformRand;
+
+    /// `(i, f(i))` for `i = 1..=n` of a random degree-`t - 1` polynomial with `f(0) = secret`.
+    fn split(secret: Fr, n: u64, t: usize) -> Vec<(u64, Fr)> {
+        let mut rng = ark_std::test_rng();
+        let mut coeffs = vec![secret];
+        coeffs.extend((1..t).map(|_| Fr::rand(&mut rng)));
+        (1..=n)
+            .map(|x| (x, coeffs.iter().rev().fold(Fr::zero(), |acc, c| acc * Fr::from(x) + c)))
+            .collect()
+    }
+
+    #[test]
+    fn any_threshold_subset_recovers_the_secret() {
+        let secret = Fr::rand(&mut ark_std::test_rng());
+        let shares = split(secret, 5, 3);
+        for subset in [&shares[..3], &shares[2..], &[shares[0], shares[2], shares[4]][..], &shares[..]] {
+            assert_eq!(lagrange_interpolate_at_zero(subset, 3), Ok(secret));
+        }
+        // Fewer points than the degree needs interpolate some other value.
+        assert_ne!(lagrange_interpolate_at_zero(&shares[..2], 2), Ok(secret));
+    }
+
+    #[test]
+    fn too_few_shares_are_rejected() {
+        let shares = split(Fr::from(7u64), 5, 3);
+        assert_eq!(
+            lagrange_interpolate_at_zero(&shares[..2], 3),
+            Err(RecoveryError::NotEnoughShares { needed: 3, got: 2 })
+        );
+        assert_eq!(
+            lagrange_interpolate_at_zero(&[], 0),
+            Err(RecoveryError::NotEnoughShares { needed: 1, got: 0 })
+        );
+    }
+
+    #[test]
+    fn duplicate_and_zero_x_coordinates_are_rejected() {
+        let shares = split(Fr::from(7u64), 3, 2);
+        let duplicated = [shares[0], shares[1], (2, shares[2].1)];
+        assert_eq!(lagrange_interpolate_at_zero(&duplicated, 2), Err(RecoveryError::DuplicateIndex(2)));
+        let with_zero = [(0, Fr::from(7u64)), shares[0]];
+        assert_eq!(lagrange_interpolate_at_zero(&with_zero, 2), Err(RecoveryError::ZeroIndex));
+    }
+}
@@ -0,0 +1,50 @@
+//! Messages exchanged between the DKG coordinator (`dkg/`) and the issuers,
+//! shared so both sides agree on the wire format.
+
+use serde::{Deserialize, Serialize};
+
+/// One party's share and the dealer's public commitments, all hex-encoded
+/// compressed arkworks elements. The body of `POST /admin/receive_dkg`.
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct DkgPointMessage {
+    pub sid: String,
+    /// The party index (x-coordinate) the coordinator evaluated `f_i` at.
+    pub i: u64,
+    /// Number of parties; `Ai_all` must hold exactly one commitment each.
+    pub n: u64,
+    pub A: String,
+    pub f_i: String,
+    /// `A_j = g^{f(j)}` for every party `j = 1..=n`, in order.
+    pub Ai_all: Vec<String>,
+    /// Feldman commitments `C_j = g^{coeffs[j]}` to the dealer's polynomial.
+    pub C_all: Vec<String>,
+}
+
+/// Frames of the `/admin/dkg_ws` channel, one JSON text frame each:
+///
+/// ```text
+/// round 1   coordinator → issuer   share
+///           issuer → coordinator   ack | complaint
+/// round 2   coordinator → issuer   justify          (only after a complaint)
+///           issuer → coordinator   ack | complaint
+/// ```
+///
+/// A complaint that survives the justification means the dealer's share and
+/// commitments disagree; the coordinator reports it rather than retrying.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DkgWsMessage {
+    /// The share dealt to party `i`, with the commitments it is checked
+    /// against.
+    Share(DkgPointMessage),
+    /// The share verified and is now this issuer's.
+    Ack { sid: String, i: u64 },
+    /// The share failed verification, and why.
+    Complaint { sid: String, i: u64, reason: String },
+    /// The dealer's answer to a complaint: `f_i` revealed again, checked
+    /// against the commitments from the round-1 `share`.
+    Justify { sid: String, i: u64, f_i: String },
+    /// The frame was undecodable or out of order.
+    Error { message: String },
+}
@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use actix_web::error::JsonPayloadError;
+use actix_web::{http::{header, StatusCode}, HttpRequest, HttpResponse, ResponseError};
+use ark_serialize::SerializationError;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::audit::AuditUnavailable;
+use crate::challenge::ChallengeError;
+use crate::fallback;
+
+/// Errors returned by the HTTP handlers. Rendered as
+/// `{ "code": "<CODE>", "message": "<human readable>", "request_id": "<uuid>" }`
+/// where `code` is stable and meant for clients to match on.
+#[derive(thiserror::Error, Debug)]
+pub enum ApiError {
+    #[error("invalid request body: {0}")]
+    InvalidBody(String),
+    #[error("DKG state not initialized; call /admin/receive_dkg first")]
+    DkgNotInitialized,
+    #[error("issuer keys not initialized")]
+    IssuerKeysNotInitialized,
+    #[error("invalid proof")]
+    InvalidProof,
+    #[error("identity provider unavailable: {0}")]
+    ProviderUnavailable(String),
+    #[error("invalid token claims: {0}")]
+    InvalidClaims(String),
+    #[error("invalid token: {0}")]
+    InvalidToken(String),
+    #[error("native JWT verification is disabled; start with --allow-native-jwt")]
+    NativeJwtDisabled,
+    #[error("invalid challenge: {0}")]
+    InvalidChallenge(ChallengeError),
+    #[error("unknown circuit id '{0}'")]
+    UnknownCircuit(String),
+    #[error("invalid user_id: {0}")]
+    InvalidSubject(String),
+    #[error("s + isk is not invertible")]
+    InversionFailed,
+    #[error("invalid DKG share: {0}")]
+    InvalidDkgShare(String),
+    #[error("missing or invalid admin bearer token")]
+    Unauthorized,
+    #[error("too many requests; retry in {}s", retry_after_secs(.0))]
+    RateLimited(Duration),
+    #[error("request body exceeds the limit of {0} bytes")]
+    PayloadTooLarge(usize),
+    #[error("batch of {0} items exceeds the limit of {1}")]
+    BatchTooLarge(usize, usize),
+    #[error("failed to persist issuer keys")]
+    PersistenceFailed,
+    #[error("audit log unavailable: {0}")]
+    AuditLogUnavailable(String),
+    #[error("failed to serialize response: {0}")]
+    Serialization(#[from] SerializationError),
+    #[error("no route for {0}")]
+    RouteNotFound(String),
+    #[error("method {0} not allowed; use {}", .1.join(" or "))]
+    MethodNotAllowed(String, Vec<&'static str>),
+}
+
+impl From<AuditUnavailable> for ApiError {
+    fn from(e: AuditUnavailable) -> Self {
+        ApiError::AuditLogUnavailable(e.0)
+    }
+}
+
+/// JSON body of every error response.
+#[derive(Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// stable, machine-readable code, e.g. `INVALID_PROOF`
+    code: &'static str,
+    message: String,
+    /// the request's correlation id, also sent as `X-Request-Id` and logged
+    /// as `request_id` on the server
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+/// `404` body: the usual error plus every `"METHOD /path"` served.
+#[derive(Serialize)]
+struct NotFoundBody {
+    #[serde(flatten)]
+    error: ErrorBody,
+    endpoints: Vec<String>,
+}
+
+impl From<&ApiError> for ErrorBody {
+    fn from(e: &ApiError) -> Self {
+        ErrorBody {
+            code: e.code(),
+            message: e.to_string(),
+            request_id: crate::request_id::current(),
+        }
+    }
+}
+
+impl ApiError {
+    /// Machine-readable error code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidBody(_) => "INVALID_REQUEST_BODY",
+            ApiError::DkgNotInitialized => "DKG_NOT_INITIALIZED",
+            ApiError::IssuerKeysNotInitialized => "ISSUER_KEYS_NOT_INITIALIZED",
+            ApiError::InvalidProof => "INVALID_PROOF",
+            ApiError::ProviderUnavailable(_) => "PROVIDER_UNAVAILABLE",
+            ApiError::InvalidClaims(_) => "INVALID_TOKEN_CLAIMS",
+            ApiError::InvalidToken(_) => "INVALID_TOKEN",
+            ApiError::NativeJwtDisabled => "NATIVE_JWT_DISABLED",
+            ApiError::InvalidChallenge(_) => "INVALID_CHALLENGE",
+            ApiError::UnknownCircuit(_) => "UNKNOWN_CIRCUIT",
+            ApiError::InvalidSubject(_) => "INVALID_SUBJECT",
+            ApiError::InversionFailed => "INVERSION_FAILED",
+            ApiError::InvalidDkgShare(_) => "INVALID_DKG_SHARE",
+            ApiError::Unauthorized => "UNAUTHORIZED",
+            ApiError::RateLimited(_) => "RATE_LIMITED",
+            ApiError::PayloadTooLarge(_) => "PAYLOAD_TOO_LARGE",
+            ApiError::BatchTooLarge(..) => "BATCH_TOO_LARGE",
+            ApiError::PersistenceFailed => "PERSISTENCE_FAILED",
+            ApiError::AuditLogUnavailable(_) => "AUDIT_LOG_UNAVAILABLE",
+            ApiError::Serialization(_) => "SERIALIZATION_FAILED",
+            ApiError::RouteNotFound(_) => "NOT_FOUND",
+            ApiError::MethodNotAllowed(..) => "METHOD_NOT_ALLOWED",
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::InvalidBody(_) => StatusCode::BAD_REQUEST,
+            ApiError::DkgNotInitialized => StatusCode::BAD_REQUEST,
+            ApiError::IssuerKeysNotInitialized => StatusCode::NOT_FOUND,
+            ApiError::InvalidProof => StatusCode::UNAUTHORIZED,
+            ApiError::ProviderUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::InvalidClaims(_) => StatusCode::UNAUTHORIZED,
+            ApiError::InvalidToken(_) => StatusCode::UNAUTHORIZED,
+            ApiError::NativeJwtDisabled => StatusCode::FORBIDDEN,
+            ApiError::InvalidChallenge(_) => StatusCode::UNAUTHORIZED,
+            ApiError::UnknownCircuit(_) => StatusCode::BAD_REQUEST,
+            ApiError::InvalidSubject(_) => StatusCode::BAD_REQUEST,
+            ApiError::InversionFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::InvalidDkgShare(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::BatchTooLarge(..) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::PersistenceFailed => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::AuditLogUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::RouteNotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::MethodNotAllowed(..) => StatusCode::METHOD_NOT_ALLOWED,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut resp = HttpResponse::build(self.status_code());
+        match self {
+            ApiError::RateLimited(wait) => {
+                resp.insert_header((header::RETRY_AFTER, retry_after_secs(wait).to_string()));
+            }
+            ApiError::MethodNotAllowed(_, allowed) => {
+                resp.insert_header((header::ALLOW, allowed.join(", ")));
+            }
+            ApiError::RouteNotFound(_) => {
+                return resp.json(NotFoundBody { error: ErrorBody::from(self), endpoints: fallback::endpoints() });
+            }
+            _ => {}
+        }
+        resp.json(ErrorBody::from(self))
+    }
+}
+
+/// `web::Json` rejections as [`ApiError::InvalidBody`], so a malformed body
+/// gets the usual JSON error naming what is wrong (serde's "missing field
+/// `proof` at line 1 column 42", "invalid type: …") instead of actix's
+/// plain-text default. A body over the extractor's limit is
+/// [`ApiError::PayloadTooLarge`] instead.
+pub fn json_error_handler(err: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let message = match &err {
+        JsonPayloadError::Overflow { limit } | JsonPayloadError::OverflowKnownLength { limit, .. } => {
+            return ApiError::PayloadTooLarge(*limit).into();
+        }
+        JsonPayloadError::Deserialize(e) => e.to_string(),
+        other => other.to_string(),
+    };
+    ApiError::InvalidBody(message).into()
+}
+
+/// `Retry-After` takes whole seconds; round up so clients never retry early.
+fn retry_after_secs(wait: &Duration) -> u64 {
+    wait.as_secs() + u64::from(wait.subsec_nanos() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use serde_json::Value;
+
+    async fn render(err: ApiError) -> (StatusCode, Value) {
+        let resp = err.error_response();
+        let status = resp.status();
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn every_error_is_a_json_body_with_its_code() {
+        let cases = [
+            (ApiError::InvalidBody("missing field `proof`".into()), StatusCode::BAD_REQUEST, "INVALID_REQUEST_BODY"),
+            (ApiError::DkgNotInitialized, StatusCode::BAD_REQUEST, "DKG_NOT_INITIALIZED"),
+            (ApiError::IssuerKeysNotInitialized, StatusCode::NOT_FOUND, "ISSUER_KEYS_NOT_INITIALIZED"),
+            (ApiError::InvalidProof, StatusCode::UNAUTHORIZED, "INVALID_PROOF"),
+            (ApiError::ProviderUnavailable("timeout".into()), StatusCode::SERVICE_UNAVAILABLE, "PROVIDER_UNAVAILABLE"),
+            (ApiError::InvalidClaims("expired".into()), StatusCode::UNAUTHORIZED, "INVALID_TOKEN_CLAIMS"),
+            (ApiError::InvalidToken("bad signature".into()), StatusCode::UNAUTHORIZED, "INVALID_TOKEN"),
+            (ApiError::NativeJwtDisabled, StatusCode::FORBIDDEN, "NATIVE_JWT_DISABLED"),
+            (ApiError::InvalidChallenge(ChallengeError::Missing), StatusCode::UNAUTHORIZED, "INVALID_CHALLENGE"),
+            (ApiError::UnknownCircuit("v2".into()), StatusCode::BAD_REQUEST, "UNKNOWN_CIRCUIT"),
+            (ApiError::InvalidSubject("not decimal".into()), StatusCode::BAD_REQUEST, "INVALID_SUBJECT"),
+            (ApiError::InversionFailed, StatusCode::UNPROCESSABLE_ENTITY, "INVERSION_FAILED"),
+            (ApiError::InvalidDkgShare("bad hex".into()), StatusCode::BAD_REQUEST, "INVALID_DKG_SHARE"),
+            (ApiError::Unauthorized, StatusCode::UNAUTHORIZED, "UNAUTHORIZED"),
+            (ApiError::RateLimited(Duration::from_millis(1500)), StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED"),
+            (ApiError::PayloadTooLarge(8192), StatusCode::PAYLOAD_TOO_LARGE, "PAYLOAD_TOO_LARGE"),
+            (ApiError::BatchTooLarge(101, 100), StatusCode::PAYLOAD_TOO_LARGE, "BATCH_TOO_LARGE"),
+            (ApiError::PersistenceFailed, StatusCode::INTERNAL_SERVER_ERROR, "PERSISTENCE_FAILED"),
+            (ApiError::AuditLogUnavailable("disk full".into()), StatusCode::SERVICE_UNAVAILABLE, "AUDIT_LOG_UNAVAILABLE"),
+            (ApiError::Serialization(SerializationError::InvalidData), StatusCode::INTERNAL_SERVER_ERROR, "SERIALIZATION_FAILED"),
+            (ApiError::RouteNotFound("GET /nope".into()), StatusCode::NOT_FOUND, "NOT_FOUND"),
+            (ApiError::MethodNotAllowed("GET".into(), vec!["POST"]), StatusCode::METHOD_NOT_ALLOWED, "METHOD_NOT_ALLOWED"),
+        ];
+        for (err, status, code) in cases {
+            let message = err.to_string();
+            let (got_status, body) = render(err).await;
+            assert_eq!(got_status, status, "{code}");
+            assert_eq!(body["code"], code);
+            assert_eq!(body["message"], message.as_str());
+            // Outside a request there is no correlation id to report.
+            assert!(body.get("request_id").is_none(), "{code}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn rate_limited_rounds_retry_after_up() {
+        let resp = ApiError::RateLimited(Duration::from_millis(1500)).error_response();
+        assert_eq!(resp.headers().get(header::RETRY_AFTER).unwrap(), "2");
+    }
+
+    #[actix_web::test]
+    async fn not_found_lists_the_endpoints() {
+        let (_, body) = render(ApiError::RouteNotFound("GET /nope".into())).await;
+        let endpoints = body["endpoints"].as_array().unwrap();
+        assert!(endpoints.iter().any(|e| e == "POST /admin/generate_user_key"));
+    }
+}
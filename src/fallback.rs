@@ -0,0 +1,167 @@
+use actix_web::{HttpRequest, HttpResponse};
+
+use crate::error::ApiError;
+
+/// Every route `routes` mounts, as `(method, path)`; keep the two in step
+/// (the tests below fail when they drift). A `{…}` segment matches any
+/// single path segment.
+pub const ROUTES: &[(&str, &str)] = &[
+    ("POST", "/admin/generate_user_key"),
+    ("POST", "/admin/generate_user_keys_batch"),
+    ("POST", "/admin/recover_user_key"),
+    ("POST", "/admin/generate_user_key_jwt"),
+    ("GET", "/challenge"),
+    ("POST", "/admin/receive_dkg"),
+    ("POST", "/admin/receive_dkg_batch"),
+    ("GET", "/admin/dkg_ws"),
+    ("GET", "/admin/dkg_status"),
+    ("GET", "/ivk"),
+    ("GET", "/ivk/versions"),
+    ("GET", "/vk/{circuit_id}"),
+    ("POST", "/verify_proof"),
+    ("POST", "/pseudonym"),
+    ("POST", "/admin/rotate_keys"),
+    ("POST", "/admin/revoke"),
+    ("GET", "/revocation_status"),
+    ("GET", "/healthz"),
+    ("GET", "/readyz"),
+    ("GET", "/metrics"),
+    ("GET", "/api-docs/openapi.json"),
+];
+
+/// `default_service` for requests no route matched: `405` with `Allow` when
+/// the path exists under other methods (e.g. `GET` on a `POST` route),
+/// otherwise `404` listing the endpoints.
+pub async fn fallback(req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    let path = req.path();
+    let allowed: Vec<&'static str> = ROUTES
+        .iter()
+        .filter(|(_, pattern)| matches(pattern, path))
+        .map(|(method, _)| *method)
+        .collect();
+    if allowed.is_empty() {
+        Err(ApiError::RouteNotFound(format!("{} {path}", req.method())))
+    } else {
+        Err(ApiError::MethodNotAllowed(req.method().to_string(), allowed))
+    }
+}
+
+/// `"METHOD /path"` for every route, for the `404` body.
+pub fn endpoints() -> Vec<String> {
+    ROUTES.iter().map(|(method, path)| format!("{method} {path}")).collect()
+}
+
+fn matches(pattern: &str, path: &str) -> bool {
+    let (mut pattern, mut path) = (pattern.split('/'), path.trim_end_matches('/').split('/'));
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return true,
+            (Some(p), Some(s)) if p == s || (p.starts_with('{') && !s.is_empty()) => {}
+            _ => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::http::{header, Method};
+    use actix_web::test::{call_service, read_body, read_body_json, TestRequest};
+
+    use super::*;
+    use crate::test_support::{admin_auth, test_app, test_state, MockVerifier};
+
+    /// `pattern` with every `{…}` segment filled in.
+    fn sample_path(pattern: &str) -> String {
+        pattern
+            .split('/')
+            .map(|seg| if seg.starts_with('{') { "sample" } else { seg })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Every `#[get("…")]`-style route attribute under `src/`, as
+    /// `(method, path)` relative to its scope.
+    fn route_attributes() -> Vec<(String, String)> {
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut found = Vec::new();
+        for entry in std::fs::read_dir(src).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "rs") {
+                continue;
+            }
+            for line in std::fs::read_to_string(&path).unwrap().lines() {
+                let Some(attr) = line.trim().strip_prefix("#[") else { continue };
+                let Some((method, rest)) = attr.split_once("(\"") else { continue };
+                if !["get", "post", "put", "patch", "delete"].contains(&method) {
+                    continue;
+                }
+                let route = rest.split_once('"').unwrap().0;
+                found.push((method.to_uppercase(), route.to_string()));
+            }
+        }
+        found
+    }
+
+    #[actix_web::test]
+    async fn every_listed_route_is_served() {
+        let app = test_app!(test_state(Arc::new(MockVerifier::accepting())));
+        for (method, pattern) in ROUTES {
+            let req = TestRequest::default()
+                .method(Method::from_bytes(method.as_bytes()).unwrap())
+                .uri(&sample_path(pattern))
+                .insert_header(admin_auth())
+                .to_request();
+            let resp = call_service(&app, req).await;
+            let body: serde_json::Value = serde_json::from_slice(&read_body(resp).await).unwrap_or_default();
+            assert!(
+                !matches!(body["code"].as_str(), Some("NOT_FOUND" | "METHOD_NOT_ALLOWED")),
+                "{method} {pattern} is listed but not routed",
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn other_methods_on_listed_paths_get_the_listed_allow() {
+        let app = test_app!(test_state(Arc::new(MockVerifier::accepting())));
+        for (_, pattern) in ROUTES {
+            let listed: Vec<&str> = ROUTES.iter().filter(|(_, p)| p == pattern).map(|(m, _)| *m).collect();
+            for method in [Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE] {
+                if listed.contains(&method.as_str()) {
+                    continue;
+                }
+                let req = TestRequest::default()
+                    .method(method.clone())
+                    .uri(&sample_path(pattern))
+                    .insert_header(admin_auth())
+                    .to_request();
+                let resp = call_service(&app, req).await;
+                assert_eq!(resp.status(), 405, "{method} {pattern} is routed but not listed");
+                assert_eq!(resp.headers().get(header::ALLOW).unwrap(), &listed.join(", "));
+            }
+        }
+    }
+
+    #[test]
+    fn every_route_attribute_is_listed() {
+        let attributes = route_attributes();
+        assert!(attributes.len() >= ROUTES.len(), "found only {attributes:?}");
+        for (method, route) in attributes {
+            // Handlers under a scope name their path relative to it.
+            let listed = ROUTES
+                .iter()
+                .any(|(m, p)| *m == method && (*p == route || p.strip_prefix("/admin") == Some(route.as_str())));
+            assert!(listed, "{method} {route} is routed but missing from ROUTES");
+        }
+    }
+
+    #[actix_web::test]
+    async fn unknown_paths_list_every_route() {
+        let app = test_app!(test_state(Arc::new(MockVerifier::accepting())));
+        let resp = call_service(&app, TestRequest::get().uri("/nowhere").to_request()).await;
+        assert_eq!(resp.status(), 404);
+        let body: serde_json::Value = read_body_json(resp).await;
+        assert_eq!(body["endpoints"].as_array().unwrap().len(), ROUTES.len());
+    }
+}
@@ -0,0 +1,44 @@
+use ark_ff::PrimeField;
+use blake2::Blake2b512;
+use dock_crypto_utils::hashing_utils::field_elem_from_try_and_incr;
+
+/// The crate's one hash-to-field: `H(tag ∥ data)` into `F`, with Blake2b
+/// try-and-increment. Generic so the same construction serves BLS12-381 and
+/// BN254 scalars.
+///
+/// `tag` separates domains. Tags are concatenated without a length prefix
+/// (this keeps `s_from_sub` stable), so no tag may be a prefix of another.
+pub fn hash_to_fr<F: PrimeField>(tag: &[u8], data: &[u8]) -> F {
+    let mut msg = Vec::with_capacity(tag.len() + data.len());
+    msg.extend_from_slice(tag);
+    msg.extend_from_slice(data);
+    field_elem_from_try_and_incr::<F, Blake2b512>(&msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{AUDIT_USER_TAG, LOG_USER_TAG, PSEUDONYM_TAG, SIGNATURE_TAG, USER_ID_TAG};
+    use ark_bls12_381::Fr;
+
+    const TAGS: [&str; 5] = [USER_ID_TAG, PSEUDONYM_TAG, SIGNATURE_TAG, AUDIT_USER_TAG, LOG_USER_TAG];
+
+    #[test]
+    fn distinct_tags_give_distinct_outputs() {
+        for data in [&b""[..], b"alice", b"113048723091228773641"] {
+            let outputs: Vec<Fr> = TAGS.iter().map(|tag| hash_to_fr(tag.as_bytes(), data)).collect();
+            for (i, a) in outputs.iter().enumerate() {
+                for b in &outputs[i + 1..] {
+                    assert_ne!(a, b, "two tags collide on {data:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_tag_and_data_is_deterministic() {
+        let tag = USER_ID_TAG.as_bytes();
+        assert_eq!(hash_to_fr::<Fr>(tag, b"alice"), hash_to_fr::<Fr>(tag, b"alice"));
+        assert_ne!(hash_to_fr::<Fr>(tag, b"alice"), hash_to_fr::<Fr>(tag, b"bob"));
+    }
+}
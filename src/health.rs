@@ -0,0 +1,137 @@
+use actix_web::{get, web, HttpResponse};
+use serde::Serialize;
+
+use crate::lock::LockExt;
+use crate::AppState;
+
+#[derive(Serialize)]
+struct ReadinessResponse {
+    ready: bool,
+    issuer_keys: bool,
+    dkg_share: bool,
+    /// Preconditions that still block issuance.
+    missing: Vec<&'static str>,
+}
+
+/// Liveness: the process is up and serving HTTP.
+#[get("/healthz")]
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().finish()
+}
+
+/// Readiness: `200` once this node can issue keys, `503` otherwise. That
+/// needs issuer keys and, with `--require-dkg`, a complete DKG share.
+#[get("/readyz")]
+pub async fn readyz(state: web::Data<AppState>) -> HttpResponse {
+    let issuer_keys = state.issuer_keys.lock_or_recover().is_some();
+    let dkg_share = state.dkg_complete();
+
+    let mut missing = Vec::new();
+    if !issuer_keys {
+        missing.push("issuer_keys");
+    }
+    if state.require_dkg && !dkg_share {
+        missing.push("dkg_share");
+    }
+
+    let ready = missing.is_empty();
+    let body = ReadinessResponse { ready, issuer_keys, dkg_share, missing };
+    if ready {
+        HttpResponse::Ok().json(body)
+    } else {
+        HttpResponse::ServiceUnavailable().json(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use actix_web::http::StatusCode;
+    use actix_web::test;
+    use ark_bls12_381::{Fr, G1Affine};
+    use ark_ec::AffineRepr;
+    use tokio::sync::Notify;
+
+    use super::*;
+    use crate::dkg::DkgShare;
+    use crate::test_support::{keygen_body, test_app, test_app_state, test_state, MockVerifier};
+
+    async fn readyz_of(state: web::Data<AppState>) -> (StatusCode, serde_json::Value) {
+        let app = test_app!(state);
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/readyz").to_request()).await;
+        (resp.status(), test::read_body_json(resp).await)
+    }
+
+    fn share() -> DkgShare {
+        DkgShare {
+            sid: "test-session".into(),
+            isk_i: Fr::from(7u64),
+            A: G1Affine::generator(),
+            Ai_all: vec![G1Affine::generator()],
+            C_all: vec![G1Affine::generator()],
+            dealers: vec![G1Affine::generator()],
+        }
+    }
+
+    #[actix_web::test]
+    async fn ready_once_issuer_keys_exist() {
+        let (status, body) = readyz_of(test_state(Arc::new(MockVerifier::accepting()))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["ready"], true);
+        assert_eq!(body["issuer_keys"], true);
+        assert_eq!(body["missing"], serde_json::json!([]));
+    }
+
+    #[actix_web::test]
+    async fn not_ready_without_issuer_keys() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        *state.issuer_keys.lock_or_recover() = None;
+        let (status, body) = readyz_of(state).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["ready"], false);
+        assert_eq!(body["issuer_keys"], false);
+        assert_eq!(body["missing"], serde_json::json!(["issuer_keys"]));
+    }
+
+    #[actix_web::test]
+    async fn require_dkg_waits_for_a_share() {
+        let mut state = test_app_state(Arc::new(MockVerifier::accepting()));
+        state.require_dkg = true;
+        let state = web::Data::new(state);
+
+        let (status, body) = readyz_of(state.clone()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["dkg_share"], false);
+        assert_eq!(body["missing"], serde_json::json!(["dkg_share"]));
+
+        *state.dkg.lock_or_recover() = Some(share());
+        let (status, body) = readyz_of(state).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["dkg_share"], true);
+    }
+
+    /// A probe arriving while a key request is stuck in verification is
+    /// answered at once instead of queueing behind it.
+    #[actix_web::test]
+    async fn answers_while_a_verification_is_in_flight() {
+        let gate = Arc::new(Notify::new());
+        let mock = Arc::new(MockVerifier::gated(gate.clone()));
+        let state = test_state(mock.clone());
+        let req: crate::GenerateKeyRequest =
+            serde_json::from_value(keygen_body("113048723091228773641")).unwrap();
+        let keygen = actix_web::rt::spawn({
+            let state = state.clone();
+            async move { crate::issue_user_key(&state, &req, ark_serialize::Compress::Yes).await.is_ok() }
+        });
+        while mock.calls.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let (status, _) = readyz_of(state).await;
+        assert_eq!(status, StatusCode::OK);
+        gate.notify_one();
+        assert!(keygen.await.unwrap());
+    }
+}
@@ -1,59 +1,633 @@
-use ark_serialize::CanonicalDeserialize;
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use async_trait::async_trait;
 use base64::{engine::general_purpose as b64, Engine as _};
 use num_bigint::BigUint;
-use reqwest::Client;
+use reqwest::{header::{HeaderMap, CACHE_CONTROL}, Client};
 use serde::Deserialize;
+use utoipa::ToSchema;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex, RwLock, Semaphore};
 use proof::base64_to_proof;
 
 use ark_bn254::{Bn254, Fr, Fq, Fq2, G1Affine, G1Projective, G2Affine, G2Projective};
-use ark_groth16::{Groth16, Proof};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, VerifyingKey};
 use ark_snark::SNARK;
-use ark_ff::{BigInteger256, PrimeField};
+use ark_ff::{BigInteger256, PrimeField, UniformRand, Zero};
+use ark_std::rand::rngs::OsRng;
 use crate::proof;
-
-/// Match the limb size used in the Circom input generator.
-pub const CHUNK_BITS: usize = 121;
+use crate::constants::{CHUNK_BITS, MAX_CHUNK_BITS};
 
 /// Verification key in *SnarkJS JSON* format.
 const VK_JSON: &str = include_str!("./verification_key.json");
 
+/// Google's `iss` claim and certs endpoint.
+pub const GOOGLE_ISSUER: &str = "https://accounts.google.com";
+const GOOGLE_CERTS_URL: &str = "https://www.googleapis.com/oauth2/v3/certs";
+
+/// JWKS lifetime when the response carries no usable `Cache-Control: max-age`.
+const DEFAULT_JWKS_TTL: Duration = Duration::from_secs(300);
+
+/// Default tolerance for clock drift between us and the token issuer.
+pub const DEFAULT_CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+/// Timeouts and pool size of the client that fetches JWKS. A fetch that
+/// times out counts as the provider being unreachable, like any transport
+/// error, so it is retried and then answered with [`JwksUnavailable`].
+#[derive(Debug, Clone, Copy)]
+pub struct HttpConfig {
+    /// Limit on establishing the TCP + TLS connection.
+    pub connect_timeout: Duration,
+    /// Limit on one whole request, from sending it to reading the body.
+    pub timeout: Duration,
+    /// Idle keep-alive connections kept per provider host.
+    pub pool_max_idle_per_host: usize,
+    /// JWKS fetches in flight at once, across all providers; further misses
+    /// wait for a slot rather than adding to the provider's load.
+    pub max_concurrent_fetches: usize,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            connect_timeout: Duration::from_secs(5),
+            timeout: Duration::from_secs(10),
+            pool_max_idle_per_host: 8,
+            max_concurrent_fetches: 4,
+        }
+    }
+}
+
+impl HttpConfig {
+    fn client(&self) -> Result<Client> {
+        Ok(Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout)
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .build()?)
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct JwkSet {
     keys: Vec<Jwk>,
 }
 
-#[derive(Debug, Deserialize)]
-struct Jwk {
+/// One key of a JWK set. Deserialize it from the provider's JSON, or build
+/// an RSA key with [`Jwk::rsa`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct Jwk {
     kid: String,
-    n: String,
-    e: String,
-    #[serde(rename = "kty")] _kty: String,
-    #[serde(rename = "alg")] _alg: String,
+    #[serde(rename = "alg", default)] _alg: Option<String>,
+    #[serde(flatten)]
+    key: JwkKey,
+}
+
+impl Jwk {
+    /// An RS256 key from its base64url modulus `n` and exponent `e`, as they
+    /// appear in the JWKS.
+    pub fn rsa(kid: impl Into<String>, n: impl Into<String>, e: impl Into<String>) -> Self {
+        Jwk {
+            kid: kid.into(),
+            _alg: Some("RS256".into()),
+            key: JwkKey::Rsa { n: n.into(), e: e.into() },
+        }
+    }
+}
+
+/// Key material by `kty`. Keys of other types are kept (so one odd key does
+/// not fail the whole set) but cannot be used to verify.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kty")]
+enum JwkKey {
+    /// RS256: modulus `n` and exponent `e`, base64url.
+    #[serde(rename = "RSA")]
+    Rsa {
+        n: String,
+        e: String,
+    },
+    /// ES256: affine point `(x, y)` on `crv`, base64url big-endian.
+    #[serde(rename = "EC")]
+    Ec { crv: String, x: String, y: String },
+    #[serde(other)]
+    Unsupported,
+}
+
+/// Modulus size the RSA circuit must be able to take.
+pub(crate) const RSA_MODULUS_BITS: usize = 2048;
+
+/// Public inputs of the RSA circuit besides the modulus limbs: `sub` and
+/// `subStatement`. The limb count is whatever the rest of the VK's IC holds.
+pub(crate) const RSA_CIRCUIT_EXTRA_INPUTS: usize = 2;
+
+/// Byte length of a P-256 coordinate.
+const P256_COORD_LEN: usize = 32;
+
+
+/// Token issuers we accept, and where each publishes its signing keys.
+#[derive(Debug, Clone)]
+pub struct IssuerConfig {
+    /// `(issuer_url, jwks_uri)` pairs.
+    pub providers: Vec<(String, String)>,
+    /// RSA modulus size in bits per `issuer_url`, for providers whose keys
+    /// are not the size the default circuit takes (e.g. 4096-bit). Issuers
+    /// without an entry accept any modulus that fits the circuit.
+    pub modulus_bits: HashMap<String, usize>,
+    /// Client ids whose ID tokens [`Verifier::verify_jwt_rs256`] accepts, as
+    /// `aud` / `azp`. Empty accepts none.
+    pub audiences: Vec<String>,
+}
+
+impl Default for IssuerConfig {
+    /// Google only. Its ID tokens use either form of `iss`.
+    fn default() -> Self {
+        Self {
+            providers: vec![
+                (GOOGLE_ISSUER.to_string(), GOOGLE_CERTS_URL.to_string()),
+                ("accounts.google.com".to_string(), GOOGLE_CERTS_URL.to_string()),
+            ],
+            modulus_bits: HashMap::new(),
+            audiences: Vec::new(),
+        }
+    }
+}
+
+impl IssuerConfig {
+    /// JWKS URI for `iss`, or `None` if the issuer isn't allowed.
+    pub fn jwks_uri(&self, iss: &str) -> Option<&str> {
+        self.providers
+            .iter()
+            .find(|(issuer, _)| issuer == iss)
+            .map(|(_, uri)| uri.as_str())
+    }
+
+    /// The configured RSA modulus size for `iss`, if any.
+    pub fn modulus_bits(&self, iss: &str) -> Option<usize> {
+        self.modulus_bits.get(iss).copied()
+    }
+
+    /// Whether a token for `aud`, presented by `azp`, was issued to one of
+    /// our [`audiences`](Self::audiences), as OIDC Core §3.1.3.7 has clients
+    /// check: `aud` must contain an allowed client id, a token for several
+    /// audiences must name its presenter in `azp`, and `azp`, when present,
+    /// must be allowed too.
+    ///
+    /// # Errors
+    /// Describes the first check that failed.
+    pub fn check_audience(&self, aud: &[String], azp: Option<&str>) -> Result<()> {
+        let allowed = |id: &str| self.audiences.iter().any(|a| a == id);
+        ensure!(!self.audiences.is_empty(), "no audience is configured for ID tokens");
+        ensure!(aud.iter().any(|a| allowed(a)), "token audience {aud:?} is not allowed");
+        match azp {
+            Some(azp) => ensure!(allowed(azp), "token authorized party '{azp}' is not allowed"),
+            None => ensure!(aud.len() == 1, "token has several audiences but no azp"),
+        }
+        Ok(())
+    }
+}
+
+/// Time claims of the JWT a proof was made from, in seconds since the epoch.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct JwtClaims {
+    pub exp: u64,
+    pub iat: u64,
+    #[serde(default)]
+    pub nbf: Option<u64>,
+}
+
+/// Claims of an ID token verified by [`Verifier::verify_jwt_rs256`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    /// Client ids the token was issued to; a single string on the wire when
+    /// there is one.
+    #[serde(deserialize_with = "one_or_many")]
+    pub aud: Vec<String>,
+    /// Client the token was issued to, when it differs from `aud` or `aud`
+    /// lists several.
+    #[serde(default)]
+    pub azp: Option<String>,
+    #[serde(flatten)]
+    pub time: JwtClaims,
+}
+
+fn one_or_many<'de, D: serde::Deserializer<'de>>(d: D) -> std::result::Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(d)? {
+        OneOrMany::One(one) => vec![one],
+        OneOrMany::Many(many) => many,
+    })
+}
+
+/// Why a token's time claims were rejected.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ClaimsError {
+    #[error("token expired at {exp} (now {now})")]
+    Expired { exp: u64, now: u64 },
+    #[error("token not valid before {nbf} (now {now})")]
+    NotYetValid { nbf: u64, now: u64 },
+    #[error("token issued in the future at {iat} (now {now})")]
+    IssuedInFuture { iat: u64, now: u64 },
+}
+
+impl JwtClaims {
+    /// Check the claims at `now`, tolerating `skew` of clock drift either way.
+    pub fn check(&self, now: u64, skew: Duration) -> Result<(), ClaimsError> {
+        let skew = skew.as_secs();
+        if now > self.exp.saturating_add(skew) {
+            return Err(ClaimsError::Expired { exp: self.exp, now });
+        }
+        if let Some(nbf) = self.nbf {
+            if now.saturating_add(skew) < nbf {
+                return Err(ClaimsError::NotYetValid { nbf, now });
+            }
+        }
+        if now.saturating_add(skew) < self.iat {
+            return Err(ClaimsError::IssuedInFuture { iat: self.iat, now });
+        }
+        Ok(())
+    }
+}
+
+/// Which part of the statement a failed verification points at. Indices are
+/// into the public inputs (`IC[index + 1]`).
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum VerifyFailure {
+    /// `sub` (input 0) is not a decimal integer.
+    #[error("public input 0 (sub): {0}")]
+    Sub(String),
+    /// The JWK cannot be turned into the key inputs (`1..last`): modulus too
+    /// long, bad coordinates, unsupported key type or missing EC circuit.
+    #[error("key inputs: {0}")]
+    Key(String),
+    /// The inputs built for this key do not match the circuit's IC length.
+    #[error("statement has {got} public inputs but the circuit expects {expected}")]
+    InputCount { got: usize, expected: usize },
+    /// The last input (`subStatement`) is not what the proof was made for:
+    /// the caller bound it to a statement (e.g. a challenge nonce), but the
+    /// proof has `sub` there.
+    #[error("public input {index} (subStatement) does not match")]
+    SubStatement { index: usize },
+    /// The statement is well-formed; the proof was made for a different one
+    /// (another `sub`, key or statement) or is not a proof at all.
+    #[error("proof does not match the {inputs} well-formed public inputs")]
+    Proof { inputs: usize },
+}
+
+/// Result of [`Verifier::verify_verbose`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyDiagnostics {
+    /// `"RSA"` or `"EC"`, once the key type is known.
+    pub circuit: Option<&'static str>,
+    /// `None` iff the proof verified.
+    pub failure: Option<VerifyFailure>,
+}
+
+impl VerifyDiagnostics {
+    pub fn is_valid(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Circuit id of the RSA (RS256) circuit; used for RSA keys when a request
+/// names no circuit.
+pub const RSA_CIRCUIT_ID: &str = "rsa";
+
+/// Circuit id of the ES256 circuit; used for P-256 keys when a request names
+/// no circuit.
+pub const EC_CIRCUIT_ID: &str = "ec";
+
+/// One proof of a [`Verifier::verify_batch`]: the arguments of
+/// [`Verifier::verify_proof`] but the issuer, which a batch shares.
+#[derive(Clone, Debug)]
+pub struct BatchItem {
+    /// decimal subject
+    pub sub: String,
+    pub kid: String,
+    pub circuit_id: Option<String>,
+    pub proof: Proof<Bn254>,
+    pub statement: Option<Fr>,
+}
+
+/// A request named a circuit id no verifying key is registered under.
+#[derive(thiserror::Error, Debug)]
+#[error("unknown circuit id '{0}'")]
+pub struct UnknownCircuit(pub String);
+
+/// Tries per JWKS fetch before giving up on an unreachable provider.
+pub const JWKS_FETCH_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry; doubled after each one.
+const JWKS_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// The provider's key set could not be fetched for reasons on the network or
+/// the provider's side, so nothing can be said about the proof yet.
+#[derive(thiserror::Error, Debug)]
+#[error("JWKS at {uri} unreachable: {reason}")]
+pub struct JwksUnavailable {
+    pub uri: String,
+    pub reason: String,
+}
+
+/// One provider's key set, as of the last fetch. Keys are grouped by `kid`
+/// so a set that repeats one is noticed rather than resolved arbitrarily.
+struct CachedJwks {
+    keys: HashMap<String, Vec<Jwk>>,
+    expires_at: Instant,
+}
+
+impl CachedJwks {
+    fn new(keys: Vec<Jwk>, ttl: Duration) -> Self {
+        let mut by_kid: HashMap<String, Vec<Jwk>> = HashMap::new();
+        for k in keys {
+            by_kid.entry(k.kid.clone()).or_default().push(k);
+        }
+        CachedJwks { keys: by_kid, expires_at: Instant::now() + ttl }
+    }
+
+    /// The single key with `kid`: `Ok(None)` if absent, an error if the set
+    /// lists it more than once.
+    fn key(&self, kid: &str) -> Result<Option<&Jwk>> {
+        match self.keys.get(kid).map(Vec::as_slice) {
+            None | Some([]) => Ok(None),
+            Some([jwk]) => Ok(Some(jwk)),
+            Some(dups) => bail!("kid '{kid}' matches {} keys in the JWK set", dups.len()),
+        }
+    }
 }
 
+/// What the handlers need from a proof verifier. [`Verifier`] is the Groth16
+/// one; `AppState` holds an `Arc<dyn ProofVerifier>` so another backend, or
+/// a stub, can be put in its place without touching the handlers.
+#[async_trait]
+pub trait ProofVerifier: Send + Sync {
+    /// Whether `proof` shows a token for `sub` signed by `iss`'s key `kid`;
+    /// see [`Verifier::verify_proof`].
+    async fn verify(
+        &self,
+        iss: &str,
+        sub: &str,
+        kid: &str,
+        circuit_id: Option<&str>,
+        proof: Proof<Bn254>,
+        statement: Option<Fr>,
+    ) -> Result<bool>;
+
+    /// [`verify`](Self::verify) with the reason for a rejection; see
+    /// [`Verifier::verify_verbose`].
+    async fn verify_verbose(
+        &self,
+        iss: &str,
+        sub: &str,
+        kid: &str,
+        circuit_id: Option<&str>,
+        proof: Proof<Bn254>,
+        statement: Option<Fr>,
+    ) -> Result<VerifyDiagnostics>;
+
+    /// Reject tokens that are not valid right now; see
+    /// [`Verifier::check_claims`].
+    fn check_claims(&self, claims: &JwtClaims) -> Result<()>;
+
+    /// The verifying key registered as `circuit_id`; see
+    /// [`Verifier::verifying_key`].
+    fn verifying_key(&self, circuit_id: &str) -> Option<VerifyingKey<Bn254>>;
+
+    /// Verify an RS256 ID token without a proof; see
+    /// [`Verifier::verify_jwt_rs256`].
+    async fn verify_jwt_rs256(&self, token: &str, kid: &str) -> Result<IdTokenClaims>;
+
+    /// [`verify`](Self::verify) for many proofs under one `iss`, one verdict
+    /// per item in order; see [`Verifier::verify_batch`].
+    async fn verify_batch(&self, iss: &str, items: &[BatchItem]) -> Result<Vec<bool>>;
+}
+
+#[async_trait]
+impl ProofVerifier for Verifier {
+    async fn verify(
+        &self,
+        iss: &str,
+        sub: &str,
+        kid: &str,
+        circuit_id: Option<&str>,
+        proof: Proof<Bn254>,
+        statement: Option<Fr>,
+    ) -> Result<bool> {
+        self.verify_proof(iss, sub, kid, circuit_id, proof, statement).await
+    }
+
+    async fn verify_verbose(
+        &self,
+        iss: &str,
+        sub: &str,
+        kid: &str,
+        circuit_id: Option<&str>,
+        proof: Proof<Bn254>,
+        statement: Option<Fr>,
+    ) -> Result<VerifyDiagnostics> {
+        Verifier::verify_verbose(self, iss, sub, kid, circuit_id, proof, statement).await
+    }
+
+    fn check_claims(&self, claims: &JwtClaims) -> Result<()> {
+        Verifier::check_claims(self, claims)
+    }
+
+    fn verifying_key(&self, circuit_id: &str) -> Option<VerifyingKey<Bn254>> {
+        Verifier::verifying_key(self, circuit_id)
+    }
+
+    async fn verify_jwt_rs256(&self, token: &str, kid: &str) -> Result<IdTokenClaims> {
+        Verifier::verify_jwt_rs256(self, token, kid).await
+    }
+
+    async fn verify_batch(&self, iss: &str, items: &[BatchItem]) -> Result<Vec<bool>> {
+        Verifier::verify_batch(self, iss, items).await
+    }
+}
 
 pub struct Verifier {
-    vk: ark_groth16::VerifyingKey<Bn254>,
+    /// Verifying keys by circuit id: always [`RSA_CIRCUIT_ID`], plus
+    /// [`EC_CIRCUIT_ID`] and any others registered. Prepared once at
+    /// registration; `process_vk` is too costly per request. Shared with the
+    /// blocking pool threads that run the pairings.
+    circuits: HashMap<String, Arc<PreparedVerifyingKey<Bn254>>>,
     http: Client,
+    issuers: IssuerConfig,
+    /// Key sets keyed by JWKS URI.
+    jwks: RwLock<HashMap<String, CachedJwks>>,
+    /// Per JWKS URI, held while refetching so concurrent misses on one set
+    /// share a single request.
+    refresh: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    /// Bounds outbound JWKS fetches; see [`HttpConfig::max_concurrent_fetches`].
+    fetch_permits: Semaphore,
+    /// Overrides the `max-age` advertised by the provider when set.
+    cache_ttl: Option<Duration>,
+    /// Tolerance applied to `exp` / `nbf` / `iat` in `check_claims`.
+    clock_skew: Duration,
+    /// Bits per limb when chunking an RSA modulus or EC coordinate.
+    chunk_bits: usize,
 }
 
 impl Verifier {
-    pub async fn new() -> Result<Self> {
-        let vk = parse_vk_json(VK_JSON)?;
-        Ok(Self { vk, http: Client::new() })
+    /// Verifier for the embedded RSA circuit verifying key.
+    pub async fn new(issuers: IssuerConfig) -> Result<Self> {
+        Self::with_vk_json(VK_JSON, issuers)
+    }
+
+    /// Verifier for the RSA circuit whose SnarkJS verifying key is at `path`,
+    /// read at runtime so the circuit can change without a rebuild.
+    ///
+    /// # Errors
+    /// Fails if the file cannot be read or parsed, or if its IC has too few
+    /// modulus limbs for a 2048-bit modulus at [`CHUNK_BITS`] bits each.
+    pub async fn from_vk_path(path: &Path, issuers: IssuerConfig) -> Result<Self> {
+        let vk_json = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("reading {}: {e}", path.display()))?;
+        Self::with_vk_json(&vk_json, issuers)
+    }
+
+    fn with_vk_json(vk_json: &str, issuers: IssuerConfig) -> Result<Self> {
+        let vk = parse_vk_json(vk_json)?;
+        let pvk = Arc::new(Groth16::<Bn254>::process_vk(&vk)?);
+        check_rsa_capacity(&pvk, CHUNK_BITS)?;
+        Ok(Self {
+            circuits: HashMap::from([(RSA_CIRCUIT_ID.to_string(), pvk)]),
+            http: HttpConfig::default().client()?,
+            issuers,
+            jwks: RwLock::new(HashMap::new()),
+            refresh: Mutex::new(HashMap::new()),
+            fetch_permits: Semaphore::new(HttpConfig::default().max_concurrent_fetches),
+            cache_ttl: None,
+            clock_skew: DEFAULT_CLOCK_SKEW,
+            chunk_bits: CHUNK_BITS,
+        })
+    }
+
+    /// Chunk RSA moduli and EC coordinates into `bits`-bit limbs instead of
+    /// [`CHUNK_BITS`], for circuits built with another limb size. Applies to
+    /// every registered circuit.
+    ///
+    /// # Errors
+    /// - Fails if `bits` is 0 or above [`MAX_CHUNK_BITS`].
+    /// - Fails if the RSA circuit's limbs cannot hold a 2048-bit modulus at
+    ///   this width.
+    pub fn with_chunk_bits(mut self, bits: usize) -> Result<Self> {
+        ensure!(
+            (1..=MAX_CHUNK_BITS).contains(&bits),
+            "chunk width must be 1..={MAX_CHUNK_BITS} bits, got {bits}"
+        );
+        check_rsa_capacity(&self.circuits[RSA_CIRCUIT_ID], bits)?;
+        self.chunk_bits = bits;
+        Ok(self)
+    }
+
+    /// Fetch JWKS with `config`'s timeouts and pool size instead of
+    /// [`HttpConfig::default`].
+    ///
+    /// # Errors
+    /// Fails if the HTTP client cannot be built (e.g. no TLS backend).
+    pub fn with_http_config(mut self, config: HttpConfig) -> Result<Self> {
+        self.http = config.client()?;
+        self.fetch_permits = Semaphore::new(config.max_concurrent_fetches.max(1));
+        Ok(self)
+    }
+
+    /// Tolerate `skew` of clock drift when checking JWT time claims.
+    pub fn with_clock_skew(mut self, skew: Duration) -> Self {
+        self.clock_skew = skew;
+        self
+    }
+
+    /// Accept ES256 (P-256) tokens, verified against the EC circuit whose
+    /// SnarkJS verifying key is `vk_json`. Without this, proofs for EC keys
+    /// are rejected.
+    pub fn with_ec_verifying_key(self, vk_json: &str) -> Result<Self> {
+        self.with_circuit(EC_CIRCUIT_ID, vk_json)
+    }
+
+    /// Register the SnarkJS verifying key `vk_json` under `circuit_id`, for
+    /// requests that name it: another provider's circuit, or a new version
+    /// of an existing one. Its public inputs are built from the JWK as for
+    /// the default circuit of the key's type, so the layout must match.
+    /// Registering an id again replaces its key.
+    pub fn with_circuit(mut self, circuit_id: &str, vk_json: &str) -> Result<Self> {
+        let vk = parse_vk_json(vk_json)?;
+        let pvk = Arc::new(Groth16::<Bn254>::process_vk(&vk)?);
+        self.circuits.insert(circuit_id.to_string(), pvk);
+        Ok(self)
+    }
+
+    /// The registered circuit ids, sorted.
+    pub fn circuit_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.circuits.keys().map(String::as_str).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The verifying key registered as `circuit_id`, e.g. for
+    /// [`vk_to_snarkjs_json`].
+    pub fn verifying_key(&self, circuit_id: &str) -> Option<VerifyingKey<Bn254>> {
+        self.circuits.get(circuit_id).map(|pvk| pvk.vk.clone())
+    }
+
+    /// The verifying key for `circuit_id`, or for the default circuit of
+    /// `jwk`'s key type when the request names none.
+    ///
+    /// # Errors
+    /// - Fails with [`UnknownCircuit`] if `circuit_id` is not registered.
+    /// - Fails if no id is given and the key type's default circuit is not
+    ///   configured (ES256 without an EC verifying key).
+    fn circuit(&self, circuit_id: Option<&str>, jwk: &Jwk) -> Result<Arc<PreparedVerifyingKey<Bn254>>> {
+        if let Some(id) = circuit_id {
+            return self
+                .circuits
+                .get(id)
+                .cloned()
+                .ok_or_else(|| UnknownCircuit(id.to_string()).into());
+        }
+        let default = match &jwk.key {
+            JwkKey::Rsa { .. } => RSA_CIRCUIT_ID,
+            JwkKey::Ec { .. } => EC_CIRCUIT_ID,
+            JwkKey::Unsupported => bail!("key {} has an unsupported kty", jwk.kid),
+        };
+        self.circuits.get(default).cloned().ok_or_else(|| {
+            anyhow!("key {} is ES256 but no EC circuit verifying key is configured", jwk.kid)
+        })
+    }
+
+    /// Cache fetched JWKs for `ttl` instead of the provider's `max-age`.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
     }
 
     /// # Arguments
     /// * `&self`  
     ///   The verifier instance, containing the in-memory verification key (`vk`).  
+    /// * `iss: &str`  
+    ///   The token's `iss` claim; selects which provider's JWKS to use.  
     /// * `sub: &str`  
-    ///   The subject identifier (decimal string) to be bound by the proof.  
+    ///   The subject identifier to be bound by the proof.  
     /// * `kid: &str`  
-    ///   The Google JWK key ID used to fetch the public key.  
+    ///   The JWK key ID used to fetch the public key.  
+    /// * `sub_encoding: SubEncoding`  
+    ///   How `sub` is spelled; rewritten to decimal before the public inputs are built.  
     /// * `proof_b64: &str`  
     ///   The Base64-encoded zkSNARK proof to verify.  
+    /// * `statement: Option<Fr>`  
+    ///   The value the proof's `subStatement` binds (a nonce, an audience hash, …);
+    ///   `None` for the original statement, `sub` repeated.  
     ///
     /// # Returns
     /// * `Ok(true)` if the proof is valid for the given public inputs.  
@@ -62,14 +636,17 @@ impl Verifier {
     ///
     /// # Pseudocode
     /// ```text
-    /// // 1) Fetch Google JSON Web Key (JWK) for `kid`
-    /// jwk = fetch_google_key(kid)
+    /// // 1) Fetch the issuer's JSON Web Key (JWK) for `kid`
+    /// jwks_uri = issuers.jwks_uri(iss)
+    /// jwk = fetch_key(jwks_uri, kid)
     ///
-    /// // 2) Chunk the RSA modulus `n` into fixed-size limbs
-    /// limbs = chunk_modulus(jwk.n, CHUNK_BITS)
+    /// // 2) Pick the circuit by key type: RSA → circuits["rsa"], EC → circuits["ec"],
+    /// //    and chunk the RSA modulus `n` (or EC x ∥ y) into fixed-size limbs
+    /// limbs = chunk_modulus(jwk.n, self.chunk_bits)   // CHUNK_BITS = 121 by default
     ///
     /// // 3) Build Groth16 public inputs:
     /// //    IC[1] = sub as field element
+    /// sub      = sub_encoding.to_decimal(sub)
     /// sub_big  = BigUint::parse(sub, base=10)
     /// sub_fr   = biguint_to_fr(sub_big)
     /// public_inputs = [ sub_fr ]
@@ -78,73 +655,693 @@ impl Verifier {
     /// for limb in limbs:
     ///     public_inputs.push(biguint_to_fr(limb))
     ///
-    /// //    IC[19] = sub_fr again
-    /// public_inputs.push(sub_fr)
+    /// //    IC[19] = the statement the proof is bound to, or sub_fr again
+    /// public_inputs.push(statement ?? sub_fr)
     ///
     /// // 4) Decode the Base64 proof into proof struct
     /// proof = base64_to_proof(proof_b64)
     ///
-    /// // 5) Process the verification key and verify the proof
-    /// pvk      = Groth16.process_vk(self.vk)
-    /// verified = Groth16.verify_with_processed_vk(pvk, public_inputs, proof)
+    /// // 5) Verify the proof against the prepared verification key,
+    /// //    on the blocking pool so the async workers keep serving requests
+    /// verified = spawn_blocking(Groth16.verify_with_processed_vk(pvk, public_inputs, proof))
     ///
     /// return verified
     /// ```
     ///
     /// # Errors
+    /// - Fails if `iss` is not in the configured allowlist.
     /// - Fails if fetching or parsing the JWK returns an error.
+    /// - Fails if the key is EC and no EC verifying key is configured, or if
+    ///   the key type's inputs do not fit the selected circuit.
     /// - Fails if the modulus cannot be chunked correctly.
-    /// - Fails if `sub` is not a valid decimal integer.
+    /// - Fails if `sub` is not valid in `sub_encoding`.
     /// - Fails if proof Base64 decoding or deserialization errors.
-    /// - Fails if the Groth16 verification key cannot be processed or the proof verification itself errors.
-    pub async fn verify(&self, sub: &str, kid: &str, proof_b64: &str) -> Result<bool, anyhow::Error> {
-        // 1. Google key
-        let jwk = self.fetch_google_key(kid).await?;
+    /// - Fails if the Groth16 proof verification itself errors.
+    pub async fn verify(
+        &self,
+        iss: &str,
+        sub: &str,
+        kid: &str,
+        sub_encoding: SubEncoding,
+        proof_b64: &str,
+        statement: Option<Fr>,
+    ) -> Result<bool, anyhow::Error> {
+        let sub = sub_encoding.to_decimal(sub)?;
+        // 1. Issuer key
+        let jwk = self.fetch_key(self.jwks_uri(iss)?, kid).await?;
+        self.verify_with_jwk(iss, &sub, &jwk, proof_b64, statement).await
+    }
+
+    /// [`verify`](Self::verify) against a key the caller supplies instead of
+    /// one fetched from `iss`'s JWKS: for air-gapped deployments, tests, or
+    /// keys distributed out of band. `sub` is decimal; `iss` only selects the
+    /// provider's configured modulus size and need not be in the allowlist.
+    ///
+    /// # Errors
+    /// As [`verify`](Self::verify), minus the allowlist and fetch failures.
+    pub async fn verify_with_jwk(
+        &self,
+        iss: &str,
+        sub: &str,
+        jwk: &Jwk,
+        proof_b64: &str,
+        statement: Option<Fr>,
+    ) -> Result<bool> {
+        // 4. Decode proof
+        let proof = base64_to_proof(proof_b64)?;
+        self.verify_against(iss, sub, jwk, None, proof, statement).await
+    }
+
+    /// [`verify`](Self::verify) for a proof the caller already decoded, e.g.
+    /// one submitted as a SnarkJS JSON object rather than base-64.
+    ///
+    /// With `statement`, the last public input (`subStatement`) is that value
+    /// instead of a second copy of `sub`, so the proof is bound to something
+    /// beyond the subject: a server-issued nonce (the proof was made for this
+    /// request and cannot be replayed), an audience hash, etc. Without one the
+    /// circuit's original duplicate-`sub` statement is checked.
+    ///
+    /// `circuit_id` selects the verifying key; without one, the default
+    /// circuit for the key's type is used.
+    ///
+    /// # Errors
+    /// - As [`verify`](Self::verify), minus the decoding failures.
+    /// - Fails with [`UnknownCircuit`] if `circuit_id` is not registered.
+    pub async fn verify_proof(
+        &self,
+        iss: &str,
+        sub: &str,
+        kid: &str,
+        circuit_id: Option<&str>,
+        proof: Proof<Bn254>,
+        statement: Option<Fr>,
+    ) -> Result<bool> {
+        // 1. Issuer key
+        let jwk = self.fetch_key(self.jwks_uri(iss)?, kid).await?;
+        self.verify_against(iss, sub, &jwk, circuit_id, proof, statement).await
+    }
 
-        // 2. RSA modulus → limbs
-        let limbs = chunk_modulus(&jwk.n, CHUNK_BITS)?;
+    /// Steps 2–5 of [`verify_proof`](Self::verify_proof), once the key is
+    /// known.
+    async fn verify_against(
+        &self,
+        iss: &str,
+        sub: &str,
+        jwk: &Jwk,
+        circuit_id: Option<&str>,
+        proof: Proof<Bn254>,
+        statement: Option<Fr>,
+    ) -> Result<bool> {
+        // 2.–3. Key → circuit and public inputs
+        let (pvk, mut public_inputs) = self.statement(iss, sub, jwk, circuit_id)?;
+        if let (Some(statement), Some(last)) = (statement, public_inputs.last_mut()) {
+            *last = statement;
+        }
 
-        // 3. Public inputs
-        // 1) main.sub  (output)  – decimal → Fr
-        let sub_big = BigUint::parse_bytes(sub.as_bytes(), 10)
-            .ok_or_else(|| anyhow!("sub is not valid decimal"))?;
-        let sub_fr  = biguint_to_fr(sub_big);
+        // 5. Verify (using ark‑circom’s reduction). The pairings take
+        //    milliseconds of CPU, so keep them off the async workers.
+        let verified = tokio::task::spawn_blocking(move || {
+            Groth16::<Bn254>::verify_with_processed_vk(&pvk, &public_inputs, &proof)
+        })
+        .await??;
+        Ok(verified)
+    }
 
-        let mut public_inputs = vec![sub_fr];   // IC[1]
+    /// [`verify_proof`](Self::verify_proof) for integrators debugging a
+    /// rejection: the statement is checked piece by piece before the pairing,
+    /// and the first part that is wrong is reported instead of a bare `false`.
+    ///
+    /// The pairing itself cannot say which input diverged, so a proof that
+    /// fails against a well-formed statement is [`VerifyFailure::Proof`].
+    /// The one exception: with a `statement`, a failing proof is checked
+    /// again with `sub` in the last slot, and one that passes that way is
+    /// [`VerifyFailure::SubStatement`].
+    ///
+    /// # Errors
+    /// Only for failures unrelated to the statement: unknown `iss`, JWKS
+    /// fetch errors, unknown `kid`, unknown `circuit_id`, or the blocking
+    /// verification task failing.
+    pub async fn verify_verbose(
+        &self,
+        iss: &str,
+        sub: &str,
+        kid: &str,
+        circuit_id: Option<&str>,
+        proof: Proof<Bn254>,
+        statement: Option<Fr>,
+    ) -> Result<VerifyDiagnostics> {
+        if let Some(id) = circuit_id {
+            ensure!(self.circuits.contains_key(id), UnknownCircuit(id.to_string()));
+        }
+        let jwk = self.fetch_key(self.jwks_uri(iss)?, kid).await?;
+        let circuit = match &jwk.key {
+            JwkKey::Rsa { .. } => Some("RSA"),
+            JwkKey::Ec { .. } => Some("EC"),
+            JwkKey::Unsupported => None,
+        };
+        let fail = |failure| Ok(VerifyDiagnostics { circuit, failure: Some(failure) });
 
-        // 2) main.pubkey[0..16]  – 17 limbs, little-endian
-        public_inputs.extend(limbs.into_iter().map(biguint_to_fr));   // IC[2]..IC[18]
+        // input 0
+        let sub_fr = match sub_to_fr(sub) {
+            Ok(fr) => fr,
+            Err(e) => return fail(VerifyFailure::Sub(format!("{e:#}"))),
+        };
 
-        // 3) main.subStatement   – same value again
-        public_inputs.push(sub_fr);             // IC[19]
+        // inputs 1..last, from the key
+        match &jwk.key {
+            JwkKey::Ec { crv, .. } if crv != "P-256" => {
+                return fail(VerifyFailure::Key(format!("unsupported curve {crv}")));
+            }
+            JwkKey::Unsupported => return fail(VerifyFailure::Key("unsupported kty".into())),
+            _ => {}
+        }
+        let pvk = match self.circuit(circuit_id, &jwk) {
+            Ok(pvk) => pvk,
+            Err(e) => return fail(VerifyFailure::Key(format!("{e:#}"))),
+        };
+        let inputs = match &jwk.key {
+            JwkKey::Rsa { n, .. } => self.rsa_inputs(iss, sub, n, &pvk),
+            JwkKey::Ec { x, y, .. } => ec_public_inputs(sub, x, y, self.chunk_bits),
+            JwkKey::Unsupported => unreachable!("rejected above"),
+        };
+        let mut inputs = match inputs {
+            Ok(inputs) => inputs,
+            Err(e) => return fail(VerifyFailure::Key(format!("{e:#}"))),
+        };
+        let expected = pvk.vk.gamma_abc_g1.len() - 1;
+        if inputs.len() != expected {
+            return fail(VerifyFailure::InputCount { got: inputs.len(), expected });
+        }
 
-        // 4. Decode proof
-        let proof     = base64_to_proof(&proof_b64)?;
+        // last input: subStatement, which the builder sets to `sub`; the
+        // caller's statement replaces it only after that is checked
+        let last = inputs.len() - 1;
+        if inputs[last] != sub_fr {
+            return fail(VerifyFailure::SubStatement { index: last });
+        }
+        let unbound = statement.map(|statement| std::mem::replace(&mut inputs[last], statement));
 
-        // 5. Verify (using ark‑circom’s reduction)
-        let pvk = Groth16::<Bn254>::process_vk(&self.vk)?;
-        let verified = Groth16::<Bn254>::verify_with_processed_vk(&pvk, &public_inputs, &proof)?;
-        Ok(verified)
+        let n_inputs = inputs.len();
+        let failure = tokio::task::spawn_blocking(move || -> Result<Option<VerifyFailure>> {
+            if Groth16::<Bn254>::verify_with_processed_vk(&pvk, &inputs, &proof)? {
+                return Ok(None);
+            }
+            // Made without the statement, e.g. a client that ignored the nonce.
+            if let Some(sub_fr) = unbound {
+                inputs[last] = sub_fr;
+                if Groth16::<Bn254>::verify_with_processed_vk(&pvk, &inputs, &proof)? {
+                    return Ok(Some(VerifyFailure::SubStatement { index: last }));
+                }
+            }
+            Ok(Some(VerifyFailure::Proof { inputs: n_inputs }))
+        })
+        .await??;
+        Ok(VerifyDiagnostics { circuit, failure })
     }
 
-    async fn fetch_google_key(&self, kid: &str) -> Result<Jwk> {
-        let set: JwkSet = self
-            .http
-            .get("https://www.googleapis.com/oauth2/v3/certs")
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await?;
-        set.keys.into_iter()
-            .find(|k| k.kid == kid)
-            .ok_or_else(|| anyhow!("kid '{kid}' not found"))
+    /// Check `claims` against the clock, with the configured skew.
+    ///
+    /// # Errors
+    /// Fails with a [`ClaimsError`] if the token is expired, not yet valid,
+    /// or issued in the future.
+    pub fn check_claims(&self, claims: &JwtClaims) -> Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        claims.check(now, self.clock_skew)?;
+        Ok(())
+    }
+
+    /// Verify an RS256 ID token directly: its signature against the JWK
+    /// `kid` of the issuer named in its `iss` claim, then its audience and
+    /// time claims. No proof is involved, so the server sees the token and
+    /// its `sub`; only for clients that cannot prove, behind
+    /// `--allow-native-jwt`.
+    ///
+    /// # Errors
+    /// - Fails if the token is not three base64url parts, its header is not
+    ///   `RS256`, or names a `kid` other than `kid`.
+    /// - Fails if `iss` is not in the configured allowlist, or with
+    ///   [`JwksUnavailable`] if its JWKS cannot be fetched.
+    /// - Fails if `kid` is unknown or not an RSA key.
+    /// - Fails if the signature does not verify.
+    /// - Fails if `aud` / `azp` name no configured audience; see
+    ///   [`IssuerConfig::check_audience`].
+    /// - Fails with a [`ClaimsError`] if the token is expired, not yet valid,
+    ///   or issued in the future.
+    pub async fn verify_jwt_rs256(&self, token: &str, kid: &str) -> Result<IdTokenClaims> {
+        #[derive(Deserialize)]
+        struct Header {
+            alg: String,
+            #[serde(default)]
+            kid: Option<String>,
+        }
+
+        let mut parts = token.trim().split('.');
+        let (Some(header_b64), Some(claims_b64), Some(sig_b64), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            bail!("token is not a compact JWS");
+        };
+        let header: Header = serde_json::from_slice(&b64::URL_SAFE_NO_PAD.decode(header_b64).context("token header")?)
+            .context("token header")?;
+        ensure!(header.alg == "RS256", "unsupported alg {}", header.alg);
+        if let Some(header_kid) = &header.kid {
+            ensure!(header_kid == kid, "token header names kid '{header_kid}', not '{kid}'");
+        }
+        let claims: IdTokenClaims =
+            serde_json::from_slice(&b64::URL_SAFE_NO_PAD.decode(claims_b64).context("token claims")?)
+                .context("token claims")?;
+        let signature = b64::URL_SAFE_NO_PAD.decode(sig_b64).context("token signature")?;
+
+        let jwk = self.fetch_key(self.jwks_uri(&claims.iss)?, kid).await?;
+        let JwkKey::Rsa { n, e } = &jwk.key else {
+            bail!("key {kid} is not an RSA key");
+        };
+        let n = b64::URL_SAFE_NO_PAD.decode(n).context("JWK modulus")?;
+        let e = b64::URL_SAFE_NO_PAD.decode(e).context("JWK exponent")?;
+        let key = rsa::RsaPublicKey::new(rsa::BigUint::from_bytes_be(&n), rsa::BigUint::from_bytes_be(&e))
+            .context("invalid JWK")?;
+        let signing_input = &token.trim()[..header_b64.len() + 1 + claims_b64.len()];
+        key.verify(rsa::Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(signing_input), &signature)
+            .map_err(|_| anyhow!("token signature does not verify"))?;
+
+        // A genuine token for another client would otherwise mint keys too.
+        self.issuers.check_audience(&claims.aud, claims.azp.as_deref())?;
+        self.check_claims(&claims.time)?;
+        Ok(claims)
+    }
+
+    /// Puts `keys` in the cache as the key set at `jwks_uri`, as if just
+    /// fetched: for keys distributed out of band, benchmarks and tests. They
+    /// expire after the cache TTL like fetched ones (an hour without
+    /// [`with_cache_ttl`](Self::with_cache_ttl)), after which `jwks_uri` is
+    /// fetched again.
+    pub async fn cache_jwks(&self, jwks_uri: &str, keys: Vec<Jwk>) {
+        let ttl = self.cache_ttl.unwrap_or(Duration::from_secs(3600));
+        let cached = CachedJwks::new(keys, ttl);
+        self.jwks.write().await.insert(jwks_uri.to_string(), cached);
+    }
+
+    /// [`verify_proof`](Self::verify_proof) for many proofs of tokens issued
+    /// by `iss` at once.
+    ///
+    /// Each distinct `kid` is looked up once and all well-formed proofs are
+    /// checked with a single randomized batch pairing equation per circuit.
+    /// If a batch fails, its proofs are re-checked one by one to locate the
+    /// bad ones.
+    ///
+    /// # Returns
+    /// One boolean per item, in input order. Items whose key, circuit or
+    /// public inputs cannot be built are `false`.
+    ///
+    /// # Errors
+    /// - Fails if `iss` is not in the configured allowlist.
+    /// - Fails with [`JwksUnavailable`] if `iss`'s key set cannot be fetched;
+    ///   nothing can be said about any item then.
+    pub async fn verify_batch(&self, iss: &str, items: &[BatchItem]) -> Result<Vec<bool>> {
+        let jwks_uri = self.jwks_uri(iss)?;
+
+        // 1. One key lookup per distinct kid
+        let mut keys: HashMap<&str, Option<Jwk>> = HashMap::new();
+        for item in items {
+            if !keys.contains_key(item.kid.as_str()) {
+                let jwk = match self.fetch_key(jwks_uri, &item.kid).await {
+                    Ok(jwk) => Some(jwk),
+                    Err(e) if e.downcast_ref::<JwksUnavailable>().is_some() => return Err(e),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "batch verification: key lookup failed");
+                        None
+                    }
+                };
+                keys.insert(item.kid.as_str(), jwk);
+            }
+        }
+
+        // 2. Circuit and public inputs; anything malformed is simply `false`
+        let prepared: Vec<Option<(Arc<PreparedVerifyingKey<Bn254>>, ProofInstance)>> = items
+            .iter()
+            .map(|item| {
+                let jwk = keys.get(item.kid.as_str())?.as_ref()?;
+                let (pvk, mut inputs) = self.statement(iss, &item.sub, jwk, item.circuit_id.as_deref()).ok()?;
+                if let (Some(statement), Some(last)) = (item.statement, inputs.last_mut()) {
+                    *last = statement;
+                }
+                Some((pvk, (inputs, item.proof.clone())))
+            })
+            .collect();
+
+        // 3. One batch check per circuit, falling back to per-proof checks on
+        //    failure; all pairing work runs on the blocking pool
+        let circuits: Vec<_> = self.circuits.values().cloned().collect();
+        let results = tokio::task::spawn_blocking(move || {
+            let mut results = vec![false; prepared.len()];
+            for pvk in &circuits {
+                let group: Vec<(usize, &ProofInstance)> = prepared
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, item)| match item {
+                        Some((p, stmt)) if Arc::ptr_eq(p, pvk) => Some((i, stmt)),
+                        _ => None,
+                    })
+                    .collect();
+                let batch: Vec<_> = group.iter().map(|(_, stmt)| *stmt).collect();
+                if batch_verify(pvk, &batch).unwrap_or(false) {
+                    for (i, _) in &group {
+                        results[*i] = true;
+                    }
+                    continue;
+                }
+                for (i, (inputs, proof)) in &group {
+                    results[*i] = Groth16::<Bn254>::verify_with_processed_vk(pvk, inputs, proof)
+                        .unwrap_or(false);
+                }
+            }
+            results
+        })
+        .await?;
+        Ok(results)
+    }
+
+    /// The circuit that proves tokens signed by `jwk` (`circuit_id`, or the
+    /// key type's default), and the public inputs binding `sub` to that key.
+    fn statement(
+        &self,
+        iss: &str,
+        sub: &str,
+        jwk: &Jwk,
+        circuit_id: Option<&str>,
+    ) -> Result<(Arc<PreparedVerifyingKey<Bn254>>, Vec<Fr>)> {
+        let pvk = self.circuit(circuit_id, jwk)?;
+        let inputs = match &jwk.key {
+            JwkKey::Rsa { n, .. } => self.rsa_inputs(iss, sub, n, &pvk)?,
+            JwkKey::Ec { crv, x, y } => {
+                ensure!(crv == "P-256", "key {} is on unsupported curve {crv}", jwk.kid);
+                ec_public_inputs(sub, x, y, self.chunk_bits)?
+            }
+            JwkKey::Unsupported => bail!("key {} has an unsupported kty", jwk.kid),
+        };
+        let circuit = circuit_id.unwrap_or(match &jwk.key {
+            JwkKey::Ec { .. } => EC_CIRCUIT_ID,
+            _ => RSA_CIRCUIT_ID,
+        });
+        ensure!(
+            inputs.len() + 1 == pvk.vk.gamma_abc_g1.len(),
+            "key {} yields {} public inputs but circuit '{circuit}' expects {}",
+            jwk.kid,
+            inputs.len(),
+            pvk.vk.gamma_abc_g1.len() - 1
+        );
+        Ok((pvk, inputs))
+    }
+
+    /// [`rsa_public_inputs`] laid out for `pvk`. When `iss` has a configured
+    /// modulus size, the key must have exactly that many bits and the circuit
+    /// exactly `⌈bits / chunk_bits⌉` limbs, so a 4096-bit provider is not
+    /// silently checked against the 2048-bit layout.
+    fn rsa_inputs(&self, iss: &str, sub: &str, n: &str, pvk: &PreparedVerifyingKey<Bn254>) -> Result<Vec<Fr>> {
+        let limbs = rsa_limbs(pvk);
+        if let Some(bits) = self.issuers.modulus_bits(iss) {
+            let n_bits = BigUint::from_bytes_be(&b64::URL_SAFE_NO_PAD.decode(n)?).bits();
+            ensure!(
+                n_bits == bits as u64,
+                "issuer '{iss}' is configured for {bits}-bit RSA keys, but the key has {n_bits} bits"
+            );
+            let expected = bits.div_ceil(self.chunk_bits);
+            ensure!(
+                limbs == expected,
+                "issuer '{iss}' needs {expected} limbs of {} bits for {bits}-bit keys, but the circuit takes {limbs}; \
+                 pass the circuit_id of a matching circuit",
+                self.chunk_bits
+            );
+        }
+        rsa_public_inputs(sub, n, self.chunk_bits, limbs)
+    }
+
+    fn jwks_uri(&self, iss: &str) -> Result<&str> {
+        self.issuers
+            .jwks_uri(iss)
+            .ok_or_else(|| anyhow!("issuer '{iss}' is not in the allowlist"))
+    }
+
+    /// Look `kid` up in the cached key set, refetching it once on a miss (the
+    /// token may use a key rotated in since the last fetch), on an ambiguous
+    /// match, or once it expired.
+    ///
+    /// # Errors
+    /// - Fails if `kid` is still absent after the refetch.
+    /// - Fails if the fresh set lists `kid` more than once.
+    async fn fetch_key(&self, jwks_uri: &str, kid: &str) -> Result<Jwk> {
+        if let Some(jwk) = self.cached_key(jwks_uri, kid).await {
+            return Ok(jwk);
+        }
+
+        let refresh = self.refresh.lock().await.entry(jwks_uri.to_string()).or_default().clone();
+        let _refresh = refresh.lock().await;
+        // Another request may have refreshed the set while we waited.
+        if let Some(jwk) = self.cached_key(jwks_uri, kid).await {
+            return Ok(jwk);
+        }
+
+        let fresh = self.fetch_jwks(jwks_uri).await?;
+        let jwk = fresh.key(kid).map(|k| k.cloned());
+        self.jwks.write().await.insert(jwks_uri.to_string(), fresh);
+        jwk?.ok_or_else(|| anyhow!("kid '{kid}' not found at {jwks_uri}"))
+    }
+
+    /// An unexpired, unambiguous cache hit; anything else goes to the network.
+    async fn cached_key(&self, jwks_uri: &str, kid: &str) -> Option<Jwk> {
+        let cache = self.jwks.read().await;
+        cache
+            .get(jwks_uri)
+            .filter(|c| c.expires_at > Instant::now())
+            .and_then(|c| c.key(kid).ok().flatten().cloned())
+    }
+
+    /// GET the key set, retrying transport errors and 5xx answers with
+    /// exponential backoff. Waits for one of the `fetch_permits` first and
+    /// holds it through the retries.
+    ///
+    /// # Errors
+    /// - [`JwksUnavailable`] if the provider is still unreachable (or
+    ///   answering 5xx) after [`JWKS_FETCH_ATTEMPTS`] tries.
+    /// - Other errors for answers that will not improve on retry (4xx, a
+    ///   body that is not a JWK set).
+    async fn fetch_jwks(&self, jwks_uri: &str) -> Result<CachedJwks> {
+        let _permit = self.fetch_permits.acquire().await.expect("fetch_permits is never closed");
+        let mut backoff = JWKS_RETRY_BACKOFF;
+        let mut attempt = 1;
+        let res = loop {
+            let transient = match self.http.get(jwks_uri).send().await {
+                Ok(res) if res.status().is_server_error() => format!("status {}", res.status()),
+                Ok(res) => break res.error_for_status()?,
+                Err(e) => e.to_string(),
+            };
+            if attempt == JWKS_FETCH_ATTEMPTS {
+                return Err(JwksUnavailable { uri: jwks_uri.to_string(), reason: transient }.into());
+            }
+            tracing::warn!(jwks_uri, attempt, error = %transient, "JWKS fetch failed; retrying");
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        };
+        let ttl = self
+            .cache_ttl
+            .or_else(|| max_age(res.headers()))
+            .unwrap_or(DEFAULT_JWKS_TTL);
+        // The request timeout also covers reading the body.
+        let set: JwkSet = res.json().await.map_err(|e| -> anyhow::Error {
+            if e.is_timeout() {
+                JwksUnavailable { uri: jwks_uri.to_string(), reason: e.to_string() }.into()
+            } else {
+                e.into()
+            }
+        })?;
+        Ok(CachedJwks::new(set.keys, ttl))
     }
 }
 
-fn parse_vk_json(json_str: &str) -> Result<ark_groth16::VerifyingKey<Bn254>> {
+/// `max-age` from a `Cache-Control` header, if present.
+fn max_age(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .find_map(|d| d.trim().strip_prefix("max-age=")?.parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Groth16 public inputs for `sub` under the RSA key with modulus `n`:
+///
+/// ```text
+/// IC[1]      = sub          (main.sub, decimal → Fr)
+/// IC[2..18]  = n_0 … n_16   (main.pubkey, little-endian limbs)
+/// IC[19]     = sub          (main.subStatement; replaced by the caller's statement, if any)
+/// ```
+///
+/// (indices for the default 17 limbs of [`CHUNK_BITS`] bits). A modulus
+/// shorter than `n_limbs · chunk_bits` bits is zero-padded to `n_limbs` limbs; a
+/// longer one cannot be expressed by the circuit and is rejected.
+pub(crate) fn rsa_public_inputs(sub: &str, n: &str, chunk_bits: usize, n_limbs: usize) -> Result<Vec<Fr>> {
+    let mut limbs = chunk_modulus(n, chunk_bits)?;
+    ensure!(
+        limbs.len() <= n_limbs,
+        "RSA modulus needs {} limbs of {chunk_bits} bits, but the circuit takes {n_limbs}",
+        limbs.len()
+    );
+    limbs.resize(n_limbs, BigUint::default());
+    let sub_fr = sub_to_fr(sub)?;
+
+    let mut public_inputs = vec![sub_fr];
+    public_inputs.extend(limbs.into_iter().map(biguint_to_fr));
+    public_inputs.push(sub_fr);
+    Ok(public_inputs)
+}
+
+/// Groth16 public inputs for `sub` under the P-256 key `(x, y)`, for the
+/// ES256 circuit variant:
+///
+/// ```text
+/// IC[1]      = sub
+/// IC[2..5]   = x_0 … x_2    (little-endian limbs)
+/// IC[5..8]   = y_0 … y_2
+/// IC[8]      = sub
+/// ```
+///
+/// (indices for [`CHUNK_BITS`]; each coordinate takes `⌈256 / chunk_bits⌉`
+/// limbs).
+fn ec_public_inputs(sub: &str, x: &str, y: &str, chunk_bits: usize) -> Result<Vec<Fr>> {
+    let sub_fr = sub_to_fr(sub)?;
+
+    let mut public_inputs = vec![sub_fr];
+    for (name, coord) in [("x", x), ("y", y)] {
+        let bytes = b64::URL_SAFE_NO_PAD.decode(coord)?;
+        ensure!(
+            bytes.len() == P256_COORD_LEN,
+            "P-256 {name} must be {P256_COORD_LEN} bytes, got {}",
+            bytes.len()
+        );
+        let mut limbs = chunk_modulus(coord, chunk_bits)?;
+        limbs.resize((P256_COORD_LEN * 8).div_ceil(chunk_bits), BigUint::default());
+        public_inputs.extend(limbs.into_iter().map(biguint_to_fr));
+    }
+    public_inputs.push(sub_fr);
+    Ok(public_inputs)
+}
+
+/// Modulus limbs the RSA circuit `pvk` takes: its IC minus `IC[0]`, `sub`
+/// and `subStatement`.
+fn rsa_limbs(pvk: &PreparedVerifyingKey<Bn254>) -> usize {
+    (pvk.vk.gamma_abc_g1.len() - 1).saturating_sub(RSA_CIRCUIT_EXTRA_INPUTS)
+}
+
+/// Whether the RSA circuit `pvk` has room for a 2048-bit modulus in limbs of
+/// `chunk_bits` bits.
+fn check_rsa_capacity(pvk: &PreparedVerifyingKey<Bn254>, chunk_bits: usize) -> Result<()> {
+    let limbs = rsa_limbs(pvk);
+    ensure!(
+        limbs * chunk_bits >= RSA_MODULUS_BITS,
+        "verifying key has {} IC points, i.e. {limbs} modulus limbs of {chunk_bits} bits, \
+         too few for a {RSA_MODULUS_BITS}-bit RSA modulus",
+        pvk.vk.gamma_abc_g1.len()
+    );
+    Ok(())
+}
+
+/// How a client spells `sub`. The circuit and [`s_from_sub`] take the
+/// decimal string Google issues; other providers' subjects are opaque bytes
+/// that clients tend to send as hex or base64url. Either is read as a
+/// big-endian integer and rewritten in decimal before anything else sees it.
+///
+/// [`s_from_sub`]: crate::keys::s_from_sub
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SubEncoding {
+    /// Base-10 digits, passed through unchanged (the original encoding).
+    #[default]
+    Decimal,
+    /// Base-16 digits, with or without a `0x` prefix.
+    Hex,
+    /// Unpadded URL-safe base-64 of the big-endian bytes.
+    Base64url,
+}
+
+impl SubEncoding {
+    /// `sub`, read in this encoding, as the decimal string the public inputs
+    /// and `s` are built from.
+    ///
+    /// # Errors
+    /// Fails if `sub` is empty or is not valid in this encoding, e.g. a hex
+    /// subject sent without `sub_encoding: "hex"`.
+    pub fn to_decimal(self, sub: &str) -> Result<String> {
+        ensure!(!sub.is_empty(), "sub is empty");
+        let n = match self {
+            SubEncoding::Decimal => {
+                ensure!(sub.bytes().all(|b| b.is_ascii_digit()), "sub is not valid decimal");
+                return Ok(sub.to_string());
+            }
+            SubEncoding::Hex => {
+                let digits = sub.strip_prefix("0x").unwrap_or(sub);
+                BigUint::parse_bytes(digits.as_bytes(), 16).ok_or_else(|| anyhow!("sub is not valid hex"))?
+            }
+            SubEncoding::Base64url => {
+                let bytes = b64::URL_SAFE_NO_PAD.decode(sub).context("sub is not valid base64url")?;
+                BigUint::from_bytes_be(&bytes)
+            }
+        };
+        Ok(n.to_str_radix(10))
+    }
+}
+
+/// `sub` (a decimal string) as a field element.
+fn sub_to_fr(sub: &str) -> Result<Fr> {
+    let sub_big = BigUint::parse_bytes(sub.as_bytes(), 10)
+        .ok_or_else(|| anyhow!("sub is not valid decimal"))?;
+    Ok(biguint_to_fr(sub_big))
+}
+
+/// A proof with the public inputs it is checked against.
+type ProofInstance = (Vec<Fr>, Proof<Bn254>);
+
+/// Randomized batch check of several Groth16 proofs. With fresh random `r_i`,
+///
+/// ```text
+/// Π e(r_i·A_i, B_i) == e(Σ r_i·α, β) · e(Σ r_i·L_i, γ) · e(Σ r_i·C_i, δ)
+/// ```
+///
+/// where `L_i = IC_0 + Σ_j x_ij·IC_j`. One invalid proof makes the equation
+/// fail except with negligible probability.
+fn batch_verify(pvk: &PreparedVerifyingKey<Bn254>, batch: &[&ProofInstance]) -> Result<bool> {
+    let mut rng = OsRng;
+    let mut a_terms = Vec::with_capacity(batch.len());
+    let mut b_terms = Vec::with_capacity(batch.len());
+    let mut r_sum = Fr::zero();
+    let mut inputs_acc = G1Projective::zero();
+    let mut c_acc = G1Projective::zero();
+
+    for (inputs, proof) in batch.iter().copied() {
+        let r = Fr::rand(&mut rng);
+        a_terms.push((proof.a * r).into_affine());
+        b_terms.push(proof.b);
+        r_sum += r;
+        inputs_acc += Groth16::<Bn254>::prepare_inputs(pvk, inputs)? * r;
+        c_acc += proof.c * r;
+    }
+
+    let lhs = Bn254::multi_pairing(a_terms, b_terms);
+    let rhs = Bn254::multi_pairing(
+        [
+            (pvk.vk.alpha_g1 * r_sum).into_affine(),
+            inputs_acc.into_affine(),
+            c_acc.into_affine(),
+        ],
+        [pvk.vk.beta_g2, pvk.vk.gamma_g2, pvk.vk.delta_g2],
+    );
+    Ok(lhs == rhs)
+}
+
+pub(crate) fn parse_vk_json(json_str: &str) -> Result<VerifyingKey<Bn254>> {
     let v: Value = serde_json::from_str(json_str)?;
-    Ok(ark_groth16::VerifyingKey {
+    Ok(VerifyingKey {
         alpha_g1: json_to_g1(&v, "vk_alpha_1")?,
         beta_g2:  json_to_g2(&v, "vk_beta_2")?,
         gamma_g2: json_to_g2(&v, "vk_gamma_2")?,
@@ -153,38 +1350,105 @@ fn parse_vk_json(json_str: &str) -> Result<ark_groth16::VerifyingKey<Bn254>> {
     })
 }
 
+/// The inverse of [`parse_vk_json`]: `vk` in SnarkJS's `verification_key.json`
+/// shape, for sharing with a SnarkJS frontend. Points are projective with
+/// `z = 1` (the identity as SnarkJS writes it), coordinates decimal strings.
+///
+/// `vk_alphabeta_12` is left out: SnarkJS's verifier recomputes
+/// `e(alpha, beta)` rather than reading it, and neither side parses it.
+pub fn vk_to_snarkjs_json(vk: &VerifyingKey<Bn254>) -> Result<String> {
+    let v = serde_json::json!({
+        "protocol": "groth16",
+        "curve": "bn128",
+        "nPublic": vk.gamma_abc_g1.len().saturating_sub(1),
+        "vk_alpha_1": g1_to_json(&vk.alpha_g1),
+        "vk_beta_2": g2_to_json(&vk.beta_g2),
+        "vk_gamma_2": g2_to_json(&vk.gamma_g2),
+        "vk_delta_2": g2_to_json(&vk.delta_g2),
+        "IC": vk.gamma_abc_g1.iter().map(g1_to_json).collect::<Vec<_>>(),
+    });
+    Ok(serde_json::to_string_pretty(&v)?)
+}
+
+/// `[x, y, "1"]`, or `["0", "1", "0"]` for the identity.
+fn g1_to_json(p: &G1Affine) -> Value {
+    match p.xy() {
+        Some((x, y)) => serde_json::json!([fq_to_dec(x), fq_to_dec(y), "1"]),
+        None => serde_json::json!(["0", "1", "0"]),
+    }
+}
+
+/// `[[x.c0, x.c1], [y.c0, y.c1], ["1", "0"]]`, or
+/// `[["0", "0"], ["1", "0"], ["0", "0"]]` for the identity.
+fn g2_to_json(p: &G2Affine) -> Value {
+    let fq2 = |c: &Fq2| serde_json::json!([fq_to_dec(&c.c0), fq_to_dec(&c.c1)]);
+    match p.xy() {
+        Some((x, y)) => serde_json::json!([fq2(x), fq2(y), ["1", "0"]]),
+        None => serde_json::json!([["0", "0"], ["1", "0"], ["0", "0"]]),
+    }
+}
+
+/// The inverse of [`fq_from_dec`].
+fn fq_to_dec(f: &Fq) -> String {
+    BigUint::from(f.into_bigint()).to_string()
+}
 
 fn json_to_g1(v: &Value, key: &str) -> Result<G1Affine> {
-    let coords: Vec<String> = v[key].as_array().ok_or_else(|| anyhow!("{key} not array"))?
-        .iter().map(|s| s.as_str().unwrap().to_string()).collect();
-    Ok(G1Affine::from(G1Projective::new(
-        fq_from_dec(&coords[0])?,
-        fq_from_dec(&coords[1])?,
-        fq_from_dec(&coords[2])?,
-    )))
+    json_g1(&v[key], key)
 }
 
 fn json_to_g1_vec(v: &Value, key: &str) -> Result<Vec<G1Affine>> {
     let list = v[key].as_array().ok_or_else(|| anyhow!("{key} not array"))?;
-    list.iter().map(|triple| {
-        let coords: Vec<String> = triple.as_array().unwrap()
-            .iter().map(|s| s.as_str().unwrap().to_string()).collect();
-        Ok(G1Affine::from(G1Projective::new(
-            fq_from_dec(&coords[0])?,
-            fq_from_dec(&coords[1])?,
-            fq_from_dec(&coords[2])?,
-        )))
-    }).collect()
+    list.iter()
+        .enumerate()
+        .map(|(i, triple)| json_g1(triple, &format!("{key}[{i}]")))
+        .collect()
 }
 
 fn json_to_g2(v: &Value, key: &str) -> Result<G2Affine> {
-    let arr = v[key].as_array().ok_or_else(|| anyhow!("{key} not array"))?;
-    let x = Fq2::new( fq_from_dec(arr[0][0].as_str().unwrap())?, fq_from_dec(arr[0][1].as_str().unwrap())? );
-    let y = Fq2::new( fq_from_dec(arr[1][0].as_str().unwrap())?, fq_from_dec(arr[1][1].as_str().unwrap())? );
-    let z = Fq2::new( fq_from_dec(arr[2][0].as_str().unwrap())?, fq_from_dec(arr[2][1].as_str().unwrap())? );
-    Ok(G2Affine::from(G2Projective::new(x, y, z)))
+    let rows = json_array(&v[key], key, 3)?;
+    let mut fq2 = Vec::with_capacity(3);
+    for (i, row) in rows.iter().enumerate() {
+        let what = format!("{key}[{i}]");
+        let c = json_array(row, &what, 2)?;
+        fq2.push(Fq2::new(
+            json_fq(&c[0], &format!("{what}[0]"))?,
+            json_fq(&c[1], &format!("{what}[1]"))?,
+        ));
+    }
+    let p = G2Projective::new_unchecked(fq2[0], fq2[1], fq2[2]);
+    let p = G2Affine::from(p);
+    ensure!(p.is_on_curve(), "{key} is not on the curve");
+    Ok(p)
+}
+
+/// SnarkJS projective G1 point `[x, y, z]` as decimal strings.
+fn json_g1(v: &Value, what: &str) -> Result<G1Affine> {
+    let c = json_array(v, what, 3)?;
+    let p = G1Projective::new_unchecked(
+        json_fq(&c[0], &format!("{what}[0]"))?,
+        json_fq(&c[1], &format!("{what}[1]"))?,
+        json_fq(&c[2], &format!("{what}[2]"))?,
+    );
+    let p = G1Affine::from(p);
+    ensure!(p.is_on_curve(), "{what} is not on the curve");
+    Ok(p)
+}
+
+fn json_array<'a>(v: &'a Value, what: &str, len: usize) -> Result<&'a [Value]> {
+    let arr = v.as_array().ok_or_else(|| anyhow!("{what} not array"))?;
+    ensure!(arr.len() == len, "{what} has {} elements, expected {len}", arr.len());
+    Ok(arr)
 }
 
+/// A decimal-string coordinate; numbers, nulls etc. are errors, not panics.
+fn json_fq(v: &Value, what: &str) -> Result<Fq> {
+    let s = v.as_str().ok_or_else(|| anyhow!("{what} is not a string (got {v})"))?;
+    fq_from_dec(s).with_context(|| what.to_string())
+}
+
+/// A base-10 string as an `Fq`. No byte order is involved: the digits are
+/// parsed into a `BigUint` and converted limb-wise, so `"1"` is `Fq::one()`.
 fn fq_from_dec(s: &str) -> Result<Fq> {
     // 1. parse the decimal string
     let n = BigUint::parse_bytes(s.as_bytes(), 10)
@@ -199,24 +1463,939 @@ fn fq_from_dec(s: &str) -> Result<Fq> {
         .ok_or_else(|| anyhow!("integer is not a canonical Fq element"))
 }
 
+/// Split a base64url integer into `chunk_bits`-bit limbs, least significant
+/// limb first, so that `n = Σ limbs[i] · 2^(i·chunk_bits)`.
+///
+/// Two byte orders meet here, both fixed by their specs:
+/// - the JWK encodes `n` (RFC 7518 §6.3.1) and EC `x` / `y` (§6.2.1) as
+///   big-endian bytes, hence `from_bytes_be`;
+/// - the circuit's input generator takes limbs little-endian (`n_0` is the
+///   low limb, `IC[2]`).
+///
+/// Trailing zero limbs are not emitted; callers pad to the circuit's count.
 fn chunk_modulus(n_b64url: &str, chunk_bits: usize) -> Result<Vec<BigUint>> {
     let n_bytes = b64::URL_SAFE_NO_PAD.decode(n_b64url)?;
     let mut n = BigUint::from_bytes_be(&n_bytes);
     let mask = (BigUint::from(1u32) << chunk_bits) - BigUint::from(1u32);
+    let low_bits = &n & &mask;
     let mut limbs = Vec::new();
     while n > BigUint::default() {
         limbs.push(&n & &mask);
         n >>= chunk_bits;
     }
+    // A byte-order slip would not fail anywhere else, only make every proof
+    // invalid, so check it in release builds too.
+    ensure!(
+        limbs.first().cloned().unwrap_or_default() == low_bits,
+        "limb 0 of the modulus does not hold its least significant bits"
+    );
     Ok(limbs)
 }
 
-fn hash_to_fr(data: &[u8]) -> Fr {
-    let mut tmp = [0u8; 32];
-    tmp.copy_from_slice(blake3::hash(data).as_bytes());
-    Fr::from_le_bytes_mod_order(&tmp)
-}
-
+/// A non-negative integer as an `Fr`, reduced mod `r`. `to_bytes_le` and
+/// `from_le_bytes_mod_order` agree on little-endian, so the value is kept
+/// exactly whenever `x < r` (every limb of at most [`MAX_CHUNK_BITS`] bits,
+/// and `sub`, which is far shorter).
 fn biguint_to_fr(x: BigUint) -> Fr {
     Fr::from_le_bytes_mod_order(&x.to_bytes_le())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::test_circuit::TestCircuit;
+    use crate::test_tokens::{id_token_claims, sign_id_token, signing_jwk, CLIENT_ID};
+
+    /// Inputs of the default RSA layout: `sub`, 17 limbs, `subStatement`.
+    fn rsa_input_count() -> usize {
+        RSA_MODULUS_BITS.div_ceil(CHUNK_BITS) + RSA_CIRCUIT_EXTRA_INPUTS
+    }
+
+    /// A base64url modulus of exactly `bits` bits (a multiple of 8).
+    fn modulus_b64(bits: usize) -> String {
+        let mut bytes = vec![0xa5u8; bits / 8];
+        bytes[0] |= 0x80;
+        b64::URL_SAFE_NO_PAD.encode(bytes)
+    }
+
+    const TEST_KID: &str = "test-kid";
+
+    /// Puts `keys` in `verifier`'s cache as Google's key set, so nothing is
+    /// fetched.
+    async fn seed_google_jwks(verifier: &Verifier, keys: Vec<Jwk>) {
+        verifier.cache_jwks(GOOGLE_CERTS_URL, keys).await;
+    }
+
+    /// Issuer whose JWKS a [`MockJwks`] serves.
+    const MOCK_ISSUER: &str = "https://issuer.test";
+
+    /// JSON of an RSA JWK `kid` with the [`modulus_b64`] test modulus.
+    fn rsa_jwk_json(kid: &str) -> Value {
+        serde_json::json!({ "kty": "RSA", "kid": kid, "alg": "RS256", "n": modulus_b64(RSA_MODULUS_BITS), "e": "AQAB" })
+    }
+
+    /// A JWKS endpoint on a local port, answering every request with `keys`
+    /// and `Cache-Control: max-age=3600` after `delay`.
+    struct MockJwks {
+        uri: String,
+        /// The `keys` array served; swap it to rotate keys.
+        keys: Arc<std::sync::Mutex<Vec<Value>>>,
+        /// HTTP status of the answers, 200 unless set.
+        status: Arc<std::sync::atomic::AtomicU16>,
+        /// Requests received so far.
+        hits: Arc<AtomicUsize>,
+        /// Most requests ever being answered at once.
+        peak: Arc<AtomicUsize>,
+    }
+
+    impl MockJwks {
+        async fn start(keys: Vec<Value>, delay: Duration) -> Self {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let uri = format!("http://{}/certs", listener.local_addr().unwrap());
+            let mock = MockJwks {
+                uri,
+                keys: Arc::new(std::sync::Mutex::new(keys)),
+                status: Arc::new(200.into()),
+                hits: Arc::default(),
+                peak: Arc::default(),
+            };
+            let (keys, status, hits, peak) = (mock.keys.clone(), mock.status.clone(), mock.hits.clone(), mock.peak.clone());
+            let in_flight = Arc::new(AtomicUsize::new(0));
+            tokio::spawn(async move {
+                while let Ok((mut stream, _)) = listener.accept().await {
+                    let (keys, status, hits, peak, in_flight) =
+                        (keys.clone(), status.clone(), hits.clone(), peak.clone(), in_flight.clone());
+                    tokio::spawn(async move {
+                        let mut request = Vec::new();
+                        let mut buf = [0u8; 1024];
+                        while !request.ends_with(b"\r\n\r\n") {
+                            match stream.read(&mut buf).await {
+                                Ok(0) | Err(_) => return,
+                                Ok(n) => request.extend_from_slice(&buf[..n]),
+                            }
+                        }
+                        hits.fetch_add(1, Ordering::SeqCst);
+                        let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        tokio::time::sleep(delay).await;
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        let body = serde_json::json!({ "keys": *keys.lock().unwrap() }).to_string();
+                        let response = format!(
+                            "HTTP/1.1 {} Mock\r\ncontent-type: application/json\r\ncache-control: public, max-age=3600\r\n\
+                             content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                            status.load(Ordering::SeqCst),
+                            body.len()
+                        );
+                        let _ = stream.write_all(response.as_bytes()).await;
+                    });
+                }
+            });
+            mock
+        }
+
+        fn hits(&self) -> usize {
+            self.hits.load(Ordering::SeqCst)
+        }
+
+        /// A verifier for [`MOCK_ISSUER`] fetching from this endpoint.
+        async fn verifier(&self) -> Verifier {
+            let issuers = IssuerConfig { providers: vec![(MOCK_ISSUER.to_string(), self.uri.clone())], ..IssuerConfig::default() };
+            Verifier::new(issuers).await.unwrap()
+        }
+    }
+
+    /// A verifier whose RSA circuit is a fresh [`TestCircuit`], with a
+    /// 2048-bit Google key [`TEST_KID`] already in its JWKS cache.
+    async fn rsa_test_verifier() -> (Verifier, TestCircuit) {
+        let circuit = TestCircuit::new(rsa_input_count());
+        let verifier = Verifier::with_vk_json(&circuit.vk_json(), IssuerConfig::default()).unwrap();
+        seed_google_jwks(&verifier, vec![Jwk::rsa(TEST_KID, modulus_b64(RSA_MODULUS_BITS), "AQAB")]).await;
+        (verifier, circuit)
+    }
+
+    /// The public inputs [`rsa_test_verifier`] builds for `sub`.
+    fn test_key_inputs(sub: &str) -> Vec<Fr> {
+        let limbs = RSA_MODULUS_BITS.div_ceil(CHUNK_BITS);
+        rsa_public_inputs(sub, &modulus_b64(RSA_MODULUS_BITS), CHUNK_BITS, limbs).unwrap()
+    }
+
+    #[test]
+    fn claims_are_checked_against_now_within_the_skew() {
+        const NOW: u64 = 1_700_000_000;
+        let skew = Duration::from_secs(60);
+        let claims = |iat, exp, nbf| JwtClaims { iat, exp, nbf };
+
+        // valid, and valid up to exactly `skew` past `exp` / before `iat`, `nbf`
+        assert_eq!(claims(NOW - 10, NOW + 10, Some(NOW - 10)).check(NOW, skew), Ok(()));
+        assert_eq!(claims(NOW - 3600, NOW - 60, None).check(NOW, skew), Ok(()));
+        assert_eq!(claims(NOW + 60, NOW + 3600, Some(NOW + 60)).check(NOW, skew), Ok(()));
+
+        // one second further is rejected
+        assert_eq!(
+            claims(NOW - 3600, NOW - 61, None).check(NOW, skew),
+            Err(ClaimsError::Expired { exp: NOW - 61, now: NOW })
+        );
+        assert_eq!(
+            claims(NOW, NOW + 3600, Some(NOW + 61)).check(NOW, skew),
+            Err(ClaimsError::NotYetValid { nbf: NOW + 61, now: NOW })
+        );
+        assert_eq!(
+            claims(NOW + 61, NOW + 3600, None).check(NOW, skew),
+            Err(ClaimsError::IssuedInFuture { iat: NOW + 61, now: NOW })
+        );
+
+        // without skew, the boundaries themselves
+        assert_eq!(claims(NOW, NOW, Some(NOW)).check(NOW, Duration::ZERO), Ok(()));
+        assert!(claims(NOW, NOW - 1, None).check(NOW, Duration::ZERO).is_err());
+
+        // near the end of time the skew saturates instead of overflowing
+        assert_eq!(claims(0, u64::MAX, None).check(u64::MAX, skew), Ok(()));
+    }
+
+    #[test]
+    fn modulus_too_long_for_the_circuit_is_a_descriptive_error() {
+        let limbs = RSA_MODULUS_BITS.div_ceil(CHUNK_BITS);
+        let err = rsa_public_inputs("1", &modulus_b64(4096), CHUNK_BITS, limbs).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("RSA modulus needs {} limbs of {CHUNK_BITS} bits, but the circuit takes {limbs}", 4096usize.div_ceil(CHUNK_BITS))
+        );
+    }
+
+    #[test]
+    fn shorter_modulus_is_zero_padded_to_the_circuit() {
+        let limbs = RSA_MODULUS_BITS.div_ceil(CHUNK_BITS);
+        let inputs = rsa_public_inputs("1", &modulus_b64(1024), CHUNK_BITS, limbs).unwrap();
+        assert_eq!(inputs.len(), limbs + RSA_CIRCUIT_EXTRA_INPUTS);
+        let used = 1024usize.div_ceil(CHUNK_BITS);
+        assert!(inputs[1 + used..=limbs].iter().all(Fr::is_zero));
+    }
+
+    #[test]
+    fn configured_modulus_size_rejects_other_key_sizes() {
+        let mut issuers = IssuerConfig::default();
+        issuers.modulus_bits.insert(GOOGLE_ISSUER.to_string(), RSA_MODULUS_BITS);
+        let verifier = Verifier::with_vk_json(VK_JSON, issuers).unwrap();
+        let pvk = verifier.circuits[RSA_CIRCUIT_ID].clone();
+
+        assert!(verifier.rsa_inputs(GOOGLE_ISSUER, "1", &modulus_b64(2048), &pvk).is_ok());
+        let err = verifier.rsa_inputs(GOOGLE_ISSUER, "1", &modulus_b64(1024), &pvk).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("issuer '{GOOGLE_ISSUER}' is configured for 2048-bit RSA keys, but the key has 1024 bits")
+        );
+    }
+
+    #[test]
+    fn chunk_modulus_is_little_endian_limbs_of_a_big_endian_integer() {
+        // 0x0102_0304 in the JWK's big-endian bytes
+        let n = b64::URL_SAFE_NO_PAD.encode([0x01, 0x02, 0x03, 0x04]);
+        let limbs = chunk_modulus(&n, 8).unwrap();
+        let expected: Vec<BigUint> = [0x04u32, 0x03, 0x02, 0x01].map(BigUint::from).into();
+        assert_eq!(limbs, expected);
+
+        // Limbs that straddle bytes: 0x0102_0304 = 0x304 + 0x020·2^12 + 0x01·2^24
+        let limbs = chunk_modulus(&n, 12).unwrap();
+        let expected: Vec<BigUint> = [0x304u32, 0x020, 0x001].map(BigUint::from).into();
+        assert_eq!(limbs, expected);
+
+        // Leading zero bytes do not add limbs; zero has none.
+        let padded = b64::URL_SAFE_NO_PAD.encode([0x00, 0x00, 0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(chunk_modulus(&padded, 8).unwrap().len(), 4);
+        assert!(chunk_modulus(&b64::URL_SAFE_NO_PAD.encode([0x00]), 8).unwrap().is_empty());
+    }
+
+    #[test]
+    fn biguint_to_fr_keeps_the_value() {
+        assert_eq!(biguint_to_fr(BigUint::from(0u32)), Fr::zero());
+        assert_eq!(biguint_to_fr(BigUint::from(1u32)), Fr::from(1u64));
+        assert_eq!(biguint_to_fr(BigUint::from(0x0102_0304u32)), Fr::from(0x0102_0304u64));
+        let two_64 = BigUint::from(1u32) << 64;
+        assert_eq!(biguint_to_fr(two_64 + 5u32), Fr::from(u64::MAX) + Fr::from(6u64));
+        // r ≡ 0
+        assert_eq!(biguint_to_fr(BigUint::from(Fr::MODULUS)), Fr::zero());
+    }
+
+    #[test]
+    fn fq_from_dec_reads_base_ten() {
+        assert_eq!(fq_from_dec("0").unwrap(), Fq::zero());
+        assert_eq!(fq_from_dec("1").unwrap(), Fq::from(1u64));
+        assert_eq!(fq_from_dec("16909060").unwrap(), Fq::from(0x0102_0304u64));
+        assert_eq!(fq_from_dec("18446744073709551616").unwrap(), Fq::from(u64::MAX) + Fq::from(1u64));
+        // not canonical: q itself, and anything wider than 256 bits
+        assert!(fq_from_dec(&BigUint::from(Fq::MODULUS).to_string()).is_err());
+        assert!(fq_from_dec(&(BigUint::from(1u32) << 256usize).to_string()).is_err());
+        assert!(fq_from_dec("0x01").is_err());
+        assert_eq!(fq_to_dec(&fq_from_dec("16909060").unwrap()), "16909060");
+    }
+
+    /// The cost [`Verifier`] avoids by preparing each verifying key once:
+    /// `Groth16::verify` runs `process_vk` (a pairing and two G2
+    /// preparations) before every check. Timing only means something in a
+    /// release build:
+    ///
+    /// ```text
+    /// cargo test --release prepared_vk -- --ignored --nocapture
+    /// ```
+    #[test]
+    #[ignore = "timing; run in release with --ignored"]
+    fn prepared_vk_speeds_up_1000_sequential_verifications() {
+        const N: u32 = 1000;
+        let circuit = TestCircuit::new(rsa_input_count());
+        let inputs: Vec<Fr> = (0..circuit.n_inputs() as u64).map(Fr::from).collect();
+        let proof = circuit.prove(&inputs);
+        let pvk = Groth16::<Bn254>::process_vk(&circuit.vk).unwrap();
+
+        let start = Instant::now();
+        for _ in 0..N {
+            assert!(Groth16::<Bn254>::verify(&circuit.vk, &inputs, &proof).unwrap());
+        }
+        let per_request = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..N {
+            assert!(Groth16::<Bn254>::verify_with_processed_vk(&pvk, &inputs, &proof).unwrap());
+        }
+        let prepared = start.elapsed();
+
+        eprintln!(
+            "{N} verifications: process_vk per request {:?} ({:?} each), prepared once {:?} ({:?} each)",
+            per_request,
+            per_request / N,
+            prepared,
+            prepared / N,
+        );
+        assert!(prepared < per_request);
+    }
+
+    /// p99 of how late a 1 ms timer fires on this (single-threaded, like an
+    /// actix worker) runtime while `load` runs on it: the extra wait a cheap
+    /// request such as `/healthz` sees on a busy worker.
+    async fn timer_lateness_p99<F: std::future::Future<Output = ()> + 'static>(
+        load: impl IntoIterator<Item = F>,
+    ) -> Duration {
+        const TICK: Duration = Duration::from_millis(1);
+        let tasks: Vec<_> = load.into_iter().map(actix_web::rt::spawn).collect();
+        let mut lateness = Vec::new();
+        while !tasks.iter().all(|t| t.is_finished()) {
+            let start = Instant::now();
+            actix_web::rt::time::sleep(TICK).await;
+            lateness.push(start.elapsed().saturating_sub(TICK));
+        }
+        lateness.sort();
+        lateness[(lateness.len() * 99).div_ceil(100) - 1]
+    }
+
+    /// What moving the pairings to the blocking pool buys: `N` concurrent
+    /// verifications on one worker, once run inline on it (as before) and
+    /// once through [`Verifier::verify_proof`]. Numbers are in the README;
+    /// reproduce them with
+    ///
+    /// ```text
+    /// cargo test --release p99 -- --ignored --nocapture
+    /// ```
+    #[actix_web::test]
+    #[ignore = "timing; run in release with --ignored"]
+    async fn p99_latency_beside_64_concurrent_verifications() {
+        const N: usize = 64;
+        const SUB: &str = "113048723091228773641";
+        let (verifier, circuit) = rsa_test_verifier().await;
+        let inputs = test_key_inputs(SUB);
+        let proof = circuit.prove(&inputs);
+        let pvk = verifier.circuits[RSA_CIRCUIT_ID].clone();
+
+        let inline = timer_lateness_p99((0..N).map(|_| {
+            let (pvk, inputs, proof) = (pvk.clone(), inputs.clone(), proof.clone());
+            async move {
+                assert!(Groth16::<Bn254>::verify_with_processed_vk(&pvk, &inputs, &proof).unwrap());
+            }
+        }))
+        .await;
+
+        let verifier = Arc::new(verifier);
+        let offloaded = timer_lateness_p99((0..N).map(|_| {
+            let (verifier, proof) = (verifier.clone(), proof.clone());
+            async move {
+                let verified = verifier.verify_proof(GOOGLE_ISSUER, SUB, TEST_KID, None, proof, None);
+                assert!(verified.await.unwrap());
+            }
+        }))
+        .await;
+
+        eprintln!("p99 extra latency beside {N} verifications: inline {inline:?}, blocking pool {offloaded:?}");
+        assert!(offloaded < inline);
+    }
+
+    const SUB: &str = "113048723091228773641";
+
+    #[test]
+    fn every_sub_encoding_of_one_subject_gives_the_same_element() {
+        let n = BigUint::parse_bytes(SUB.as_bytes(), 10).unwrap();
+        let hex = n.to_str_radix(16);
+        let b64url = b64::URL_SAFE_NO_PAD.encode(n.to_bytes_be());
+        let spellings = [
+            (SubEncoding::Decimal, SUB.to_string()),
+            (SubEncoding::Hex, hex.clone()),
+            (SubEncoding::Hex, format!("0x{hex}")),
+            (SubEncoding::Hex, hex.to_uppercase()),
+            (SubEncoding::Base64url, b64url.clone()),
+        ];
+        for (encoding, sub) in &spellings {
+            let decimal = encoding.to_decimal(sub).unwrap();
+            assert_eq!(decimal, SUB, "{encoding:?} {sub}");
+            assert_eq!(sub_to_fr(&decimal).unwrap(), sub_to_fr(SUB).unwrap());
+        }
+
+        // a subject sent in the wrong encoding fails rather than being
+        // silently read as another number
+        for (encoding, sub, message) in [
+            (SubEncoding::Decimal, hex.as_str(), "sub is not valid decimal"),
+            (SubEncoding::Decimal, "-1", "sub is not valid decimal"),
+            (SubEncoding::Hex, "0xg1", "sub is not valid hex"),
+            (SubEncoding::Hex, "0x", "sub is not valid hex"),
+            (SubEncoding::Base64url, "a+b/", "sub is not valid base64url"),
+            (SubEncoding::Base64url, &format!("{b64url}="), "sub is not valid base64url"),
+            (SubEncoding::Hex, "", "sub is empty"),
+        ] {
+            let err = encoding.to_decimal(sub).unwrap_err();
+            assert_eq!(err.to_string(), message, "{encoding:?} {sub:?}");
+        }
+    }
+
+    /// Without a statement the last input is `sub` again; with one it is
+    /// that statement, so each proof verifies in exactly one of the modes.
+    #[actix_web::test]
+    async fn verify_proof_binds_the_statement_when_given() {
+        let (verifier, circuit) = rsa_test_verifier().await;
+        let nonce = Fr::from(0x5eed_u64);
+        let plain = circuit.prove(&test_key_inputs(SUB));
+        let mut bound_inputs = test_key_inputs(SUB);
+        *bound_inputs.last_mut().unwrap() = nonce;
+        let bound = circuit.prove(&bound_inputs);
+        let verify = |proof: &Proof<Bn254>, statement| {
+            verifier.verify_proof(GOOGLE_ISSUER, SUB, TEST_KID, None, proof.clone(), statement)
+        };
+
+        assert!(verify(&plain, None).await.unwrap());
+        assert!(!verify(&plain, Some(nonce)).await.unwrap());
+        assert!(verify(&bound, Some(nonce)).await.unwrap());
+        assert!(!verify(&bound, None).await.unwrap());
+        assert!(!verify(&bound, Some(nonce + Fr::from(1u64))).await.unwrap());
+        // `sub` itself is still the first input either way.
+        let other = verifier.verify_proof(GOOGLE_ISSUER, "42", TEST_KID, None, bound, Some(nonce));
+        assert!(!other.await.unwrap());
+    }
+
+    #[actix_web::test]
+    async fn verify_verbose_accepts_a_valid_proof() {
+        let (verifier, circuit) = rsa_test_verifier().await;
+        let proof = circuit.prove(&test_key_inputs(SUB));
+        let diag = verifier.verify_verbose(GOOGLE_ISSUER, SUB, TEST_KID, None, proof, None).await.unwrap();
+        assert_eq!(diag, VerifyDiagnostics { circuit: Some("RSA"), failure: None });
+
+        // bound to a statement
+        let (verifier, circuit) = rsa_test_verifier().await;
+        let statement = Fr::from(7u64);
+        let mut inputs = test_key_inputs(SUB);
+        *inputs.last_mut().unwrap() = statement;
+        let proof = circuit.prove(&inputs);
+        let diag = verifier.verify_verbose(GOOGLE_ISSUER, SUB, TEST_KID, None, proof, Some(statement));
+        assert!(diag.await.unwrap().is_valid());
+    }
+
+    #[actix_web::test]
+    async fn verify_verbose_reports_a_non_decimal_sub() {
+        let (verifier, circuit) = rsa_test_verifier().await;
+        let proof = circuit.prove(&test_key_inputs(SUB));
+        let diag = verifier.verify_verbose(GOOGLE_ISSUER, "0xabc", TEST_KID, None, proof, None).await.unwrap();
+        assert_eq!(diag.failure, Some(VerifyFailure::Sub("sub is not valid decimal".into())));
+    }
+
+    #[actix_web::test]
+    async fn verify_verbose_reports_a_key_the_circuit_cannot_take() {
+        let (verifier, circuit) = rsa_test_verifier().await;
+        seed_google_jwks(&verifier, vec![Jwk::rsa("rsa-4096", modulus_b64(4096), "AQAB")]).await;
+        let proof = circuit.prove(&test_key_inputs(SUB));
+        let diag = verifier.verify_verbose(GOOGLE_ISSUER, SUB, "rsa-4096", None, proof, None).await.unwrap();
+        assert!(matches!(&diag.failure, Some(VerifyFailure::Key(e)) if e.contains("limbs")), "{diag:?}");
+    }
+
+    #[actix_web::test]
+    async fn verify_verbose_reports_an_input_count_mismatch() {
+        let circuit = TestCircuit::new(rsa_input_count());
+        let ec_circuit = TestCircuit::new(5);
+        let verifier = Verifier::with_vk_json(&circuit.vk_json(), IssuerConfig::default())
+            .and_then(|v| v.with_ec_verifying_key(&ec_circuit.vk_json()))
+            .unwrap();
+        let coord = b64::URL_SAFE_NO_PAD.encode([0x11u8; P256_COORD_LEN]);
+        let ec: Jwk = serde_json::from_value(serde_json::json!({
+            "kid": "ec", "kty": "EC", "crv": "P-256", "x": coord, "y": coord,
+        }))
+        .unwrap();
+        seed_google_jwks(&verifier, vec![ec]).await;
+
+        let proof = ec_circuit.prove(&[Fr::from(1u64); 5]);
+        let diag = verifier.verify_verbose(GOOGLE_ISSUER, SUB, "ec", None, proof, None).await.unwrap();
+        assert_eq!(diag.circuit, Some("EC"));
+        assert_eq!(diag.failure, Some(VerifyFailure::InputCount { got: 8, expected: 5 }));
+    }
+
+    /// The P-256 key of RFC 7517, appendix A.1.
+    const EC_FIXTURE_JWK: &str = include_str!("../tests/fixtures/ec_jwk.json");
+
+    /// `ec_public_inputs` lays a real P-256 key out as `sub`, three 121-bit
+    /// little-endian limbs of `x`, three of `y`, `sub`; a proof over that
+    /// layout verifies through the EC circuit and binds `sub`.
+    #[actix_web::test]
+    async fn ec_fixture_key_verifies_a_proof_over_its_coordinates() {
+        let jwk: Value = serde_json::from_str(EC_FIXTURE_JWK).unwrap();
+        let limbs = |coord: &str| -> Vec<Fr> {
+            let n = BigUint::from_bytes_be(&b64::URL_SAFE_NO_PAD.decode(coord).unwrap());
+            let mask = (BigUint::from(1u8) << CHUNK_BITS) - 1u8;
+            (0..3).map(|i| biguint_to_fr((&n >> (i * CHUNK_BITS)) & &mask)).collect()
+        };
+        let sub = sub_to_fr(SUB).unwrap();
+        let mut inputs = vec![sub];
+        inputs.extend(limbs(jwk["x"].as_str().unwrap()));
+        inputs.extend(limbs(jwk["y"].as_str().unwrap()));
+        inputs.push(sub);
+        let (x, y) = (jwk["x"].as_str().unwrap(), jwk["y"].as_str().unwrap());
+        assert_eq!(ec_public_inputs(SUB, x, y, CHUNK_BITS).unwrap(), inputs);
+
+        let ec_circuit = TestCircuit::new(inputs.len());
+        let verifier = Verifier::with_vk_json(VK_JSON, IssuerConfig::default())
+            .and_then(|v| v.with_ec_verifying_key(&ec_circuit.vk_json()))
+            .unwrap();
+        seed_google_jwks(&verifier, vec![serde_json::from_value(jwk).unwrap()]).await;
+        let proof = proof::proof_to_base64_compressed(&ec_circuit.prove(&inputs)).unwrap();
+
+        assert!(verifier.verify(GOOGLE_ISSUER, SUB, "1", SubEncoding::Decimal, &proof, None).await.unwrap());
+        assert!(!verifier.verify(GOOGLE_ISSUER, "42", "1", SubEncoding::Decimal, &proof, None).await.unwrap());
+    }
+
+    #[actix_web::test]
+    async fn verify_verbose_reports_a_proof_made_without_the_statement() {
+        let (verifier, circuit) = rsa_test_verifier().await;
+        let proof = circuit.prove(&test_key_inputs(SUB));
+        let diag = verifier.verify_verbose(GOOGLE_ISSUER, SUB, TEST_KID, None, proof, Some(Fr::from(7u64)));
+        let last = rsa_input_count() - 1;
+        assert_eq!(diag.await.unwrap().failure, Some(VerifyFailure::SubStatement { index: last }));
+    }
+
+    #[actix_web::test]
+    async fn verify_verbose_reports_a_proof_for_another_statement() {
+        let (verifier, circuit) = rsa_test_verifier().await;
+        let proof = circuit.prove(&test_key_inputs("42"));
+        let diag = verifier.verify_verbose(GOOGLE_ISSUER, SUB, TEST_KID, None, proof.clone(), None).await;
+        let inputs = rsa_input_count();
+        assert_eq!(diag.unwrap().failure, Some(VerifyFailure::Proof { inputs }));
+
+        // With a statement, a proof that fails with `sub` too is still the proof's fault.
+        let diag = verifier.verify_verbose(GOOGLE_ISSUER, SUB, TEST_KID, None, proof, Some(Fr::from(7u64)));
+        assert_eq!(diag.await.unwrap().failure, Some(VerifyFailure::Proof { inputs }));
+    }
+
+    #[actix_web::test]
+    async fn cache_ttl_overrides_max_age_and_expired_sets_are_refetched() {
+        let mock = MockJwks::start(vec![rsa_jwk_json(TEST_KID)], Duration::ZERO).await;
+        let verifier = mock.verifier().await.with_cache_ttl(Duration::from_millis(200));
+        verifier.fetch_key(&mock.uri, TEST_KID).await.unwrap();
+        verifier.fetch_key(&mock.uri, TEST_KID).await.unwrap();
+        assert_eq!(mock.hits(), 1, "served from the cache within the TTL");
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(verifier.cached_key(&mock.uri, TEST_KID).await.is_none(), "expired despite max-age=3600");
+        verifier.fetch_key(&mock.uri, TEST_KID).await.unwrap();
+        assert_eq!(mock.hits(), 2, "refetched once expired");
+        verifier.fetch_key(&mock.uri, TEST_KID).await.unwrap();
+        assert_eq!(mock.hits(), 2, "the refetched set is cached again");
+
+        // Without the override, the provider's max-age holds.
+        let verifier = mock.verifier().await;
+        verifier.fetch_key(&mock.uri, TEST_KID).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        verifier.fetch_key(&mock.uri, TEST_KID).await.unwrap();
+        assert_eq!(mock.hits(), 3);
+    }
+
+    #[actix_web::test]
+    async fn concurrent_misses_share_one_fetch() {
+        let mock = MockJwks::start(vec![rsa_jwk_json(TEST_KID)], Duration::from_millis(100)).await;
+        let verifier = Arc::new(mock.verifier().await.with_cache_ttl(Duration::from_secs(60)));
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let (verifier, uri) = (verifier.clone(), mock.uri.clone());
+                tokio::spawn(async move { verifier.fetch_key(&uri, TEST_KID).await.map(|jwk| jwk.kid) })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), TEST_KID);
+        }
+        assert_eq!(mock.hits(), 1);
+    }
+
+    #[test]
+    fn cached_set_finds_one_key_per_kid_and_flags_duplicates() {
+        let key = |kid: &str, e: &str| Jwk::rsa(kid, modulus_b64(RSA_MODULUS_BITS), e);
+        let set = CachedJwks::new(vec![key("a", "AQAB"), key("b", "AQAB"), key("b", "Aw")], Duration::from_secs(60));
+        assert_eq!(set.key("a").unwrap().unwrap().kid, "a");
+        assert!(set.key("c").unwrap().is_none());
+        let err = set.key("b").unwrap_err();
+        assert_eq!(err.to_string(), "kid 'b' matches 2 keys in the JWK set");
+    }
+
+    /// A cached kid is served without a request; a missing one refetches
+    /// once, finding a key rotated in since or failing by name; a duplicated
+    /// one is never picked arbitrarily.
+    #[actix_web::test]
+    async fn fetch_key_refetches_on_a_miss_and_rejects_duplicate_kids() {
+        let mock = MockJwks::start(vec![rsa_jwk_json("k1")], Duration::ZERO).await;
+        let verifier = mock.verifier().await;
+
+        assert_eq!(verifier.fetch_key(&mock.uri, "k1").await.unwrap().kid, "k1");
+        assert_eq!(verifier.fetch_key(&mock.uri, "k1").await.unwrap().kid, "k1");
+        assert_eq!(mock.hits(), 1);
+
+        mock.keys.lock().unwrap().push(rsa_jwk_json("k2"));
+        assert_eq!(verifier.fetch_key(&mock.uri, "k2").await.unwrap().kid, "k2");
+        assert_eq!(mock.hits(), 2);
+
+        let err = verifier.fetch_key(&mock.uri, "k3").await.unwrap_err();
+        assert_eq!(err.to_string(), format!("kid 'k3' not found at {}", mock.uri));
+        assert_eq!(mock.hits(), 3);
+
+        // The provider now lists k2 twice; a cache hit on k2 would hide
+        // that, so let another miss load the new set first.
+        mock.keys.lock().unwrap().push(rsa_jwk_json("k2"));
+        assert!(verifier.fetch_key(&mock.uri, "k4").await.is_err());
+        let err = verifier.fetch_key(&mock.uri, "k2").await.unwrap_err();
+        assert_eq!(err.to_string(), "kid 'k2' matches 2 keys in the JWK set");
+        // k1 is still unambiguous in the new set
+        assert_eq!(verifier.fetch_key(&mock.uri, "k1").await.unwrap().kid, "k1");
+    }
+
+    /// Misses on distinct key sets each need a fetch, but no more than
+    /// `max_concurrent_fetches` of them reach the network at once.
+    #[actix_web::test]
+    async fn fetches_in_flight_never_exceed_the_cap() {
+        const CAP: usize = 2;
+        let mock = MockJwks::start(vec![rsa_jwk_json(TEST_KID)], Duration::from_millis(100)).await;
+        let config = HttpConfig { max_concurrent_fetches: CAP, ..HttpConfig::default() };
+        let verifier = Arc::new(mock.verifier().await.with_http_config(config).unwrap());
+        let tasks: Vec<_> = (0..3 * CAP)
+            .map(|i| {
+                // The mock ignores the query, but the cache keys on it.
+                let (verifier, uri) = (verifier.clone(), format!("{}?set={i}", mock.uri));
+                tokio::spawn(async move { verifier.fetch_key(&uri, TEST_KID).await.map(|jwk| jwk.kid) })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), TEST_KID);
+        }
+        assert_eq!(mock.hits(), 3 * CAP);
+        assert_eq!(mock.peak.load(Ordering::SeqCst), CAP);
+    }
+
+    #[actix_web::test]
+    async fn verify_batch_answers_each_item_in_input_order() {
+        const OTHER: &str = "42";
+        let (verifier, circuit) = rsa_test_verifier().await;
+        let item = |sub: &str, kid: &str, proof: &Proof<Bn254>, statement| BatchItem {
+            sub: sub.to_string(),
+            kid: kid.to_string(),
+            circuit_id: None,
+            proof: proof.clone(),
+            statement,
+        };
+        let for_sub = circuit.prove(&test_key_inputs(SUB));
+        let for_other = circuit.prove(&test_key_inputs(OTHER));
+        let nonce = Fr::from(7u64);
+        let mut bound_inputs = test_key_inputs(SUB);
+        *bound_inputs.last_mut().unwrap() = nonce;
+        let bound = circuit.prove(&bound_inputs);
+
+        // All valid: the single batch equation holds.
+        let valid = [
+            item(SUB, TEST_KID, &for_sub, None),
+            item(OTHER, TEST_KID, &for_other, None),
+            item(SUB, TEST_KID, &bound, Some(nonce)),
+        ];
+        assert_eq!(verifier.verify_batch(GOOGLE_ISSUER, &valid).await.unwrap(), [true; 3]);
+
+        // Mixed: the equation fails and every item gets its own verdict.
+        let mixed = [
+            item(SUB, TEST_KID, &for_sub, None),
+            item(SUB, TEST_KID, &for_other, None),
+            item(OTHER, TEST_KID, &for_other, None),
+            item(SUB, TEST_KID, &bound, Some(nonce)),
+            item(SUB, TEST_KID, &bound, None),
+            item("not decimal", TEST_KID, &for_sub, None),
+        ];
+        let verdicts = verifier.verify_batch(GOOGLE_ISSUER, &mixed).await.unwrap();
+        assert_eq!(verdicts, [true, false, true, true, false, false]);
+
+        let err = verifier.verify_batch("https://unknown.example", &mixed).await.unwrap_err();
+        assert!(err.to_string().contains("not in the allowlist"), "{err}");
+    }
+
+    /// A verifier that accepts ID tokens for [`CLIENT_ID`] signed with the
+    /// [`sign_id_token`] key.
+    async fn id_token_verifier() -> Verifier {
+        let issuers = IssuerConfig { audiences: vec![CLIENT_ID.to_string()], ..IssuerConfig::default() };
+        let verifier = Verifier::new(issuers).await.unwrap();
+        verifier.cache_jwks(GOOGLE_CERTS_URL, vec![signing_jwk(TEST_KID)]).await;
+        verifier
+    }
+
+    #[actix_web::test]
+    async fn native_id_token_verifies() {
+        let verifier = id_token_verifier().await;
+        let token = sign_id_token(TEST_KID, &id_token_claims(SUB));
+        let claims = verifier.verify_jwt_rs256(&token, TEST_KID).await.unwrap();
+        assert_eq!((claims.iss.as_str(), claims.sub.as_str()), (GOOGLE_ISSUER, SUB));
+        assert_eq!(claims.aud, [CLIENT_ID]);
+    }
+
+    #[actix_web::test]
+    async fn native_id_token_with_altered_claims_is_rejected() {
+        let verifier = id_token_verifier().await;
+        let token = sign_id_token(TEST_KID, &id_token_claims(SUB));
+        let (_, sig) = token.rsplit_once('.').unwrap();
+        let forged = sign_id_token(TEST_KID, &id_token_claims("42"));
+        let (signing_input, _) = forged.rsplit_once('.').unwrap();
+        let err = verifier.verify_jwt_rs256(&format!("{signing_input}.{sig}"), TEST_KID).await.unwrap_err();
+        assert!(err.to_string().contains("signature"), "{err:#}");
+    }
+
+    #[actix_web::test]
+    async fn expired_native_id_token_is_rejected() {
+        let verifier = id_token_verifier().await;
+        let mut claims = id_token_claims(SUB);
+        claims["iat"] = (claims["iat"].as_u64().unwrap() - 7200).into();
+        claims["exp"] = (claims["exp"].as_u64().unwrap() - 7200).into();
+        let err = verifier.verify_jwt_rs256(&sign_id_token(TEST_KID, &claims), TEST_KID).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<ClaimsError>(), Some(ClaimsError::Expired { .. })), "{err:#}");
+    }
+
+    #[actix_web::test]
+    async fn native_id_token_for_another_client_is_rejected() {
+        let verifier = id_token_verifier().await;
+        let mut claims = id_token_claims(SUB);
+        claims["aud"] = "other-client.apps.googleusercontent.com".into();
+        claims["azp"] = "other-client.apps.googleusercontent.com".into();
+        let err = verifier.verify_jwt_rs256(&sign_id_token(TEST_KID, &claims), TEST_KID).await.unwrap_err();
+        assert!(err.to_string().contains("audience"), "{err:#}");
+
+        // Ours among several audiences, but presented by someone else.
+        claims["aud"] = serde_json::json!([CLIENT_ID, "other-client.apps.googleusercontent.com"]);
+        let err = verifier.verify_jwt_rs256(&sign_id_token(TEST_KID, &claims), TEST_KID).await.unwrap_err();
+        assert!(err.to_string().contains("authorized party"), "{err:#}");
+    }
+
+    #[test]
+    fn audience_checks() {
+        let issuers = IssuerConfig { audiences: vec!["ours".into()], ..IssuerConfig::default() };
+        let aud = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(issuers.check_audience(&aud(&["ours"]), None).is_ok());
+        assert!(issuers.check_audience(&aud(&["ours", "theirs"]), Some("ours")).is_ok());
+        assert!(issuers.check_audience(&aud(&["ours", "theirs"]), None).is_err());
+        assert!(issuers.check_audience(&aud(&["ours"]), Some("theirs")).is_err());
+        assert!(issuers.check_audience(&aud(&["theirs"]), None).is_err());
+        assert!(IssuerConfig::default().check_audience(&aud(&["ours"]), None).is_err());
+    }
+
+    #[test]
+    fn vk_survives_a_snarkjs_json_round_trip() {
+        let embedded = parse_vk_json(VK_JSON).unwrap();
+        let mut with_identities = TestCircuit::new(3).vk;
+        with_identities.gamma_abc_g1[1] = G1Affine::zero();
+        with_identities.delta_g2 = G2Affine::zero();
+
+        for vk in [embedded, TestCircuit::new(3).vk, with_identities] {
+            let json = vk_to_snarkjs_json(&vk).unwrap();
+            assert_eq!(parse_vk_json(&json).unwrap(), vk);
+            let v: Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(v["nPublic"], vk.gamma_abc_g1.len() - 1);
+        }
+    }
+
+    /// Hand-edited or foreign VKs fail with the coordinate at fault named,
+    /// never a panic on an unexpected JSON type.
+    #[test]
+    fn vk_coordinates_of_the_wrong_type_are_named_in_the_error() {
+        let vk: Value = serde_json::from_str(VK_JSON).unwrap();
+        let parse_with = |path: &[&dyn serde_json::value::Index], value: Value| {
+            let mut vk = vk.clone();
+            let slot = path.iter().fold(&mut vk, |v, i| &mut v[*i]);
+            *slot = value;
+            format!("{:#}", parse_vk_json(&vk.to_string()).unwrap_err())
+        };
+
+        assert_eq!(parse_with(&[&"IC", &1, &0], serde_json::json!(5)), "IC[1][0] is not a string (got 5)");
+        assert_eq!(
+            parse_with(&[&"vk_beta_2", &1, &0], Value::Null),
+            "vk_beta_2[1][0] is not a string (got null)"
+        );
+        assert_eq!(parse_with(&[&"vk_alpha_1", &2], serde_json::json!(1)), "vk_alpha_1[2] is not a string (got 1)");
+        assert_eq!(parse_with(&[&"IC", &2, &1], "0x12".into()), "IC[2][1]: invalid decimal");
+        assert_eq!(parse_with(&[&"IC", &0], serde_json::json!(["1", "2"])), "IC[0] has 2 elements, expected 3");
+        assert_eq!(parse_with(&[&"vk_gamma_2", &1], "1".into()), "vk_gamma_2[1] not array");
+    }
+
+    /// One modulus chunked at 64 and at 121 bits: both limb lists rebuild
+    /// it, and a circuit built for 64-bit limbs verifies only once the
+    /// verifier is switched to that width.
+    #[actix_web::test]
+    async fn with_chunk_bits_lays_the_modulus_out_at_the_given_width() {
+        let n = modulus_b64(RSA_MODULUS_BITS);
+        let modulus = BigUint::from_bytes_be(&b64::URL_SAFE_NO_PAD.decode(&n).unwrap());
+        for bits in [64, CHUNK_BITS] {
+            let limbs = chunk_modulus(&n, bits).unwrap();
+            assert_eq!(limbs.len(), RSA_MODULUS_BITS.div_ceil(bits));
+            assert!(limbs.iter().all(|l| l.bits() <= bits as u64));
+            let rebuilt = limbs.iter().rev().fold(BigUint::default(), |acc, l| (acc << bits) + l);
+            assert_eq!(rebuilt, modulus, "{bits}-bit limbs");
+        }
+
+        let limbs = RSA_MODULUS_BITS / 64;
+        let circuit = TestCircuit::new(limbs + RSA_CIRCUIT_EXTRA_INPUTS);
+        let proof = circuit.prove(&rsa_public_inputs(SUB, &n, 64, limbs).unwrap());
+        let proof = proof::proof_to_base64_compressed(&proof).unwrap();
+        let verify = |verifier: Verifier| {
+            let proof = proof.clone();
+            async move {
+                seed_google_jwks(&verifier, vec![Jwk::rsa(TEST_KID, modulus_b64(RSA_MODULUS_BITS), "AQAB")]).await;
+                verifier.verify(GOOGLE_ISSUER, SUB, TEST_KID, SubEncoding::Decimal, &proof, None).await.unwrap()
+            }
+        };
+        let at_default = Verifier::with_vk_json(&circuit.vk_json(), IssuerConfig::default()).unwrap();
+        assert!(!verify(at_default).await, "121-bit limbs zero-padded to 32 are another statement");
+        let at_64 = Verifier::with_vk_json(&circuit.vk_json(), IssuerConfig::default())
+            .and_then(|v| v.with_chunk_bits(64))
+            .unwrap();
+        assert!(verify(at_64).await);
+
+        // The default circuit's 17 limbs cannot hold 2048 bits at 64 each.
+        let default_vk = Verifier::with_vk_json(VK_JSON, IssuerConfig::default()).unwrap();
+        assert!(default_vk.with_chunk_bits(64).is_err());
+        for bits in [0, MAX_CHUNK_BITS + 1] {
+            let verifier = Verifier::with_vk_json(VK_JSON, IssuerConfig::default()).unwrap();
+            let Err(err) = verifier.with_chunk_bits(bits) else { panic!("accepted {bits}-bit limbs") };
+            assert_eq!(err.to_string(), format!("chunk width must be 1..={MAX_CHUNK_BITS} bits, got {bits}"));
+        }
+    }
+
+    /// A VK read from disk at startup replaces the embedded one; a missing
+    /// or malformed file fails naming the path.
+    #[actix_web::test]
+    async fn from_vk_path_loads_the_key_and_verifies_its_proofs() {
+        let circuit = TestCircuit::rsa();
+        let path = std::env::temp_dir().join(format!("syra-vk-{}.json", std::process::id()));
+        std::fs::write(&path, circuit.vk_json()).unwrap();
+        let verifier = Verifier::from_vk_path(&path, IssuerConfig::default()).await.unwrap();
+        seed_google_jwks(&verifier, vec![Jwk::rsa(TEST_KID, modulus_b64(RSA_MODULUS_BITS), "AQAB")]).await;
+        let proof = proof::proof_to_base64_compressed(&circuit.prove(&test_key_inputs(SUB))).unwrap();
+        assert!(verifier.verify(GOOGLE_ISSUER, SUB, TEST_KID, SubEncoding::Decimal, &proof, None).await.unwrap());
+        assert_eq!(verifier.verifying_key(RSA_CIRCUIT_ID), Some(circuit.vk.clone()));
+
+        std::fs::write(&path, "{ \"IC\": ").unwrap();
+        assert!(Verifier::from_vk_path(&path, IssuerConfig::default()).await.is_err());
+        std::fs::remove_file(&path).unwrap();
+        let Err(err) = Verifier::from_vk_path(&path, IssuerConfig::default()).await else {
+            panic!("loaded a missing file");
+        };
+        assert!(err.to_string().starts_with(&format!("reading {}", path.display())), "{err:#}");
+    }
+
+    #[actix_web::test]
+    async fn verify_with_jwk_checks_a_proof_against_an_inline_key() {
+        let circuit = TestCircuit::new(rsa_input_count());
+        let verifier = Verifier::with_vk_json(&circuit.vk_json(), IssuerConfig::default()).unwrap();
+        let jwk = Jwk::rsa("out-of-band", modulus_b64(RSA_MODULUS_BITS), "AQAB");
+        let proof = proof::proof_to_base64_compressed(&circuit.prove(&test_key_inputs(SUB))).unwrap();
+
+        assert!(verifier.verify_with_jwk(GOOGLE_ISSUER, SUB, &jwk, &proof, None).await.unwrap());
+        assert!(!verifier.verify_with_jwk(GOOGLE_ISSUER, "42", &jwk, &proof, None).await.unwrap());
+        let other_key = Jwk::rsa("out-of-band", modulus_b64(RSA_MODULUS_BITS - 8), "AQAB");
+        assert!(!verifier.verify_with_jwk(GOOGLE_ISSUER, SUB, &other_key, &proof, None).await.unwrap());
+        assert!(verifier.verify_with_jwk(GOOGLE_ISSUER, SUB, &jwk, "not base64!", None).await.is_err());
+    }
+
+    #[actix_web::test]
+    async fn verify_reads_sub_in_its_encoding_and_looks_the_key_up() {
+        let (verifier, circuit) = rsa_test_verifier().await;
+        let proof = proof::proof_to_base64_compressed(&circuit.prove(&test_key_inputs(SUB))).unwrap();
+        let hex = format!("{:x}", BigUint::parse_bytes(SUB.as_bytes(), 10).unwrap());
+
+        assert!(verifier.verify(GOOGLE_ISSUER, SUB, TEST_KID, SubEncoding::Decimal, &proof, None).await.unwrap());
+        assert!(verifier.verify(GOOGLE_ISSUER, &hex, TEST_KID, SubEncoding::Hex, &proof, None).await.unwrap());
+        let err = verifier.verify("https://evil.example", SUB, TEST_KID, SubEncoding::Decimal, &proof, None);
+        assert!(err.await.unwrap_err().to_string().contains("allowlist"));
+    }
+
+    /// `http://` URI of a local port nothing listens on.
+    fn closed_port_uri() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}/certs", listener.local_addr().unwrap())
+    }
+
+    #[actix_web::test]
+    async fn unreachable_jwks_is_retried_then_reported_unavailable() {
+        let uri = closed_port_uri();
+        let issuers = IssuerConfig { providers: vec![(MOCK_ISSUER.to_string(), uri.clone())], ..IssuerConfig::default() };
+        let verifier = Verifier::new(issuers).await.unwrap();
+
+        let started = Instant::now();
+        let err = verifier.fetch_key(&uri, "any").await.unwrap_err();
+        let unavailable = err.downcast_ref::<JwksUnavailable>().expect("a JwksUnavailable");
+        assert_eq!(unavailable.uri, uri);
+        // the backoff between the attempts: 200 ms, then 400 ms
+        assert!(started.elapsed() >= JWKS_RETRY_BACKOFF * 3, "gave up after {:?}", started.elapsed());
+    }
+
+    #[actix_web::test]
+    async fn server_errors_are_retried_jwks_fetch_attempts_times() {
+        let mock = MockJwks::start(vec![rsa_jwk_json("k1")], Duration::ZERO).await;
+        mock.status.store(503, Ordering::SeqCst);
+        let verifier = mock.verifier().await;
+
+        let err = verifier.fetch_key(&mock.uri, "k1").await.unwrap_err();
+        let unavailable = err.downcast_ref::<JwksUnavailable>().expect("a JwksUnavailable");
+        assert!(unavailable.reason.contains("503"), "{unavailable}");
+        assert_eq!(mock.hits(), JWKS_FETCH_ATTEMPTS as usize);
+
+        // a provider that recovers between attempts is not reported at all
+        mock.status.store(200, Ordering::SeqCst);
+        assert_eq!(verifier.fetch_key(&mock.uri, "k1").await.unwrap().kid, "k1");
+        assert_eq!(mock.hits(), JWKS_FETCH_ATTEMPTS as usize + 1);
+
+        // a 4xx will not improve on retry, and is not the provider being down
+        mock.status.store(404, Ordering::SeqCst);
+        let fresh = mock.verifier().await;
+        let err = fresh.fetch_key(&mock.uri, "k1").await.unwrap_err();
+        assert!(err.downcast_ref::<JwksUnavailable>().is_none(), "{err:#}");
+        assert_eq!(mock.hits(), JWKS_FETCH_ATTEMPTS as usize + 2);
+    }
+
+    /// A provider that accepts the connection but answers too slowly is cut
+    /// off by the request timeout, each attempt, and reported like an
+    /// unreachable one rather than holding the request for its full delay.
+    #[actix_web::test]
+    async fn slow_jwks_times_out_as_unavailable() {
+        let delay = Duration::from_secs(5);
+        let mock = MockJwks::start(vec![rsa_jwk_json("k1")], delay).await;
+        let config = HttpConfig { timeout: Duration::from_millis(100), ..HttpConfig::default() };
+        let verifier = mock.verifier().await.with_http_config(config).unwrap();
+
+        let started = Instant::now();
+        let err = verifier.fetch_key(&mock.uri, "k1").await.unwrap_err();
+        let elapsed = started.elapsed();
+        assert!(err.downcast_ref::<JwksUnavailable>().is_some(), "{err:#}");
+        assert_eq!(mock.hits(), JWKS_FETCH_ATTEMPTS as usize);
+        assert!(elapsed < delay, "waited {elapsed:?} for a {delay:?} answer");
+    }
+}
@@ -0,0 +1,239 @@
+use anyhow::Context;
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ec::{pairing::Pairing, AffineRepr};
+use ark_ff::{One, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, SerializationError, Validate};
+use dock_crypto_utils::hashing_utils::affine_group_elem_from_try_and_incr;
+use unicode_normalization::UnicodeNormalization;
+
+use serde::Serialize;
+
+use crate::codec::{g1_from_hex, g2_from_hex};
+use crate::curve::SyraCurve;
+use crate::constants::{GroupHash, G1_GENERATOR_TAG, G2_GENERATOR_TAG, HASH_METHOD, USER_ID_TAG};
+use crate::hash::hash_to_fr;
+
+/// Pairing‐group description (just the two generators here).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bp {
+    pub g1: G1Affine,
+    pub g2: G2Affine,
+}
+
+impl Bp {
+    /// `g1 = H_to_G1(g1_tag)`, `g2 = H_to_G2(g2_tag)` by try-and-increment.
+    pub fn from_tags(g1_tag: &[u8], g2_tag: &[u8]) -> Self {
+        Bp { g1: g1_from_tag(g1_tag), g2: g2_from_tag(g2_tag) }
+    }
+
+    /// Generators published elsewhere, e.g. by a trusted setup, as hex of
+    /// their compressed encodings. Decoding checks that both are on the curve
+    /// and in the prime-order subgroup; the identity is not a generator.
+    pub fn from_hex(g1: &str, g2: &str) -> anyhow::Result<Self> {
+        let g1 = g1_from_hex(g1).context("g1")?;
+        let g2 = g2_from_hex(g2).context("g2")?;
+        anyhow::ensure!(!g1.is_zero(), "g1 is the identity");
+        anyhow::ensure!(!g2.is_zero(), "g2 is the identity");
+        Ok(Bp { g1, g2 })
+    }
+}
+
+impl Default for Bp {
+    /// The generators for the tags in [`crate::constants`].
+    fn default() -> Self {
+        Bp::from_tags(G1_GENERATOR_TAG.as_bytes(), G2_GENERATOR_TAG.as_bytes())
+    }
+}
+
+/// Hash `tag` to a G1 generator; the DKG coordinator uses this for its `g`.
+pub fn g1_from_tag(tag: &[u8]) -> G1Affine {
+    affine_group_elem_from_try_and_incr::<G1Affine, GroupHash>(tag)
+}
+
+/// Hash `tag` to a G2 generator.
+pub fn g2_from_tag(tag: &[u8]) -> G2Affine {
+    affine_group_elem_from_try_and_incr::<G2Affine, GroupHash>(tag)
+}
+
+/// The scheme parameters behind an [`IvkBundle`], published so other
+/// implementations can re-derive the generators and `s` instead of assuming
+/// this crate's defaults.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SchemeParams {
+    pub curve: String,
+    /// Empty when the generators were loaded rather than hashed; read them
+    /// from the bundle then.
+    pub g1_tag: String,
+    pub g2_tag: String,
+    pub user_id_tag: String,
+    /// Used for the generators and for `s`.
+    pub hash: String,
+    /// Default point encoding of the hex fields.
+    pub compression: String,
+}
+
+impl SchemeParams {
+    /// The parameters of keys whose generators are [`Bp::from_tags`] of
+    /// `g1_tag` / `g2_tag`.
+    pub fn new(g1_tag: &str, g2_tag: &str) -> Self {
+        SchemeParams {
+            curve: <Bls12_381 as SyraCurve>::NAME.to_string(),
+            g1_tag: g1_tag.to_string(),
+            g2_tag: g2_tag.to_string(),
+            user_id_tag: USER_ID_TAG.to_string(),
+            hash: HASH_METHOD.to_string(),
+            compression: "compressed".to_string(),
+        }
+    }
+
+    /// The generators these parameters describe.
+    pub fn generators(&self) -> Bp {
+        Bp::from_tags(self.g1_tag.as_bytes(), self.g2_tag.as_bytes())
+    }
+}
+
+impl Default for SchemeParams {
+    fn default() -> Self {
+        SchemeParams::new(G1_GENERATOR_TAG, G2_GENERATOR_TAG)
+    }
+}
+
+/// What you publish as your “verification key bundle.”
+#[derive(Clone, Debug, PartialEq)]
+pub struct IvkBundle {
+    pub version: u32,
+    pub bp:      Bp,
+    pub ivk_hat: G2Affine,
+    pub W:       G1Affine,
+    pub W_hat:   G2Affine,
+    /// Which issuer published the bundle, so federations can route a
+    /// credential to the right parameters; empty for an unnamed issuer.
+    pub issuer_id: String,
+}
+
+impl IvkBundle {
+    /// Serialize the entire bundle as
+    /// version ∥ g1 ∥ g2 ∥ ivk_hat ∥ W ∥ W_hat ∥ issuer_id
+    /// where `version` is a little-endian u32, each element is in its
+    /// compressed form, and `issuer_id` is a little-endian u64 length
+    /// followed by its UTF-8 bytes.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        self.to_bytes_with(Compress::Yes)
+    }
+
+    /// [`to_bytes`](Self::to_bytes) with each element in the chosen form:
+    /// 396 bytes compressed, 780 uncompressed, plus the `issuer_id` bytes.
+    pub fn to_bytes_with(&self, compress: Compress) -> Result<Vec<u8>, SerializationError> {
+        let mut buf = Vec::new();
+        self.version.serialize_with_mode(&mut buf, compress)?;
+        self.bp.g1.serialize_with_mode(&mut buf, compress)?;
+        self.bp.g2.serialize_with_mode(&mut buf, compress)?;
+        self.ivk_hat.serialize_with_mode(&mut buf, compress)?;
+        self.W.serialize_with_mode(&mut buf, compress)?;
+        self.W_hat.serialize_with_mode(&mut buf, compress)?;
+        self.issuer_id.serialize_with_mode(&mut buf, compress)?;
+        Ok(buf)
+    }
+
+    /// Hex-encode the above byte sequence into one big string.
+    pub fn to_hex_string(&self) -> Result<String, SerializationError> {
+        Ok(hex::encode(self.to_bytes()?))
+    }
+
+    /// Hex of [`to_bytes_with`](Self::to_bytes_with).
+    pub fn to_hex_string_with(&self, compress: Compress) -> Result<String, SerializationError> {
+        Ok(hex::encode(self.to_bytes_with(compress)?))
+    }
+
+    /// Inverse of [`to_bytes`](Self::to_bytes). Every point is checked to be
+    /// on the curve and in the prime-order subgroup, and the input must be
+    /// exactly one bundle long.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Self::from_bytes_with(bytes, Compress::Yes)
+    }
+
+    /// Inverse of [`to_bytes_with`](Self::to_bytes_with); `compress` must
+    /// match the mode the bundle was written in.
+    pub fn from_bytes_with(bytes: &[u8], compress: Compress) -> anyhow::Result<Self> {
+        let mut rd = bytes;
+        let v = Validate::Yes;
+        let bundle = IvkBundle {
+            version: CanonicalDeserialize::deserialize_with_mode(&mut rd, compress, v).context("version")?,
+            bp: Bp {
+                g1: CanonicalDeserialize::deserialize_with_mode(&mut rd, compress, v).context("g1")?,
+                g2: CanonicalDeserialize::deserialize_with_mode(&mut rd, compress, v).context("g2")?,
+            },
+            ivk_hat: CanonicalDeserialize::deserialize_with_mode(&mut rd, compress, v).context("ivk_hat")?,
+            W: CanonicalDeserialize::deserialize_with_mode(&mut rd, compress, v).context("W")?,
+            W_hat: CanonicalDeserialize::deserialize_with_mode(&mut rd, compress, v).context("W_hat")?,
+            issuer_id: CanonicalDeserialize::deserialize_with_mode(&mut rd, compress, v).context("issuer_id")?,
+        };
+        anyhow::ensure!(rd.is_empty(), "{} trailing bytes after IVK bundle", rd.len());
+        Ok(bundle)
+    }
+
+    /// `W` and `W_hat` share a discrete log: `e(W, g2) == e(g1, W_hat)`.
+    pub fn is_consistent(&self) -> bool {
+        Bls12_381::pairing(self.W, self.bp.g2) == Bls12_381::pairing(self.bp.g1, self.W_hat)
+    }
+
+    /// Inverse of [`to_hex_string`](Self::to_hex_string).
+    pub fn from_hex_string(s: &str) -> anyhow::Result<Self> {
+        let bytes = hex::decode(s.trim()).context("invalid hex")?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// Deterministic hash-to-field:  sub  →  s ∈ Fr  (never 0).
+///
+/// `s = hash_to_fr(USER_ID_TAG, NFC(sub))`, see [`hash_to_fr`].
+///
+/// The canonical form of `sub` is Unicode NFC, so composed and decomposed
+/// spellings of the same identifier (e.g. `"é"` vs `"e\u{301}"`) yield the
+/// same `s` and therefore the same `usk`.
+///
+/// Invariants issuance relies on:
+/// - pure and deterministic: no state, randomness or clock, so every issuer
+///   and verifier derives the same `s` for the same `sub`;
+/// - never zero, so `usk = g1^{1/(s+isk)}` is never `g1^{1/isk}`;
+/// - distinct NFC forms collide only with the hash's negligible probability;
+///   the `0 → 1` remap is the one deliberate collision, reachable only by a
+///   Blake2b preimage of zero.
+pub fn s_from_sub<S: AsRef<str>>(sub: S) -> Fr {
+    let sub: String = sub.as_ref().nfc().collect();
+    let s: Fr = hash_to_fr(USER_ID_TAG.as_bytes(), sub.as_bytes());
+
+    if s.is_zero() { Fr::one() } else { s } // avoid 0 just like TS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn s_from_sub_is_normalization_invariant() {
+        // U+00E9 vs "e" + U+0301 COMBINING ACUTE ACCENT
+        assert_eq!(s_from_sub("caf\u{e9}"), s_from_sub("cafe\u{301}"));
+        assert_ne!(s_from_sub("caf\u{e9}"), s_from_sub("cafe"));
+    }
+
+    proptest! {
+        #[test]
+        fn s_from_sub_is_deterministic(sub in any::<String>()) {
+            prop_assert_eq!(s_from_sub(&sub), s_from_sub(sub.clone()));
+        }
+
+        #[test]
+        fn s_from_sub_is_never_zero(sub in any::<String>()) {
+            prop_assert!(!s_from_sub(&sub).is_zero());
+        }
+
+        #[test]
+        fn distinct_nfc_forms_give_distinct_s(a in any::<String>(), b in any::<String>()) {
+            let (a_nfc, b_nfc): (String, String) = (a.nfc().collect(), b.nfc().collect());
+            prop_assume!(a_nfc != b_nfc);
+            prop_assert_ne!(s_from_sub(&a), s_from_sub(&b));
+        }
+    }
+}
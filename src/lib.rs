@@ -0,0 +1,35 @@
+//! Pure SyRA key types and credential verification.
+//!
+//! This half of the crate has no server dependencies (no actix, reqwest,
+//! tokio or OS randomness), so verifiers can use it directly, including in
+//! the browser through the `wasm` feature. The issuer service itself lives in
+//! the `syra-login` binary behind the default `server` feature, as does the
+//! Groth16 verifier of Google-login proofs (`jwt_proof_verifier`, `proof`),
+//! which fetches JWKS over HTTP.
+
+// Points keep the names they have in the SyRA paper and on the wire
+// (`W`, `W_hat`, `A`, `C_all`, `U`, `T1`, …).
+#![allow(non_snake_case)]
+
+pub mod codec;
+pub mod constants;
+pub mod credential;
+pub mod curve;
+pub mod dkg_recovery;
+pub mod dkg_wire;
+pub mod hash;
+#[cfg(feature = "server")]
+pub mod jwt_proof_verifier;
+pub mod keys;
+#[cfg(feature = "server")]
+pub mod proof;
+pub mod signature;
+#[cfg(feature = "server")]
+#[doc(hidden)]
+pub mod test_circuit;
+#[cfg(feature = "server")]
+#[doc(hidden)]
+pub mod test_tokens;
+pub mod test_vectors;
+#[cfg(feature = "wasm")]
+pub mod wasm;
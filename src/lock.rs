@@ -0,0 +1,21 @@
+use std::sync::{Mutex, MutexGuard};
+
+/// Locking that survives a panicked request.
+///
+/// A handler that panics while holding one of the state mutexes poisons it,
+/// and `lock().unwrap()` would then panic in every later request. The state
+/// behind these locks is replaced wholesale (a key set, a share, a map
+/// entry), never left half-written, so the inner value is safe to keep using.
+pub trait LockExt<T> {
+    /// [`Mutex::lock`], taking the guard out of a poisoned lock with a warning.
+    fn lock_or_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockExt<T> for Mutex<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(|poisoned| {
+            tracing::warn!("mutex poisoned by a panicked request; recovering its value");
+            poisoned.into_inner()
+        })
+    }
+}
@@ -1,98 +1,267 @@
-use std::ops::{AddAssign, MulAssign};
-use ark_ff::UniformRand;
-mod jwt_proof_verifier;
-mod proof;
+// Points keep the names they have in the SyRA paper and on the wire
+// (`W`, `W_hat`, `A`, `C_all`, `U`, `T1`, …).
+#![allow(non_snake_case)]
+
+mod audit;
+mod auth;
+mod challenge;
+mod config;
+mod dkg;
+mod error;
+mod fallback;
+mod health;
+mod lock;
+mod metrics;
+mod openapi;
+mod persistence;
+mod rate_limit;
+mod request_id;
+mod revocation;
+#[cfg(test)]
+mod test_support;
 
 use actix_cors::Cors;
-use actix_web::{http::header,post, web, App, HttpServer, HttpResponse, Responder};
+use clap::Parser;
+use actix_web::{http::header,get,middleware,post, web, App, HttpRequest, HttpServer, HttpResponse};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
-use ark_std::rand::{CryptoRng, RngCore, rngs::OsRng};
+use std::path::PathBuf;
+use std::time::Duration;
+use ark_std::rand::rngs::OsRng;
 
-use ark_bls12_381::{Fr, G1Affine, G1Projective, G2Affine, G2Projective};
-use ark_ec::CurveGroup;
-use ark_ff::{Field, PrimeField, Zero, One};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use unicode_normalization::UnicodeNormalization;
+use ark_bls12_381::{Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_serialize::{CanonicalSerialize, Compress};
 use blake2::{Blake2b512, Digest};
-use dock_crypto_utils::hashing_utils::{
-    affine_group_elem_from_try_and_incr,
-    field_elem_from_try_and_incr,
+use syra_login::jwt_proof_verifier::{
+    vk_to_snarkjs_json, BatchItem, ClaimsError, JwksUnavailable, JwtClaims, ProofVerifier, SubEncoding, UnknownCircuit,
+    Verifier, VerifyDiagnostics,
 };
-use jwt_proof_verifier::Verifier;
-
-use hex;
+use audit::AuditLog;
+use dkg::DkgShare;
+use lock::LockExt;
+use challenge::{ChallengeError, ChallengeStore};
+use config::{Config, LogFormat};
+use error::{ApiError, ErrorBody};
+use metrics::Metrics;
+use rate_limit::RateLimiter;
+use revocation::RevocationList;
+use syra_login::proof::{decode_proof, ProofFormat};
 
-#[derive(Deserialize)]
-struct DkgPointMessage {
-    A: String,
-    f_i: String,
-    Ai_all: Vec<String>,
-}
+use tracing_actix_web::TracingLogger;
+use utoipa::ToSchema;
+use tracing_subscriber::EnvFilter;
+use zeroize::{Zeroize, Zeroizing};
+use syra_login::{credential, curve};
+use syra_login::constants::LOG_USER_TAG;
+use syra_login::codec::{fr_to_hex, g1_from_hex, g1_to_hex, g1_to_hex_with, g2_to_hex, g2_to_hex_with};
+use syra_login::signature::derive_pseudonym;
+use syra_login::keys::{s_from_sub, Bp, IvkBundle, SchemeParams};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct GenerateKeyRequest {
-    /// plain‐text user identifier
+    /// token issuer (`iss` claim); Google when omitted
+    #[serde(default = "default_iss")]
+    iss: String,
+    /// plain‐text user identifier; NFC-normalized before deriving `s`
     user_id: String,
+    /// how `user_id` is spelled; hex and base64url subjects are rewritten
+    /// in decimal before the public inputs and `s` are built from them
+    #[serde(default)]
+    sub_encoding: SubEncoding,
     kid: String,       // Google key-id
-    proof: String,
+    /// base-64 proof, or the SnarkJS proof object itself
+    #[schema(value_type = Object)]
+    proof: serde_json::Value,
+    /// how `proof` is encoded; inferred from its JSON type when omitted
+    #[serde(default)]
+    proof_format: Option<ProofFormat>,
+    /// the token's `exp` / `iat` / `nbf`; checked when present
+    #[serde(default)]
+    claims: Option<JwtClaims>,
+    /// nonce from `GET /challenge` the proof's `subStatement` was set to
+    #[serde(default)]
+    nonce: Option<String>,
+    /// verifying key the proof was made for (`--circuit-vk`); the default
+    /// circuit for the key's type (`rsa` / `ec`) when omitted
+    #[serde(default)]
+    circuit_id: Option<String>,
 }
 
-#[derive(Serialize)]
+fn default_iss() -> String {
+    syra_login::jwt_proof_verifier::GOOGLE_ISSUER.to_string()
+}
+
+/// Point encoding of `usk`, `usk_hat` and `ivk` in the response.
+#[derive(Deserialize, ToSchema, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Compression {
+    /// G1 48 bytes, G2 96, IVK bundle 396 + issuer id
+    #[default]
+    Compressed,
+    /// G1 96 bytes, G2 192, IVK bundle 780 + issuer id
+    Uncompressed,
+}
+
+impl From<Compression> for Compress {
+    fn from(c: Compression) -> Self {
+        match c {
+            Compression::Compressed => Compress::Yes,
+            Compression::Uncompressed => Compress::No,
+        }
+    }
+}
+
+/// `?compression=` on `generate_user_key`.
+#[derive(Deserialize, utoipa::IntoParams)]
+struct KeyEncodingQuery {
+    /// `compressed` (default) or `uncompressed`
+    #[serde(default)]
+    compression: Compression,
+}
+
+#[derive(Serialize, ToSchema)]
 struct GenerateKeyResponse {
+    /// version of the issuer keys `usk` was derived under
+    version: u32,
+    /// `s_from_sub(user_id)`, 32-byte little-endian hex. Informational only:
+    /// verifiers must re-derive `s` themselves rather than trust this.
+    s: String,
+    /// IVK bundle hex, 396 bytes compressed / 780 uncompressed, plus the
+    /// issuer id
     ivk: String,
+    /// G1 point hex, 48 bytes compressed / 96 uncompressed
     usk: String,
+    /// G2 point hex, 96 bytes compressed / 192 uncompressed
     usk_hat: String,
 }
-/// Holds your issuer’s key material once generated.
+
+/// `GET /ivk`: the combined bundle plus each element on its own.
+#[derive(Serialize, ToSchema)]
+struct IvkResponse {
+    version: u32,
+    ivk: String,
+    g1: String,
+    g2: String,
+    ivk_hat: String,
+    W: String,
+    W_hat: String,
+    /// `--issuer-id`, also inside `ivk`; empty for an unnamed issuer
+    issuer_id: String,
+    /// curve, generator / user-id tags and hash the bundle was built with
+    #[schema(value_type = Object)]
+    scheme: SchemeParams,
+}
+/// The pairing engine issuer and user keys live on.
+type Curve = ark_bls12_381::Bls12_381;
+
+/// Holds your issuer’s key material once generated. Clones zeroize their
+/// `isk` on drop like the original.
+#[derive(Clone)]
 pub struct StoredIssuerKeys {
+    /// Starts at 1 and goes up by one with every `/admin/rotate_keys`.
+    pub version:  u32,
     pub bp:       Bp,
     pub isk:      Fr,
     pub ivk_hat:  G2Affine,
     pub W:        G1Affine,
     pub W_hat:    G2Affine,
+    /// Published in the bundle; see [`IvkBundle::issuer_id`].
+    pub issuer_id: String,
 }
 
-/// Pairing‐group description (just the two generators here).
-#[derive(Clone)]
-pub struct Bp {
-    pub g1: G1Affine,
-    pub g2: G2Affine,
-}
-
-/// What you publish as your “verification key bundle.”
-#[derive(Clone)]
-pub struct IvkBundle {
-    pub bp:      Bp,
-    pub ivk_hat: G2Affine,
-    pub W:       G1Affine,
-    pub W_hat:   G2Affine,
-}
-
-impl IvkBundle {
-    /// Serialize the entire bundle as
-    /// g1 ∥ g2 ∥ ivk_hat ∥ W ∥ W_hat
-    /// where each element is in its compressed form.
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buf = Vec::new();
-        self.bp.g1.serialize_compressed(&mut buf).unwrap();
-        self.bp.g2.serialize_compressed(&mut buf).unwrap();
-        self.ivk_hat.serialize_compressed(&mut buf).unwrap();
-        self.W.serialize_compressed(&mut buf).unwrap();
-        self.W_hat.serialize_compressed(&mut buf).unwrap();
-        buf
+impl Drop for StoredIssuerKeys {
+    fn drop(&mut self) {
+        self.isk.zeroize();
     }
+}
 
-    /// Hex-encode the above byte sequence into one big string.
-    pub fn to_hex_string(&self) -> String {
-        hex::encode(self.to_bytes())
+impl StoredIssuerKeys {
+    /// The public part of these keys.
+    pub fn ivk_bundle(&self) -> IvkBundle {
+        IvkBundle {
+            version: self.version,
+            bp:      self.bp.clone(),
+            ivk_hat: self.ivk_hat,
+            W:       self.W,
+            W_hat:   self.W_hat,
+            issuer_id: self.issuer_id.clone(),
+        }
     }
 }
 
 /// Shared application state — at most one generation allowed.
 pub struct AppState {
     pub issuer_keys: Mutex<Option<StoredIssuerKeys>>,
-    pub verifier: Arc<Verifier>,
+    /// Keys replaced by `/admin/rotate_keys`, oldest first. Kept so
+    /// credentials issued under an earlier version still verify. Always
+    /// lock `issuer_keys` before this one.
+    pub retired_issuer_keys: Mutex<Vec<StoredIssuerKeys>>,
+    /// Where the current issuer keys are persisted.
+    pub issuer_keys_path: PathBuf,
+    /// Where `retired_issuer_keys` are persisted.
+    pub retired_keys_path: PathBuf,
+    /// Encrypts the key file at rest when set.
+    pub keys_passphrase: Option<Zeroizing<String>>,
+    /// Per-IP limiter for `/admin/generate_user_key`.
+    pub rate_limiter: RateLimiter,
+    /// Revoked users; lock after `issuer_keys`.
+    pub revocation: Mutex<RevocationList>,
+    /// Where `revocation` is persisted.
+    pub revocation_path: PathBuf,
+    /// Prometheus collectors served on `/metrics`.
+    pub metrics: Metrics,
+    /// Bearer token checked by [`auth::require_admin_token`] on `/admin/*`.
+    pub admin_token: String,
+    /// This issuer's DKG share, summed over the dealers accepted so far.
+    pub dkg: Mutex<Option<DkgShare>>,
+    /// Our x-coordinate in the DKG (1-based).
+    pub party_index: u64,
+    /// Whether `/readyz` and key issuance wait for a DKG share.
+    pub require_dkg: bool,
+    /// Nonces handed out by `/challenge`, redeemed by `generate_user_key`.
+    pub challenges: ChallengeStore,
+    /// Whether `generate_user_key` refuses requests without a nonce.
+    pub require_challenge: bool,
+    /// Whether `/admin/generate_user_key_jwt` accepts bare ID tokens.
+    pub allow_native_jwt: bool,
+    /// `(g1, g2)` from the configured tags; used for new keys and as the
+    /// DKG generator.
+    pub generators: Bp,
+    /// The parameters `generators` came from, published on `/ivk`.
+    pub scheme: SchemeParams,
+    /// `--issuer-id`, stamped on every key set this issuer creates.
+    pub issuer_id: String,
+    /// Where every issued key is recorded.
+    pub audit: AuditLog,
+    pub verifier: Arc<dyn ProofVerifier>,
+}
+
+impl AppState {
+    fn keys_passphrase(&self) -> Option<&str> {
+        self.keys_passphrase.as_deref().map(String::as_str)
+    }
+
+    /// Whether every dealer's share of the DKG session has arrived, so this
+    /// node issues under its final share.
+    pub fn dkg_complete(&self) -> bool {
+        self.dkg.lock_or_recover().as_ref().is_some_and(|share| share.is_complete())
+    }
+
+    /// The keys this node issues under while `stored` is current: once all
+    /// `n` dealers' shares are in, `stored` with `isk = isk_i` and
+    /// `ivk_hat = g2^{isk_i}`, so keys derived from the share verify against
+    /// the bundle `/ivk` publishes; `stored` itself until then. Locks `dkg`
+    /// only for the copy.
+    pub fn signing_keys(&self, stored: &StoredIssuerKeys) -> StoredIssuerKeys {
+        let mut keys = stored.clone();
+        if let Some(share) = self.dkg.lock_or_recover().as_ref().filter(|share| share.is_complete()) {
+            keys.isk = share.isk_i;
+            keys.ivk_hat = (stored.bp.g2 * share.isk_i).into_affine();
+        }
+        keys
+    }
 }
 
 /// Errors during key generation.
@@ -100,225 +269,1788 @@ pub struct AppState {
 pub enum KeygenError {
     #[error("issuer keys already generated")]
     AlreadyGenerated,
+    #[error("issuer keys not generated yet")]
+    NotGenerated,
 }
 
 pub fn generate_issuer_keys(
     state: &AppState,
 ) -> Result<IvkBundle, KeygenError>{
-    let mut guard = state.issuer_keys.lock().unwrap();
+    let mut guard = state.issuer_keys.lock_or_recover();
     if guard.is_some() {
         return Err(KeygenError::AlreadyGenerated);
     }
 
-    // 1) GrGen: derive g1 ∈ G1 and g2 ∈ G2 
-    let g1 = affine_group_elem_from_try_and_incr::<G1Affine, Blake2b512>(b"syra-generator-1");
-    let g2 = affine_group_elem_from_try_and_incr::<G2Affine, Blake2b512>(b"syra-generator-2");
-    let bp = Bp { g1, g2 };
+    let keys = sample_issuer_keys(1, &state.generators, &state.issuer_id);
+    let ivk = keys.ivk_bundle();
+    *guard = Some(keys);
 
-    // Prepare a secure RNG
-    let mut rng = OsRng;
+    tracing::info!("issuer keys initialized in memory");
+    Ok(ivk)
+}
 
-    // 2) Sample isk ∈ Fr
-    let isk = Fr::rand(&mut rng);
-    let isk_clone =  isk.clone();
+/// Replace the current issuer keys with a fresh set at `version + 1`,
+/// moving the old set to `state.retired_issuer_keys`.
+pub fn rotate_issuer_keys(
+    state: &AppState,
+) -> Result<IvkBundle, KeygenError> {
+    let mut guard = state.issuer_keys.lock_or_recover();
+    let previous = guard.take().ok_or(KeygenError::NotGenerated)?;
 
-    // 3) Sample two fresh group elements W = g1^r₁, W_hat = g2^r₂
-    let r1 = Fr::rand(&mut rng);
-    let r2 = Fr::rand(&mut rng);
-    let W     = (G1Projective::from(bp.g1) * r1).into_affine();
-    let W_hat = (G2Projective::from(bp.g2) * r2).into_affine();
+    let keys = sample_issuer_keys(previous.version + 1, &state.generators, &state.issuer_id);
+    let ivk = keys.ivk_bundle();
+    state.retired_issuer_keys.lock_or_recover().push(previous);
+    *guard = Some(keys);
+
+    tracing::info!(version = ivk.version, "issuer keys rotated");
+    Ok(ivk)
+}
 
-    // 4) Compute ivk_hat = g2^isk
-    let ivk_hat = (G2Projective::from(bp.g2) * isk).into_affine();
+/// Sample a fresh set of issuer keys tagged with `version` and `issuer_id`
+/// over the generators `bp`.
+fn sample_issuer_keys(version: u32, bp: &Bp, issuer_id: &str) -> StoredIssuerKeys {
+    // 1) GrGen: g1 ∈ G1 and g2 ∈ G2, hashed from the configured tags
+    let bp = bp.clone();
 
-    // 5) Bundle public IVK
-    let ivk = IvkBundle { bp: bp.clone(), ivk_hat, W, W_hat };
+    // 2)–4) isk ∈ Fr, ivk_hat = g2^isk, and W = g1^r, W_hat = g2^r with one
+    //       shared r, so that e(W, g2) == e(g1, W_hat)
+    let keys = curve::sample_issuer_keys::<Curve, _>(bp.g1, bp.g2, &mut OsRng);
 
-    // 6) Store everything for future use
-    *guard = Some(StoredIssuerKeys {
+    StoredIssuerKeys {
+        version,
         bp,
-        isk:     isk_clone,   // <-- clone here
-        ivk_hat,
-        W,
-        W_hat,
-    });
-
-    println!("✔ ISK initialized in memory");
-    Ok(ivk)
+        isk: keys.isk,
+        ivk_hat: keys.ivk_hat,
+        W: keys.W,
+        W_hat: keys.W_hat,
+        issuer_id: issuer_id.to_string(),
+    }
 }
 
-const TAG: &[u8] = b"syra-user-id";
+/// The `user_id` the startup self-test issues a credential for.
+const SELF_TEST_USER_ID: &str = "syra-self-test";
 
-/// Deterministic hash-to-field:  sub  →  s ∈ Fr  (never 0).
-pub fn s_from_sub<S: AsRef<str>>(sub: S) -> Fr {
-    let mut acc = Fr::zero();
+/// Reasons [`self_test`] rejects a key set.
+#[derive(thiserror::Error, Debug)]
+pub enum SelfTestError {
+    #[error("e(W, g2) != e(g1, W_hat)")]
+    InconsistentW,
+    #[error("s + isk = 0 for the self-test user")]
+    InversionFailed,
+    #[error("self-test user is revoked")]
+    Revoked,
+    #[error("issued credential does not verify against the published ivk")]
+    CredentialRejected,
+}
 
-    for &byte in sub.as_ref().as_bytes() {
-        acc.mul_assign(Fr::from(256u64));      // acc *= 256
-        acc.add_assign(Fr::from(byte as u64)); // acc += byte
+/// Issue a credential for [`SELF_TEST_USER_ID`] with the local `isk` and
+/// check it with [`credential::verify_credential`] against `keys`' own
+/// bundle and `revocation`, so keys whose generators, `isk` or `ivk_hat`
+/// disagree are caught before the first real user is served. `W_hat` only
+/// enters through the `e(W, g2) == e(g1, W_hat)` check.
+fn self_test(keys: &StoredIssuerKeys, revocation: &RevocationList) -> Result<(), SelfTestError> {
+    let ivk = keys.ivk_bundle();
+    if !ivk.is_consistent() {
+        return Err(SelfTestError::InconsistentW);
     }
-
-    if acc.is_zero() { Fr::one() } else { acc } // avoid 0 just like TS
+    let s = s_from_sub(SELF_TEST_USER_ID);
+    let (usk, _) = curve::derive_user_key::<Curve>(keys.bp.g1, keys.bp.g2, keys.isk, s)
+        .ok_or(SelfTestError::InversionFailed)?;
+    let accumulator = revocation.accumulator(keys);
+    let witness = revocation
+        .non_membership_witness(keys, s)
+        .ok_or(SelfTestError::Revoked)?;
+    if !credential::verify_credential(&ivk, s, &usk, &accumulator, &witness) {
+        return Err(SelfTestError::CredentialRejected);
+    }
+    Ok(())
 }
 
+/// Issues `(usk, usk_hat)` for a user who proves they hold a token for
+/// `user_id`.
+///
+/// Idempotent: the key is a function of `user_id` and the current issuer
+/// keys only, so repeating a request (e.g. after a shutdown cut the
+/// connection before the response arrived) returns the same `usk`,
+/// `usk_hat` and `s`. A consumed nonce cannot be redeemed twice, so a retry
+/// under `--require-challenge` needs a fresh one from `GET /challenge`; the
+/// key it gets is still the same. Each issuance is audited separately.
+///
 /// # Arguments
 /// * `state: web::Data<AppState>`  
 ///   Shared application state, containing:
-///   - `dkg: Option<DKGState>`: holds the server’s distributed key share (must be present).  
+///   - `issuer_keys`: the current issuer keys (must be present).  
+///   - `dkg`: this node’s DKG share, used once complete.  
 ///   - `verifier`: a proof verifier for user identity.  
+/// * `http_req: HttpRequest` — used for the peer address the rate limiter keys on.  
+/// * `query: web::Query<KeyEncodingQuery>` — `?compression=compressed|uncompressed` (default compressed)
+///   picks the encoding of `usk`, `usk_hat` and `ivk`.  
 /// * `req: web::Json<GenerateKeyRequest>`  
 ///   The JSON body with fields:  
+///   - `iss: String` — the token issuer (optional, defaults to Google).  
 ///   - `user_id: String` — the client’s identifier.  
+///   - `sub_encoding: SubEncoding` — `"decimal"` (default), `"hex"` or `"base64url"`; how `user_id` is spelled.  
 ///   - `kid: String` — key identifier.  
-///   - `proof: String` — a cryptographic proof binding `user_id` and `kid`.  
+///   - `proof` — a cryptographic proof binding `user_id` and `kid`: a base-64 string, or the SnarkJS JSON object.  
+///   - `proof_format: Option<ProofFormat>` — `"base64"` or `"snarkjs-json"`; inferred from `proof` when omitted.  
+///   - `claims: Option<JwtClaims>` — the token's `exp` / `iat` / `nbf`, checked when present.  
+///   - `nonce: Option<String>` — a nonce from `GET /challenge`, proved as `subStatement`; single use.  
 ///
 /// # Returns
-/// - `200 OK` with JSON `GenerateKeyResponse { ivk, usk, usk_hat }` on success.  
-/// - `400 Bad Request` if the DKG state is not initialized.  
+/// - `200 OK` with JSON `GenerateKeyResponse { version, s, ivk, usk, usk_hat }` on success.  
+/// - `404 Not Found` if the issuer keys are not initialized.  
+/// - `400 Bad Request` if `--require-dkg` is set and the DKG share is not complete yet.  
 /// - `401 Unauthorized` if proof verification fails or the proof is invalid.  
 ///
 /// # Pseudocode
 /// ```text
-/// // 1) Ensure DKG has been run and retrieve stored state
-/// if state.dkg is None:
-///     return BadRequest("DKG not initialized")
-/// stored = state.dkg.clone()
+/// // 0) Copy the current issuer keys and read the subject
+/// stored = state.issuer_keys.clone()                   // NotFound if none yet
+/// sub    = req.sub_encoding.to_decimal(req.user_id)    // BadRequest on mismatch
 ///
-/// // 2) Verify the user’s proof
-/// verified = verifier.verify(req.user_id, req.kid, req.proof)
+/// // 1) Redeem the nonce (single use), then verify the user’s proof
+/// challenge = req.nonce ? challenges.consume(req.nonce) : None   // Unauthorized if bad/required
+/// proof     = decode_proof(req.proof, req.proof_format)   // base-64 or SnarkJS JSON
+/// verified  = verifier.verify(req.iss, sub, req.kid, req.circuit_id, proof, challenge)
 /// if not verified:
 ///     return Unauthorized("invalid proof")
 ///
-/// // 3) Derive field element s = H_to_Fr(user_id)
-/// s = field_elem_from_try_and_incr(user_id.bytes)
-///
-/// // 4) Deserialize this node’s secret share isk_i
-/// isk_i_bytes = hex::decode(stored.isk_i)
-/// isk_i = Fr.deserialize(isk_i_bytes)
-///
-/// // 5) Compute inv = (s + isk_i)^{-1} in the field Fr
-/// inv = (s + isk_i).inverse()
+/// // 2) Derive s = Blake2b-to-Fr("syra-user-id" ‖ NFC(sub)), never zero
+/// s = s_from_sub(sub)
 ///
-/// // 6) Hash to group generators g1 (in G1) and g2 (in G2)
-/// g1 = H_to_G1("syra-generator")
-/// g2 = H_to_G2("syra-generator-2")
+/// // 3) Sign with this node’s DKG share once every dealer’s share is in,
+/// //    with the local isk before that unless --require-dkg forbids it
+/// if not state.dkg_complete() and state.require_dkg:
+///     return BadRequest("DKG not initialized")
+/// keys = state.signing_keys(stored)   // isk = isk_i, ivk_hat = g2^{isk_i} under DKG
 ///
-/// // 7) Exponentiate generators by inv to get the user’s secret keys
-/// usk    = hex_encode(g1 * inv)
-/// usk_hat= hex_encode(g2 * inv)
+/// // 4)–5) usk = g1^{1/(s+isk)}, usk_hat = g2^{1/(s+isk)}, g1/g2 from keys.bp
+/// inv = ct_inverse(s + keys.isk)      // Unprocessable if s == -isk
+/// usk, usk_hat = keys.bp.g1 * inv, keys.bp.g2 * inv
 ///
-/// // 8) Respond with the public IVK plus the two secret key shares
-/// return Ok(GenerateKeyResponse { ivk: stored.ivk, usk, usk_hat })
+/// // 6) Audit the issuance, then answer with the bundle of the key just used
+/// audit.record(sub, req.kid, stored.version)   // ServiceUnavailable if fail-closed and unwritable
+/// return Ok(GenerateKeyResponse { version: stored.version, s, ivk: keys.ivk_bundle(), usk, usk_hat })
 /// ```
 ///
 /// # Errors
-/// - Returns `400 Bad Request` if the DKG state is uninitialized.
-/// - Returns `401 Unauthorized` if proof verification fails or if inversion in the field is impossible.
+/// - Returns `404 Not Found` if the issuer keys are not initialized.
+/// - Returns `400 Bad Request` if `--require-dkg` is set and the DKG share is not complete yet.
+/// - Returns `400 Bad Request` if `circuit_id` names no registered verifying key.
+/// - Returns `400 Bad Request` if `user_id` is not valid in `sub_encoding`.
+/// - Returns `401 Unauthorized` if proof verification fails or the supplied claims are not currently valid.
+/// - Returns `503 Service Unavailable` if the provider's JWKS stayed unreachable through the retries.
+/// - Returns `503 Service Unavailable` if the audit log cannot record the issuance (fail-closed).
+/// - Returns `401 Unauthorized` if the nonce is unknown, already used or expired, or missing while
+///   challenges are required.
+/// - Returns `422 Unprocessable Entity` if inversion in the field is impossible.
+/// - Returns `500 Internal Server Error` if a key fails to serialize.
+/// - Returns `413 Payload Too Large` for a body longer than `--max-proof-bytes`.
+/// - Returns `429 Too Many Requests` with `Retry-After` once the caller's IP has used up its bucket.
+///
+/// Errors are JSON `{ "code", "message" }` bodies, see [`ApiError`].
 ///
-#[post("/admin/generate_user_key")]
+#[utoipa::path(
+    post,
+    path = "/admin/generate_user_key",
+    params(KeyEncodingQuery),
+    request_body = GenerateKeyRequest,
+    responses(
+        (status = 200, body = GenerateKeyResponse),
+        (status = 400, description = "DKG share required but missing, unknown circuit_id, or user_id not in sub_encoding", body = ErrorBody),
+        (status = 401, description = "invalid proof, claims, challenge or admin token", body = ErrorBody),
+        (status = 404, description = "issuer keys not initialized", body = ErrorBody),
+        (status = 413, description = "body longer than --max-proof-bytes", body = ErrorBody),
+        (status = 422, description = "s + isk is not invertible", body = ErrorBody),
+        (status = 429, description = "rate limited; see Retry-After", body = ErrorBody),
+        (status = 500, description = "serialization failed", body = ErrorBody),
+        (status = 503, description = "identity provider JWKS unreachable, or audit log unavailable", body = ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[post("/generate_user_key")]
+#[tracing::instrument(skip_all, fields(user = %user_tag(&req.user_id), kid = %req.kid, iss = %req.iss))]
 async fn generate_user_key(
     state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<KeyEncodingQuery>,
     req: web::Json<GenerateKeyRequest>,
-) -> Result<HttpResponse, actix_web::Error> {
-    let lock = state.issuer_keys.lock().unwrap();
+) -> Result<HttpResponse, ApiError> {
+    state.metrics.keygen_requests.inc();
 
-    let stored = if let Some(s) = &*lock {
-        s.clone()
-    } else {
-        return Err(actix_web::error::ErrorBadRequest("DKG state not initialized; call /admin/receive_dkg first"));
-    };
+    // Groth16 verification is expensive; throttle per client before doing any.
+    if let Some(addr) = http_req.peer_addr() {
+        state.rate_limiter.check(addr.ip()).map_err(ApiError::RateLimited)?;
+    }
 
-    println!("{}", req.proof);
+    let resp = issue_user_key(&state, &req, Compress::from(query.compression)).await?;
+    Ok(HttpResponse::Ok().json(resp))
+}
 
-    println!("{}", req.kid);
+/// Most items `generate_user_keys_batch` takes; each one costs a Groth16
+/// verification.
+const MAX_KEYGEN_BATCH: usize = 100;
 
-    println!("{}", req.user_id);
+/// One entry of the `generate_user_keys_batch` response, at its request's
+/// position: the keys, or the error that item alone would have returned.
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+enum BatchKeyResult {
+    Issued(GenerateKeyResponse),
+    Failed(ErrorBody),
+}
 
-    // 1) verify proof
-    let verified = state
-        .verifier
-        .verify(&req.user_id, &req.kid, &req.proof)
+/// [`generate_user_key`] for many users at once, for provisioning tools.
+/// Items are independent: a bad proof fails its own entry, not the batch.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state, as for `generate_user_key`.
+/// * `http_req: HttpRequest` — used for the peer address the rate limiter keys on.
+/// * `query: web::Query<KeyEncodingQuery>` — encoding of every issued key.
+/// * `reqs: web::Json<Vec<GenerateKeyRequest>>` — at most [`MAX_KEYGEN_BATCH`] items.
+///
+/// # Returns
+/// - `200 OK` with a JSON array, one entry per request in order: a
+///   `GenerateKeyResponse`, or `{ "code", "message" }` for items that failed.
+/// - `404 Not Found` if the issuer keys are not initialized.
+/// - `413 Payload Too Large` if the batch exceeds [`MAX_KEYGEN_BATCH`].
+/// - `429 Too Many Requests` with `Retry-After` once the caller's IP has used up its bucket.
+///
+/// Subjects, nonces, proof encodings and claims are checked item by item;
+/// the proofs that pass are verified with one
+/// [`ProofVerifier::verify_batch`] per `iss`, so each distinct `kid` is
+/// fetched at most once and the pairings are batched.
+#[utoipa::path(
+    post,
+    path = "/admin/generate_user_keys_batch",
+    params(KeyEncodingQuery),
+    request_body = Vec<GenerateKeyRequest>,
+    responses(
+        (status = 200, body = [BatchKeyResult]),
+        (status = 401, description = "invalid admin token", body = ErrorBody),
+        (status = 404, description = "issuer keys not initialized", body = ErrorBody),
+        (status = 413, description = "too many items", body = ErrorBody),
+        (status = 429, description = "rate limited; see Retry-After", body = ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[post("/generate_user_keys_batch")]
+async fn generate_user_keys_batch(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<KeyEncodingQuery>,
+    reqs: web::Json<Vec<GenerateKeyRequest>>,
+) -> Result<HttpResponse, ApiError> {
+    if reqs.len() > MAX_KEYGEN_BATCH {
+        return Err(ApiError::BatchTooLarge(reqs.len(), MAX_KEYGEN_BATCH));
+    }
+    if let Some(addr) = http_req.peer_addr() {
+        state.rate_limiter.check(addr.ip()).map_err(ApiError::RateLimited)?;
+    }
+
+    let compress = Compress::from(query.compression);
+    let stored = state
+        .issuer_keys
+        .lock_or_recover()
+        .as_ref()
+        .ok_or(ApiError::IssuerKeysNotInitialized)?
+        .clone();
+
+    // 1) Everything but the pairing, per item: a failure here fails only
+    //    its own entry
+    let mut outcomes: Vec<Result<String, ApiError>> = Vec::with_capacity(reqs.len());
+    let mut by_iss: BTreeMap<&str, (Vec<usize>, Vec<BatchItem>)> = BTreeMap::new();
+    for (index, req) in reqs.iter().enumerate() {
+        state.metrics.keygen_requests.inc();
+        let span = tracing::info_span!(
+            "batch_item", index, user = %user_tag(&req.user_id), kid = %req.kid, iss = %req.iss
+        );
+        match span.in_scope(|| batch_item(&state, req)) {
+            Ok(item) => {
+                outcomes.push(Ok(item.sub.clone()));
+                let (indices, items) = by_iss.entry(req.iss.as_str()).or_default();
+                indices.push(index);
+                items.push(item);
+            }
+            Err(e) => outcomes.push(Err(e)),
+        }
+    }
+
+    // 2) One `verify_batch` per issuer: each kid is looked up once and the
+    //    proofs under one circuit share a pairing check
+    for (iss, (indices, items)) in by_iss {
+        let timer = state.metrics.verify_duration.start_timer();
+        let verdicts = state.verifier.verify_batch(iss, &items).await;
+        let elapsed = timer.stop_and_record();
+        tracing::info!(iss, items = items.len(), elapsed_ms = elapsed * 1e3, "batch verification finished");
+        for (position, index) in indices.into_iter().enumerate() {
+            outcomes[index] = match &verdicts {
+                Ok(verdicts) if verdicts[position] => continue,
+                Ok(_) => {
+                    state.metrics.proof_verify_failures.inc();
+                    Err(ApiError::InvalidProof)
+                }
+                Err(e) => Err(verification_error(&state, e)),
+            };
+        }
+    }
+
+    // 3) Keys for the items that verified, in request order
+    let results: Vec<BatchKeyResult> = outcomes
+        .into_iter()
+        .zip(reqs.iter())
+        .map(|(outcome, req)| {
+            let issued = outcome.and_then(|sub| {
+                let resp = derive_user_keys(&state, &stored, &sub, compress)?;
+                state.audit.record(&sub, Some(&req.kid), stored.version)?;
+                Ok(resp)
+            });
+            match issued {
+                Ok(keys) => BatchKeyResult::Issued(keys),
+                Err(e) => BatchKeyResult::Failed(ErrorBody::from(&e)),
+            }
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// One `generate_user_keys_batch` item up to its pairing check, in the order
+/// `issue_user_key` runs the steps: subject, nonce, proof encoding, claims,
+/// circuit id.
+fn batch_item(state: &AppState, req: &GenerateKeyRequest) -> Result<BatchItem, ApiError> {
+    let sub = req
+        .sub_encoding
+        .to_decimal(&req.user_id)
+        .map_err(|e| ApiError::InvalidSubject(format!("{e:#}")))?;
+    let statement = redeem_challenge(state, req)?;
+    let proof = decode_proof(&req.proof, req.proof_format).map_err(|e| verification_error(state, &e))?;
+    if let Some(claims) = &req.claims {
+        state.verifier.check_claims(claims).map_err(|e| verification_error(state, &e))?;
+    }
+    if let Some(id) = &req.circuit_id {
+        if state.verifier.verifying_key(id).is_none() {
+            return Err(ApiError::UnknownCircuit(id.clone()));
+        }
+    }
+    Ok(BatchItem { sub, kid: req.kid.clone(), circuit_id: req.circuit_id.clone(), proof, statement })
+}
+
+/// `POST /admin/recover_user_key` request.
+#[derive(Deserialize, ToSchema)]
+struct RecoverKeyRequest {
+    /// plain‐text user identifier, as sent to `generate_user_key`
+    user_id: String,
+}
+
+/// Re-issues the keys of an existing user without a proof, for recovery
+/// after an out-of-band identity check. The derivation is the one
+/// `generate_user_key` runs after verifying, so under the same issuer keys
+/// (and DKG share) the result equals the original issuance.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state, as for `generate_user_key`.
+/// * `query: web::Query<KeyEncodingQuery>` — encoding of `usk`, `usk_hat` and `ivk`.
+/// * `req: web::Json<RecoverKeyRequest>` — the `user_id` to recover.
+///
+/// # Returns
+/// - `200 OK` with JSON `GenerateKeyResponse { version, s, ivk, usk, usk_hat }`.
+/// - `404 Not Found` if the issuer keys are not initialized.
+/// - `400 Bad Request` if `--require-dkg` is set and no DKG share was accepted yet.
+/// - `422 Unprocessable Entity` if inversion in the field is impossible.
+/// - `500 Internal Server Error` if a key fails to serialize.
+/// - `503 Service Unavailable` if the audit log cannot record the issuance (fail-closed).
+///
+/// Nothing but the admin token stands between a caller and any user's key
+/// here; every call is logged at `warn` with the hashed user tag.
+#[utoipa::path(
+    post,
+    path = "/admin/recover_user_key",
+    params(KeyEncodingQuery),
+    request_body = RecoverKeyRequest,
+    responses(
+        (status = 200, body = GenerateKeyResponse),
+        (status = 400, description = "DKG share required but missing", body = ErrorBody),
+        (status = 401, description = "invalid admin token", body = ErrorBody),
+        (status = 404, description = "issuer keys not initialized", body = ErrorBody),
+        (status = 422, description = "s + isk is not invertible", body = ErrorBody),
+        (status = 500, description = "serialization failed", body = ErrorBody),
+        (status = 503, description = "audit log unavailable", body = ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[post("/recover_user_key")]
+#[tracing::instrument(skip_all, fields(user = %user_tag(&req.user_id)))]
+async fn recover_user_key(
+    state: web::Data<AppState>,
+    query: web::Query<KeyEncodingQuery>,
+    req: web::Json<RecoverKeyRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let lock = state.issuer_keys.lock_or_recover();
+    let stored = lock.as_ref().ok_or(ApiError::IssuerKeysNotInitialized)?;
+
+    tracing::warn!("re-deriving user key without proof verification");
+    let resp = derive_user_keys(&state, stored, &req.user_id, Compress::from(query.compression))?;
+    state.audit.record(&req.user_id, None, stored.version)?;
+    Ok(HttpResponse::Ok().json(resp))
+}
+
+/// `POST /admin/generate_user_key_jwt` request.
+#[derive(Deserialize, ToSchema)]
+struct NativeJwtRequest {
+    /// the RS256 ID token itself, `header.claims.signature`
+    token: String,
+    kid: String,       // key-id of the signing key
+}
+
+/// `generate_user_key` for clients that cannot produce a proof: the ID
+/// token's RS256 signature is checked directly against the provider's JWK,
+/// and the key is issued for its `sub` claim. The server sees the token and
+/// `sub` in the clear, so this path is off unless `--allow-native-jwt` is set.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state, as for `generate_user_key`.
+/// * `http_req: HttpRequest` — used for the peer address the rate limiter keys on.
+/// * `query: web::Query<KeyEncodingQuery>` — encoding of `usk`, `usk_hat` and `ivk`.
+/// * `req: web::Json<NativeJwtRequest>` — `{ "token": "<jwt>", "kid": "<kid>" }`.
+///
+/// # Returns
+/// - `200 OK` with JSON `GenerateKeyResponse { version, s, ivk, usk, usk_hat }`.
+///
+/// # Errors
+/// - `404 Not Found` if the issuer keys are not initialized.
+/// - `400 Bad Request` if `--require-dkg` is set and no DKG share was accepted yet.
+/// - `401 Unauthorized` `INVALID_TOKEN` if the token is malformed, from an issuer not in the
+///   allowlist, its signature does not verify, or its `aud` / `azp` name no
+///   `--allowed-audience`; `INVALID_TOKEN_CLAIMS` if it is expired.
+/// - `403 Forbidden` `NATIVE_JWT_DISABLED` unless `--allow-native-jwt` is set.
+/// - `422 Unprocessable Entity` if inversion in the field is impossible.
+/// - `429 Too Many Requests` with `Retry-After` once the caller's IP has used up its bucket.
+/// - `503 Service Unavailable` if the provider's JWKS is unreachable or the audit log cannot
+///   record the issuance (fail-closed).
+#[utoipa::path(
+    post,
+    path = "/admin/generate_user_key_jwt",
+    params(KeyEncodingQuery),
+    request_body = NativeJwtRequest,
+    responses(
+        (status = 200, body = GenerateKeyResponse),
+        (status = 400, description = "DKG share required but missing", body = ErrorBody),
+        (status = 401, description = "invalid token, claims or admin token", body = ErrorBody),
+        (status = 403, description = "--allow-native-jwt is not set", body = ErrorBody),
+        (status = 404, description = "issuer keys not initialized", body = ErrorBody),
+        (status = 422, description = "s + isk is not invertible", body = ErrorBody),
+        (status = 429, description = "rate limited; see Retry-After", body = ErrorBody),
+        (status = 503, description = "identity provider JWKS unreachable, or audit log unavailable", body = ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[post("/generate_user_key_jwt")]
+#[tracing::instrument(skip_all, fields(kid = %req.kid))]
+async fn generate_user_key_jwt(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    query: web::Query<KeyEncodingQuery>,
+    req: web::Json<NativeJwtRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if !state.allow_native_jwt {
+        return Err(ApiError::NativeJwtDisabled);
+    }
+    if let Some(addr) = http_req.peer_addr() {
+        state.rate_limiter.check(addr.ip()).map_err(ApiError::RateLimited)?;
+    }
+
+    let claims = state.verifier.verify_jwt_rs256(&req.token, &req.kid).await.map_err(|e| {
+        if let Some(claims_err) = e.downcast_ref::<ClaimsError>() {
+            tracing::info!(error = %claims_err, "token claims rejected");
+            return ApiError::InvalidClaims(claims_err.to_string());
+        }
+        if let Some(unavailable) = e.downcast_ref::<JwksUnavailable>() {
+            tracing::error!(error = %unavailable, "JWKS fetch failed");
+            return ApiError::ProviderUnavailable(unavailable.to_string());
+        }
+        tracing::warn!(error = %e, "native token verification failed");
+        ApiError::InvalidToken(format!("{e:#}"))
+    })?;
+    tracing::info!(user = %user_tag(&claims.sub), iss = %claims.iss, "token verified natively");
+
+    let lock = state.issuer_keys.lock_or_recover();
+    let stored = lock.as_ref().ok_or(ApiError::IssuerKeysNotInitialized)?;
+    let resp = derive_user_keys(&state, stored, &claims.sub, Compress::from(query.compression))?;
+    state.audit.record(&claims.sub, Some(&req.kid), stored.version)?;
+    Ok(HttpResponse::Ok().json(resp))
+}
+
+/// Steps 1–5 of `generate_user_key` for one request: redeem the nonce,
+/// verify the proof and derive `(usk, usk_hat)`, encoded per `compress`.
+async fn issue_user_key(
+    state: &AppState,
+    req: &GenerateKeyRequest,
+    compress: Compress,
+) -> Result<GenerateKeyResponse, ApiError> {
+    // A copy, so no guard is held across the verification below: that would
+    // stall every other request needing the keys, and a std guard must not
+    // live across an await anyway.
+    let stored = state
+        .issuer_keys
+        .lock_or_recover()
+        .as_ref()
+        .ok_or(ApiError::IssuerKeysNotInitialized)?
+        .clone();
+
+    // A mistyped encoding is the client's fault; reject it before the nonce
+    // is spent.
+    let sub = req
+        .sub_encoding
+        .to_decimal(&req.user_id)
+        .map_err(|e| ApiError::InvalidSubject(format!("{e:#}")))?;
+
+    // 1) redeem the challenge before verifying, so a captured request fails
+    //    on replay even if its proof is valid
+    let challenge = redeem_challenge(state, req)?;
+
+    //    then the proof (and the token's time claims, when supplied)
+    tracing::debug!("proof verification started");
+    let timer = state.metrics.verify_duration.start_timer();
+    let verified = check_proof(state.verifier.as_ref(), req, &sub, challenge)
         .await
-        .map_err(|e| {
-            log::warn!("proof verification failed: {e}");
-            actix_web::error::ErrorUnauthorized("invalid proof")
-        })?;
+        .map_err(|e| verification_error(state, &e))?;
+    let elapsed = timer.stop_and_record();
+    tracing::info!(verified, elapsed_ms = elapsed * 1e3, "proof verification finished");
 
     if !verified {
-        return Err(actix_web::error::ErrorUnauthorized("invalid proof"));
+        state.metrics.proof_verify_failures.inc();
+        return Err(ApiError::InvalidProof);
+    }
+
+    let resp = derive_user_keys(state, &stored, &sub, compress)?;
+    state.audit.record(&sub, Some(&req.kid), stored.version)?;
+    Ok(resp)
+}
+
+/// The nonce `req` was proved for, redeemed; `None` if it names none and
+/// `--require-challenge` is off.
+fn redeem_challenge(state: &AppState, req: &GenerateKeyRequest) -> Result<Option<ark_bn254::Fr>, ApiError> {
+    match &req.nonce {
+        Some(nonce) => Ok(Some(state.challenges.consume(nonce).map_err(|e| {
+            tracing::info!(error = %e, "challenge rejected");
+            ApiError::InvalidChallenge(e)
+        })?)),
+        None if state.require_challenge => Err(ApiError::InvalidChallenge(ChallengeError::Missing)),
+        None => Ok(None),
+    }
+}
+
+/// The answer to a verification that ended without a verdict on the proof:
+/// bad claims, an unknown circuit, an unreachable provider, or anything
+/// else, which counts as a failed verification.
+fn verification_error(state: &AppState, e: &anyhow::Error) -> ApiError {
+    if let Some(claims_err) = e.downcast_ref::<ClaimsError>() {
+        tracing::info!(error = %claims_err, "token claims rejected");
+        return ApiError::InvalidClaims(claims_err.to_string());
+    }
+    if let Some(unknown) = e.downcast_ref::<UnknownCircuit>() {
+        tracing::info!(error = %unknown, "unknown circuit requested");
+        return ApiError::UnknownCircuit(unknown.0.clone());
+    }
+    // Not the client's fault, and not a verdict on the proof.
+    if let Some(unavailable) = e.downcast_ref::<JwksUnavailable>() {
+        tracing::error!(error = %unavailable, "JWKS fetch failed");
+        return ApiError::ProviderUnavailable(unavailable.to_string());
     }
+    tracing::warn!(error = %e, "proof verification failed");
+    state.metrics.proof_verify_failures.inc();
+    ApiError::InvalidProof
+}
 
+/// Steps 2–5 of `generate_user_key`: `s` from `user_id`, then
+/// `(usk, usk_hat)` under this node's DKG share, or `stored.isk` until the
+/// share is complete, encoded per `compress`. Deterministic, so the same `user_id` under
+/// the same keys always gets the same `usk`.
+fn derive_user_keys(
+    state: &AppState,
+    stored: &StoredIssuerKeys,
+    user_id: &str,
+    compress: Compress,
+) -> Result<GenerateKeyResponse, ApiError> {
     // 2) Derive s ∈ Fr from user_id
-    let s: Fr = s_from_sub(req.user_id.clone());
-    let mut le32 = [0u8; 32];
-    s.serialize_compressed(&mut le32[..]).unwrap();   // LE, 0-padded
-    println!("s (32-byte LE) = {}", hex::encode(le32));
-    let isk = stored.isk.clone();
-
-    let inv = (s + isk)
-        .inverse()
-        .expect("s + isk_i not invertible");
-
-    // 4) usk = g1^invR
-    let usk_pt = (G1Projective::from(stored.bp.g1) * inv.clone()).into_affine();
-    let mut buf_usk = Vec::new();
-    usk_pt.serialize_compressed(&mut buf_usk).unwrap();
-    let usk = hex::encode(buf_usk);
-
-    // 5) usk_hat = g2^invR
-    let usk_hat_pt = (G2Projective::from(stored.bp.g2) * inv.clone()).into_affine();
-    let mut buf_usk_hat = Vec::new();
-    usk_hat_pt.serialize_compressed(&mut buf_usk_hat).unwrap();
-    let usk_hat = hex::encode(buf_usk_hat);
-
-    let ivk_hex = IvkBundle {
-        bp:       stored.bp.clone(),
-        ivk_hat:  stored.ivk_hat,
-        W:        stored.W,
-        W_hat:    stored.W_hat,
-    }.to_hex_string();
-
-    let resp = GenerateKeyResponse {
+    let s: Fr = s_from_sub(user_id);
+    let s_hex = fr_to_hex(&s);
+    tracing::debug!(s = %s_hex, "derived s");
+    // Prefer this node's DKG share; fall back to the local isk until every
+    // dealer's share is in.
+    // With `--require-dkg` a key under the local isk would not verify against
+    // the bundle published once the share arrives, so refuse instead.
+    let from_dkg = state.dkg_complete();
+    if !from_dkg && state.require_dkg {
+        return Err(ApiError::DkgNotInitialized);
+    }
+    // A copy whose `isk` is zeroized when it drops at the end of this function.
+    let keys = state.signing_keys(stored);
+
+    // 4)–5) usk = g1^{1/(s+isk)}, usk_hat = g2^{1/(s+isk)}.
+    // s + isk == 0 iff s == -isk; reject that (negligible, but remotely
+    // reachable) case instead of panicking. Answering it reveals only that
+    // s == -isk; `curve::ct_inverse` keeps every other input on the same timing.
+    let (usk_pt, usk_hat_pt) = curve::derive_user_key::<Curve>(keys.bp.g1, keys.bp.g2, keys.isk, s)
+        .ok_or(ApiError::InversionFailed)?;
+    let usk = g1_to_hex_with(&usk_pt, compress);
+    let usk_hat = g2_to_hex_with(&usk_hat_pt, compress);
+    tracing::info!(version = stored.version, from_dkg, "user key derived");
+
+    // The bundle of the scalar we just signed with, not the local `ivk_hat`.
+    let ivk_hex = keys.ivk_bundle().to_hex_string_with(compress)?;
+
+    Ok(GenerateKeyResponse {
+        version: stored.version,
+        s: s_hex,
         ivk: ivk_hex,
         usk,
         usk_hat,
+    })
+}
+/// Publishes the issuer verification key bundle so verifiers can bootstrap
+/// without requesting a user key. Once a DKG share was accepted, `ivk_hat`
+/// is `g2^{isk_i}`, the key `generate_user_key` then issues under.
+///
+/// # Returns
+/// - `200 OK` with JSON `IvkResponse { version, ivk, g1, g2, ivk_hat, W, W_hat, issuer_id, scheme }`,
+///   all but `version`, `issuer_id` and `scheme` hex; `scheme` names the curve, tags and hash.
+/// - `404 Not Found` if issuer keys are not initialized yet.
+#[utoipa::path(
+    get,
+    path = "/ivk",
+    responses(
+        (status = 200, body = IvkResponse),
+        (status = 404, description = "issuer keys not initialized", body = ErrorBody),
+        (status = 500, description = "serialization failed", body = ErrorBody),
+    ),
+)]
+#[get("/ivk")]
+async fn get_ivk(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let lock = state.issuer_keys.lock_or_recover();
+    let stored = lock.as_ref().ok_or(ApiError::IssuerKeysNotInitialized)?;
+    let ivk = state.signing_keys(stored).ivk_bundle();
+
+    Ok(HttpResponse::Ok().json(IvkResponse {
+        version: ivk.version,
+        ivk:     ivk.to_hex_string()?,
+        g1:      g1_to_hex(&ivk.bp.g1),
+        g2:      g2_to_hex(&ivk.bp.g2),
+        ivk_hat: g2_to_hex(&ivk.ivk_hat),
+        W:       g1_to_hex(&ivk.W),
+        W_hat:   g2_to_hex(&ivk.W_hat),
+        issuer_id: ivk.issuer_id,
+        scheme:  state.scheme.clone(),
+    }))
+}
+
+/// One entry of `GET /ivk/versions`.
+#[derive(Serialize, ToSchema)]
+struct IvkVersion {
+    version: u32,
+    /// IVK bundle hex, as in `GET /ivk`
+    ivk: String,
+    /// `true` for the key new credentials are issued under; retired
+    /// versions are verify-only
+    active: bool,
+}
+
+/// Lists every issuer key version this issuer still holds, so verifiers can
+/// accept credentials issued before a rotation.
+///
+/// # Returns
+/// - `200 OK` with a JSON array of `IvkVersion { version, ivk, active }`,
+///   oldest first; only the current version has `active: true`.
+/// - `404 Not Found` if issuer keys are not initialized yet.
+#[utoipa::path(
+    get,
+    path = "/ivk/versions",
+    responses(
+        (status = 200, body = [IvkVersion]),
+        (status = 404, description = "issuer keys not initialized", body = ErrorBody),
+        (status = 500, description = "serialization failed", body = ErrorBody),
+    ),
+)]
+#[get("/ivk/versions")]
+async fn get_ivk_versions(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let lock = state.issuer_keys.lock_or_recover();
+    let current = state.signing_keys(lock.as_ref().ok_or(ApiError::IssuerKeysNotInitialized)?);
+    let retired = state.retired_issuer_keys.lock_or_recover();
+
+    let versions = retired
+        .iter()
+        .map(|k| (k, false))
+        .chain(std::iter::once((&current, true)))
+        .map(|(k, active)| {
+            Ok(IvkVersion {
+                version: k.version,
+                ivk: k.ivk_bundle().to_hex_string()?,
+                active,
+            })
+        })
+        .collect::<Result<Vec<_>, ApiError>>()?;
+
+    Ok(HttpResponse::Ok().json(versions))
+}
+
+/// Publishes the Groth16 verifying key of one circuit in SnarkJS's
+/// `verification_key.json` shape, so a SnarkJS frontend proves against the
+/// exact key this issuer verifies with.
+///
+/// # Arguments
+/// * `circuit_id` — path segment: `rsa`, `ec`, or an id registered with `--circuit-vk`.
+///
+/// # Returns
+/// - `200 OK` with the SnarkJS JSON (`protocol`, `curve`, `nPublic`,
+///   `vk_alpha_1`, `vk_beta_2`, `vk_gamma_2`, `vk_delta_2`, `IC`).
+///
+/// # Errors
+/// - `400 Bad Request` `UNKNOWN_CIRCUIT` if no key is registered as `circuit_id`.
+#[utoipa::path(
+    get,
+    path = "/vk/{circuit_id}",
+    params(("circuit_id" = String, Path, description = "circuit whose verifying key to return")),
+    responses(
+        (status = 200, description = "SnarkJS verification_key.json", body = Object),
+        (status = 400, description = "unknown circuit id", body = ErrorBody),
+    ),
+)]
+#[get("/vk/{circuit_id}")]
+async fn get_vk(state: web::Data<AppState>, circuit_id: web::Path<String>) -> Result<HttpResponse, ApiError> {
+    let circuit_id = circuit_id.into_inner();
+    let vk = state
+        .verifier
+        .verifying_key(&circuit_id)
+        .ok_or(ApiError::UnknownCircuit(circuit_id))?;
+    let json = vk_to_snarkjs_json(&vk).expect("a serde_json::Value always serializes");
+
+    Ok(HttpResponse::Ok().content_type("application/json").body(json))
+}
+
+/// `POST /pseudonym` request.
+#[derive(Deserialize, ToSchema)]
+struct PseudonymRequest {
+    /// compressed G1 `usk`, hex
+    usk: String,
+    /// the context (e.g. a relying party's origin) to derive the pseudonym for
+    context: String,
+}
+
+/// `POST /pseudonym` response.
+#[derive(Serialize, ToSchema)]
+struct PseudonymResponse {
+    /// `e(usk, H(context))`, compressed (576 bytes), hex
+    nym: String,
+}
+
+/// Derives the user's pseudonym for a context, the value SyRA signatures in
+/// that context carry, for clients without a pairing library. Stable per
+/// `(usk, context)` and unlinkable across contexts.
+///
+/// `usk` is a secret: only call this on an issuer you already trust with it
+/// (it has seen `usk` at issuance). Nothing is stored or logged.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state; only the rate limiter is used.
+/// * `http_req: HttpRequest` — used for the peer address the rate limiter keys on.
+/// * `req: web::Json<PseudonymRequest>` — `{ "usk": "<hex>", "context": "<text>" }`.
+///
+/// # Returns
+/// - `200 OK` with JSON `{ "nym": "<hex>" }`.
+///
+/// # Errors
+/// - `400 Bad Request` `INVALID_REQUEST_BODY` if `usk` is not a compressed G1 point or is the identity.
+/// - `429 Too Many Requests` with `Retry-After` once the caller's IP has used up its bucket.
+#[utoipa::path(
+    post,
+    path = "/pseudonym",
+    request_body = PseudonymRequest,
+    responses(
+        (status = 200, body = PseudonymResponse),
+        (status = 400, description = "usk is not a valid G1 point", body = ErrorBody),
+        (status = 429, description = "rate limited; see Retry-After", body = ErrorBody),
+    ),
+)]
+#[post("/pseudonym")]
+async fn pseudonym(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    req: web::Json<PseudonymRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(addr) = http_req.peer_addr() {
+        state.rate_limiter.check(addr.ip()).map_err(ApiError::RateLimited)?;
+    }
+    let usk = g1_from_hex(&req.usk).map_err(|e| ApiError::InvalidBody(format!("usk: {e:#}")))?;
+    if usk.is_zero() {
+        return Err(ApiError::InvalidBody("usk is the identity".into()));
+    }
+    let nym = derive_pseudonym(&usk, req.context.as_bytes());
+    let mut buf = Vec::new();
+    nym.serialize_compressed(&mut buf)?;
+
+    Ok(HttpResponse::Ok().json(PseudonymResponse { nym: hex::encode(buf) }))
+}
+
+/// Stable, non-reversible tag for `user_id` in logs: the first 8 bytes of
+/// `Blake2b("syra-log-user" ∥ user_id)`, hex. Raw `user_id`s are never logged.
+fn user_tag(user_id: &str) -> String {
+    let digest = Blake2b512::new()
+        .chain_update(LOG_USER_TAG)
+        .chain_update(user_id.as_bytes())
+        .finalize();
+    hex::encode(&digest[..8])
+}
+
+/// Step 1 of `generate_user_key`: verify the proof for `sub` (`user_id` in
+/// decimal), and the token's time claims when supplied.
+async fn check_proof(
+    verifier: &dyn ProofVerifier,
+    req: &GenerateKeyRequest,
+    sub: &str,
+    challenge: Option<ark_bn254::Fr>,
+) -> anyhow::Result<bool> {
+    let proof = decode_proof(&req.proof, req.proof_format)?;
+    if let Some(claims) = &req.claims {
+        verifier.check_claims(claims)?;
+    }
+    verifier
+        .verify(&req.iss, sub, &req.kid, req.circuit_id.as_deref(), proof, challenge)
+        .await
+}
+
+/// [`check_proof`] through [`ProofVerifier::verify_verbose`], so
+/// `/verify_proof` can say which part of the statement a rejected proof
+/// points at.
+async fn diagnose_proof(
+    verifier: &dyn ProofVerifier,
+    req: &GenerateKeyRequest,
+    challenge: Option<ark_bn254::Fr>,
+) -> anyhow::Result<VerifyDiagnostics> {
+    let sub = req.sub_encoding.to_decimal(&req.user_id)?;
+    let proof = decode_proof(&req.proof, req.proof_format)?;
+    if let Some(claims) = &req.claims {
+        verifier.check_claims(claims)?;
+    }
+    verifier
+        .verify_verbose(&req.iss, &sub, &req.kid, req.circuit_id.as_deref(), proof, challenge)
+        .await
+}
+
+/// `POST /verify_proof` response.
+#[derive(Serialize, ToSchema)]
+struct VerifyProofResponse {
+    valid: bool,
+    /// why verification failed: the public input at fault, or the error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+/// Dry run of `generate_user_key`: checks the proof (and claims) exactly as
+/// issuance would, without touching issuer keys or deriving a `usk`.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state; only the verifier and rate limiter are used.
+/// * `http_req: HttpRequest` — used for the peer address the rate limiter keys on.
+/// * `req: web::Json<GenerateKeyRequest>` — the same body `generate_user_key` takes.
+///
+/// # Returns
+/// - `200 OK` with JSON `{ "valid": bool, "reason"?: string }`; an invalid
+///   proof is `valid: false`, not an error.
+/// - `413 Payload Too Large` for a body longer than `--max-proof-bytes`.
+/// - `429 Too Many Requests` with `Retry-After` once the caller's IP has used up its bucket.
+#[utoipa::path(
+    post,
+    path = "/verify_proof",
+    request_body = GenerateKeyRequest,
+    responses(
+        (status = 200, body = VerifyProofResponse),
+        (status = 413, description = "body longer than --max-proof-bytes", body = ErrorBody),
+        (status = 429, description = "rate limited; see Retry-After", body = ErrorBody),
+    ),
+)]
+#[post("/verify_proof")]
+#[tracing::instrument(skip_all, fields(user = %user_tag(&req.user_id), kid = %req.kid, iss = %req.iss))]
+async fn verify_proof(
+    state: web::Data<AppState>,
+    http_req: HttpRequest,
+    req: web::Json<GenerateKeyRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(addr) = http_req.peer_addr() {
+        state.rate_limiter.check(addr.ip()).map_err(ApiError::RateLimited)?;
+    }
+
+    // A dry run must not burn the nonce the real request will redeem.
+    let challenge = match req.nonce.as_deref().map(|n| state.challenges.peek(n)).transpose() {
+        Ok(challenge) => challenge,
+        Err(e) => {
+            let resp = VerifyProofResponse { valid: false, reason: Some(e.to_string()) };
+            return Ok(HttpResponse::Ok().json(resp));
+        }
+    };
+    let resp = match diagnose_proof(state.verifier.as_ref(), &req, challenge).await {
+        Ok(diag) => VerifyProofResponse {
+            valid: diag.is_valid(),
+            reason: diag.failure.map(|f| f.to_string()),
+        },
+        Err(e) => VerifyProofResponse { valid: false, reason: Some(format!("{e:#}")) },
     };
-    
     Ok(HttpResponse::Ok().json(resp))
 }
+
+/// `POST /admin/rotate_keys` response.
+#[derive(Serialize, ToSchema)]
+struct RotateKeysResponse {
+    version: u32,
+    ivk: String,
+}
+
+/// Generates a new set of issuer keys and makes it current. The previous set
+/// is retired rather than dropped, so credentials issued under it stay
+/// verifiable.
+///
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state; the current keys move to
+///   `state.retired_issuer_keys` (persisted to `state.retired_keys_path`) and
+///   the new set is persisted to `state.issuer_keys_path`.
+///
+/// # Returns
+/// - `200 OK` with JSON `RotateKeysResponse { version, ivk }` for the new keys.
+/// - `404 Not Found` if issuer keys are not initialized yet.
+/// - `500 Internal Server Error` if the new keys could not be persisted.
+#[utoipa::path(
+    post,
+    path = "/admin/rotate_keys",
+    responses(
+        (status = 200, body = RotateKeysResponse),
+        (status = 401, description = "invalid admin token", body = ErrorBody),
+        (status = 404, description = "issuer keys not initialized", body = ErrorBody),
+        (status = 500, description = "new keys could not be persisted", body = ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[post("/rotate_keys")]
+async fn rotate_keys(state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    let ivk = rotate_issuer_keys(&state).map_err(|_| ApiError::IssuerKeysNotInitialized)?;
+    save_issuer_keys(&state).map_err(|e| {
+        tracing::error!(error = %e, "failed to persist rotated issuer keys");
+        ApiError::PersistenceFailed
+    })?;
+
+    Ok(HttpResponse::Ok().json(RotateKeysResponse {
+        version: ivk.version,
+        ivk:     ivk.to_hex_string()?,
+    }))
+}
+
+/// Persist the retired key sets, then the current one. In that order a
+/// crash in between leaves the previous set in both files, which loading
+/// tolerates, rather than in neither.
+fn save_issuer_keys(state: &AppState) -> std::io::Result<()> {
+    let keys = state.issuer_keys.lock_or_recover();
+    let retired = state.retired_issuer_keys.lock_or_recover();
+    StoredIssuerKeys::save_retired(&retired, &state.retired_keys_path, state.keys_passphrase())?;
+    if let Some(keys) = &*keys {
+        keys.save_to_path(&state.issuer_keys_path, state.keys_passphrase())?;
+    }
+    Ok(())
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let verifier = Arc::new(
-        Verifier::new()
-            .await
-            .expect("failed to initialise Groth16 verifier"),
-    );
+    let config = Config::parse();
+
+    // `RUST_LOG` selects what is emitted, e.g. `RUST_LOG=syra_login=debug`;
+    // `LOG_FORMAT` how.
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    match config.log_format {
+        LogFormat::Pretty => tracing_subscriber::fmt().with_env_filter(filter).init(),
+        LogFormat::Json => tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_env_filter(filter)
+            .init(),
+    }
+
+    let verifier = match &config.vk_path {
+        Some(path) => Verifier::from_vk_path(path, config.issuer_config()).await,
+        None => Verifier::new(config.issuer_config()).await,
+    };
+    let mut verifier = verifier
+        .and_then(|v| v.with_http_config(config.http_config()))
+        .expect("failed to initialise Groth16 verifier")
+        .with_clock_skew(Duration::from_secs(config.clock_skew_secs));
+    if let Some(secs) = config.jwks_cache_ttl_secs {
+        verifier = verifier.with_cache_ttl(Duration::from_secs(secs));
+    }
+    if let Some(path) = &config.ec_vk_path {
+        let vk_json = std::fs::read_to_string(path)?;
+        verifier = verifier
+            .with_ec_verifying_key(&vk_json)
+            .unwrap_or_else(|e| panic!("invalid EC verifying key {}: {e:#}", path.display()));
+    }
+    for (circuit_id, path) in &config.circuit_vks {
+        let vk_json = std::fs::read_to_string(path)?;
+        verifier = verifier
+            .with_circuit(circuit_id, &vk_json)
+            .unwrap_or_else(|e| panic!("invalid verifying key {} for circuit '{circuit_id}': {e:#}", path.display()));
+    }
+    let verifier = verifier
+        .with_chunk_bits(config.chunk_bits)
+        .unwrap_or_else(|e| panic!("invalid --chunk-bits: {e:#}"));
+    tracing::info!(circuits = ?verifier.circuit_ids(), chunk_bits = config.chunk_bits, "verifying keys loaded");
+    let verifier: Arc<dyn ProofVerifier> = Arc::new(verifier);
     let state = web::Data::new(AppState {
         issuer_keys: Mutex::new(None),
+        retired_issuer_keys: Mutex::new(Vec::new()),
+        issuer_keys_path: config.issuer_keys_path.clone(),
+        retired_keys_path: config.retired_keys_path.clone(),
+        keys_passphrase: config.keys_passphrase.clone().map(Zeroizing::new),
+        revocation: Mutex::new(RevocationList::default()),
+        revocation_path: config.revocation_path.clone(),
+        admin_token: config.admin_token.clone(),
+        metrics: Metrics::new().expect("failed to register metrics"),
+        rate_limiter: RateLimiter::new(config.rate_limit),
+        dkg: Mutex::new(None),
+        party_index: config.party_index,
+        require_dkg: config.require_dkg,
+        challenges: ChallengeStore::new(Duration::from_secs(config.challenge_ttl_secs)),
+        require_challenge: config.require_challenge,
+        allow_native_jwt: config.allow_native_jwt,
+        generators: config
+            .generators()
+            .unwrap_or_else(|e| panic!("failed to load generators: {e:#}")),
+        scheme: config.scheme(),
+        issuer_id: config.issuer_id.clone(),
+        audit: AuditLog::open(config.audit_log_path.as_deref(), !config.audit_fail_open),
         verifier,
     });
-    generate_issuer_keys(&state)
-        .unwrap_or_else(|e| panic!("failed to generate issuer keys: {:?}", e));
 
-    println!("🔧 Server listening on http://127.0.0.1:9000");
+    if config.allow_native_jwt {
+        tracing::warn!("native JWT issuance enabled; subjects of those tokens are visible to this server");
+    }
+
+    if let Some(path) = &config.generators_path {
+        tracing::info!(path = %path.display(), "generators loaded from file");
+    }
+
+    let keys_path = &config.issuer_keys_path;
+    if keys_path.exists() {
+        let mut keys = StoredIssuerKeys::load_from_path(keys_path, state.keys_passphrase())
+            .unwrap_or_else(|e| panic!("failed to load issuer keys from {}: {e:#}", keys_path.display()));
+        // The id is configuration, not key material: follow --issuer-id.
+        if keys.issuer_id != state.issuer_id {
+            tracing::info!(from = %keys.issuer_id, to = %state.issuer_id, "issuer id changed");
+            keys.issuer_id = state.issuer_id.clone();
+        }
+        if keys.bp != state.generators {
+            tracing::warn!("loaded issuer keys use different generators than configured; rotate them with /admin/rotate_keys");
+        }
+        *state.issuer_keys.lock_or_recover() = Some(keys);
+        tracing::info!(path = %keys_path.display(), "issuer keys loaded");
+    } else {
+        generate_issuer_keys(&state)
+            .unwrap_or_else(|e| panic!("failed to generate issuer keys: {:?}", e));
+        state
+            .issuer_keys
+            .lock_or_recover()
+            .as_ref()
+            .expect("issuer keys just generated")
+            .save_to_path(keys_path, state.keys_passphrase())?;
+        tracing::info!(path = %keys_path.display(), "issuer keys persisted");
+    }
+
+    let retired_path = &config.retired_keys_path;
+    if retired_path.exists() {
+        let current = state.issuer_keys.lock_or_recover().as_ref().map_or(0, |k| k.version);
+        let mut retired = StoredIssuerKeys::load_retired(retired_path, state.keys_passphrase())
+            .unwrap_or_else(|e| panic!("failed to load retired issuer keys from {}: {e:#}", retired_path.display()));
+        // A crash mid-rotation can leave the current set in both files.
+        retired.retain(|k| k.version < current);
+        for keys in &mut retired {
+            keys.issuer_id = state.issuer_id.clone();
+        }
+        tracing::info!(count = retired.len(), path = %retired_path.display(), "retired issuer keys loaded");
+        *state.retired_issuer_keys.lock_or_recover() = retired;
+    }
+
+    let revocation_path = &config.revocation_path;
+    if revocation_path.exists() {
+        let list = RevocationList::load_from_path(revocation_path)
+            .unwrap_or_else(|e| panic!("failed to load revocation list from {}: {e:#}", revocation_path.display()));
+        tracing::info!(count = list.len(), path = %revocation_path.display(), "revocation list loaded");
+        *state.revocation.lock_or_recover() = list;
+    }
+
+    {
+        let keys = state.issuer_keys.lock_or_recover();
+        let keys = keys.as_ref().expect("issuer keys loaded or generated above");
+        match self_test(keys, &state.revocation.lock_or_recover()) {
+            Ok(()) => tracing::info!(version = keys.version, "issuer key self-test passed"),
+            Err(e) => panic!("issuer key self-test failed: {e}; rotate the keys with /admin/rotate_keys or fix the configured generators"),
+        }
+    }
+
+    let listen_addr = config.listen_addr();
+    tracing::info!(%listen_addr, "server listening");
+    let allowed_origins = config.allowed_origins.clone();
+    let any_origin = allowed_origins.iter().any(|o| o == "*");
+    if any_origin {
+        tracing::warn!("CORS allows any origin; do NOT use this in production");
+    }
+    let app_state = state.clone();
+    let compress = config.compress_responses;
+    let max_proof_bytes = config.max_proof_bytes;
     HttpServer::new(move || {
         // configure CORS
-        let cors = Cors::default()
-            // allow your Next.js origin
-            .allowed_origin("http://localhost:8080")
-            // allow the POST and OPTIONS methods
-            .allowed_methods(vec!["POST", "OPTIONS"])
-            // allow Content-Type header
-            .allowed_header(header::CONTENT_TYPE)
+        let mut cors = Cors::default();
+        if any_origin {
+            cors = cors.allow_any_origin();
+        } else {
+            for origin in &allowed_origins {
+                cors = cors.allowed_origin(origin);
+            }
+        }
+        let cors = cors
+            // allow the GET, POST and OPTIONS methods
+            .allowed_methods(vec!["GET", "POST", "OPTIONS"])
+            // allow Content-Type and Authorization headers
+            .allowed_headers(vec![header::CONTENT_TYPE, header::AUTHORIZATION])
             // set how long the preflight is cached (in seconds)
             .max_age(3600);
 
         App::new()
+            .wrap(middleware::from_fn(request_id::propagate))
             .wrap(cors)
-            .app_data(state.clone())
-            .service(generate_user_key)
+            .wrap(middleware::Condition::new(compress, middleware::Compress::default()))
+            .wrap(TracingLogger::default())
+            .app_data(app_state.clone())
+            .configure(|cfg| routes(cfg, max_proof_bytes))
     })
-        .bind("127.0.0.1:9000")?
+        .shutdown_timeout(config.shutdown_timeout_secs)
+        .bind(listen_addr)?
         .run()
-        .await
-}
\ No newline at end of file
+        .await?;
+
+    // actix resolves `run()` once SIGINT / SIGTERM has drained the workers.
+    flush_state(&state)?;
+    tracing::info!("state flushed; clean exit");
+    Ok(())
+}
+
+/// Every route the service exposes (listed again in [`fallback::ROUTES`]
+/// for `405` / `404` answers). Kept apart from the CORS / logging middleware
+/// and `AppState` so an `App` (e.g. under `actix_web::test::init_service`)
+/// can mount the same handlers on its own state.
+///
+/// The `/admin` handlers are mounted under a scope wrapped in
+/// [`auth::require_admin_token`], so the token check applies to whatever
+/// the router resolves into that scope, percent-encoded spellings of the
+/// prefix included; their own paths are relative to it.
+///
+/// The single-proof routes sit in unprefixed scopes so their bodies are
+/// capped at `max_proof_bytes` (`--max-proof-bytes`); everything else keeps
+/// actix's default limit. Such a scope matches every path, so it goes last
+/// in its parent: routes mounted after it would never be reached.
+fn routes(cfg: &mut web::ServiceConfig, max_proof_bytes: usize) {
+    let proof_limit = || {
+        web::JsonConfig::default()
+            .limit(max_proof_bytes)
+            .error_handler(error::json_error_handler)
+    };
+    cfg.app_data(web::JsonConfig::default().error_handler(error::json_error_handler))
+        .service(
+            web::scope("/admin")
+                .wrap(middleware::from_fn(auth::require_admin_token))
+                .service(generate_user_keys_batch)
+                .service(recover_user_key)
+                .service(generate_user_key_jwt)
+                .service(dkg::receive_dkg)
+                .service(dkg::receive_dkg_batch)
+                .service(dkg::dkg_ws)
+                .service(dkg::dkg_status)
+                .service(rotate_keys)
+                .service(revocation::revoke)
+                .service(web::scope("").app_data(proof_limit()).service(generate_user_key)),
+        )
+        .service(challenge::challenge)
+        .service(get_ivk)
+        .service(get_ivk_versions)
+        .service(get_vk)
+        .service(pseudonym)
+        .service(revocation::revocation_status)
+        .service(health::healthz)
+        .service(health::readyz)
+        .service(metrics::metrics)
+        .service(openapi::openapi_json)
+        .service(web::scope("").app_data(proof_limit()).service(verify_proof))
+        .default_service(web::to(fallback::fallback));
+}
+
+/// Write everything persistable back to disk on shutdown: the issuer keys,
+/// current and retired, and the revocation list. DKG shares are held in
+/// memory only and are re-dealt by the coordinator.
+fn flush_state(state: &AppState) -> std::io::Result<()> {
+    save_issuer_keys(state)?;
+    state
+        .revocation
+        .lock_or_recover()
+        .save_to_path(&state.revocation_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test;
+    use syra_login::codec::{g1_from_hex_with, g2_from_hex};
+    use dkg::DkgPointMessage;
+    use syra_login::dkg_wire::DkgWsMessage;
+
+    use syra_login::jwt_proof_verifier::{IssuerConfig, Jwk, VerifyFailure, GOOGLE_ISSUER, RSA_CIRCUIT_ID};
+    use syra_login::proof::proof_to_base64_compressed;
+    use syra_login::test_circuit::TestCircuit;
+    use syra_login::test_tokens::{id_token_claims, sign_id_token, signing_jwk, CLIENT_ID};
+
+    use crate::test_support::{
+        admin_auth, deal, keygen_body, serve, test_app, test_app_state, test_state, MockVerifier, WsClient,
+    };
+
+    const ALICE: &str = "113048723091228773641";
+
+    fn keygen_request(body: &serde_json::Value) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri("/admin/generate_user_key")
+            .insert_header(admin_auth())
+            .set_json(body)
+    }
+
+    #[actix_web::test]
+    async fn generate_user_key_issues_keys_under_the_current_ivk() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        let app = test_app!(state.clone());
+
+        let resp = test::call_service(&app, keygen_request(&keygen_body(ALICE)).to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+
+        let lock = state.issuer_keys.lock_or_recover();
+        let stored = lock.as_ref().unwrap();
+        let s = s_from_sub(ALICE);
+        assert_eq!(body["version"], 1);
+        assert_eq!(body["s"], fr_to_hex(&s));
+        assert_eq!(body["ivk"], stored.ivk_bundle().to_hex_string().unwrap());
+        let usk = g1_from_hex(body["usk"].as_str().unwrap()).unwrap();
+        let usk_hat = g2_from_hex(body["usk_hat"].as_str().unwrap()).unwrap();
+        assert!(curve::verify_user_key::<Curve>(stored.bp.g1, stored.bp.g2, stored.ivk_hat, s, usk));
+        assert!(curve::same_exponent::<Curve>(stored.bp.g1, stored.bp.g2, usk, usk_hat));
+    }
+
+    fn recover_request(user_id: &str) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri("/admin/recover_user_key")
+            .insert_header(admin_auth())
+            .set_json(serde_json::json!({ "user_id": user_id }))
+    }
+
+    /// Recovery re-derives exactly what issuance returned, without a proof.
+    #[actix_web::test]
+    async fn recover_user_key_returns_the_issued_key() {
+        let verifier = Arc::new(MockVerifier::accepting());
+        let state = test_state(verifier.clone());
+        let app = test_app!(state.clone());
+
+        let issued: serde_json::Value =
+            test::call_and_read_body_json(&app, keygen_request(&keygen_body(ALICE)).to_request()).await;
+        let resp = test::call_service(&app, recover_request(ALICE).to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let recovered: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(recovered, issued);
+        assert_eq!(verifier.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        let req = recover_request(ALICE).uri("/admin/recover_user_key?compression=uncompressed").to_request();
+        let uncompressed: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let usk = g1_from_hex_with(uncompressed["usk"].as_str().unwrap(), Compress::No).unwrap();
+        assert_eq!(g1_to_hex(&usk), issued["usk"]);
+    }
+
+    #[actix_web::test]
+    async fn recover_user_key_needs_issuer_keys() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        *state.issuer_keys.lock_or_recover() = None;
+        let app = test_app!(state.clone());
+
+        let resp = test::call_service(&app, recover_request(ALICE).to_request()).await;
+        assert_eq!(resp.status(), 404);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "ISSUER_KEYS_NOT_INITIALIZED");
+    }
+
+    /// With `--require-dkg`, recovery refuses the local `isk` like issuance
+    /// does, and answers once every dealer's share is in.
+    #[actix_web::test]
+    async fn recover_user_key_waits_for_the_dkg_share_under_require_dkg() {
+        let mut state = test_app_state(Arc::new(MockVerifier::accepting()));
+        state.require_dkg = true;
+        let state = web::Data::new(state);
+        let app = test_app!(state.clone());
+
+        let resp = test::call_service(&app, recover_request(ALICE).to_request()).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "DKG_NOT_INITIALIZED");
+
+        let dealt: Vec<DkgPointMessage> = (0..2).map(|_| deal("sid-1", 2, 1, state.generators.g1).remove(0)).collect();
+        let req = test::TestRequest::post()
+            .uri("/admin/receive_dkg_batch")
+            .insert_header(admin_auth())
+            .set_json(&dealt)
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+        let resp = test::call_service(&app, recover_request(ALICE).to_request()).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    /// `/admin/dkg_status` counts distinct dealers against `n` and reports
+    /// `complete` only once all of them are in; a re-sent share is not
+    /// counted twice, and issuance keeps the local `isk` until then.
+    #[actix_web::test]
+    async fn dkg_status_tracks_dealers_until_the_share_is_complete() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        let app = test_app!(state.clone());
+        let status = || async {
+            let req = test::TestRequest::get().uri("/admin/dkg_status").insert_header(admin_auth()).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            body
+        };
+        let send = |msg: &DkgPointMessage| {
+            test::TestRequest::post()
+                .uri("/admin/receive_dkg")
+                .insert_header(admin_auth())
+                .set_json(msg)
+                .to_request()
+        };
+
+        let body = status().await;
+        assert_eq!(body["shares_received"], 0);
+        assert_eq!(body["parties"], serde_json::Value::Null);
+        assert_eq!(body["threshold"], serde_json::Value::Null);
+        assert_eq!(body["complete"], false);
+
+        let dealt: Vec<DkgPointMessage> = (0..3).map(|_| deal("sid-1", 3, 2, state.generators.g1).remove(0)).collect();
+        for msg in [&dealt[0], &dealt[1], &dealt[1]] {
+            assert_eq!(test::call_service(&app, send(msg)).await.status(), 200);
+        }
+        let body = status().await;
+        assert_eq!(body["sid"], "sid-1");
+        assert_eq!(body["shares_received"], 2);
+        assert_eq!(body["parties"], 3);
+        assert_eq!(body["threshold"], 2);
+        assert_eq!(body["complete"], false);
+        assert!(!state.dkg_complete());
+
+        let mut other_t = deal("sid-1", 3, 3, state.generators.g1).remove(0);
+        assert_eq!(test::call_service(&app, send(&other_t)).await.status(), 400);
+        other_t.sid = "sid-2".into();
+        assert_eq!(status().await["shares_received"], 2);
+
+        assert_eq!(test::call_service(&app, send(&dealt[2])).await.status(), 200);
+        let body = status().await;
+        assert_eq!(body["shares_received"], 3);
+        assert_eq!(body["complete"], true);
+        assert!(state.dkg_complete());
+
+        // A share from a new session starts the count over.
+        assert_eq!(test::call_service(&app, send(&other_t)).await.status(), 200);
+        let body = status().await;
+        assert_eq!(body["sid"], "sid-2");
+        assert_eq!(body["shares_received"], 1);
+        assert_eq!(body["threshold"], 3);
+        assert_eq!(body["complete"], false);
+    }
+
+    /// One bad share fails `/admin/receive_dkg_batch` as a whole: nothing
+    /// from the batch is stored, whether or not a share was held before.
+    #[actix_web::test]
+    async fn a_bad_share_rejects_the_whole_dkg_batch() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        let app = test_app!(state.clone());
+        let send = |msgs: &[DkgPointMessage]| {
+            test::TestRequest::post()
+                .uri("/admin/receive_dkg_batch")
+                .insert_header(admin_auth())
+                .set_json(msgs)
+                .to_request()
+        };
+        let dealt: Vec<DkgPointMessage> = (0..3).map(|_| deal("sid-1", 3, 2, state.generators.g1).remove(0)).collect();
+        let mut bad = dealt.clone();
+        bad[2].f_i = dealt[1].f_i.clone();
+
+        let resp = test::call_service(&app, send(&bad)).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "INVALID_DKG_SHARE");
+        assert!(body["message"].as_str().unwrap().contains("dealer 2"), "{body}");
+        assert!(state.dkg.lock_or_recover().is_none());
+
+        assert_eq!(test::call_service(&app, send(&dealt[..1])).await.status(), 200);
+        assert_eq!(test::call_service(&app, send(&bad[1..])).await.status(), 400);
+        let dkg = state.dkg.lock_or_recover();
+        let held = dkg.as_ref().unwrap();
+        assert_eq!(held.dealers.len(), 1);
+        assert_eq!(fr_to_hex(&held.isk_i), dealt[0].f_i);
+    }
+
+    /// A coordinator session over `/admin/dkg_ws`: a good share is acked and
+    /// stored, a bad one draws a complaint that the dealer's justification
+    /// resolves, and frames an issuer does not take are answered with errors.
+    #[actix_web::test]
+    async fn dkg_ws_acks_complains_and_accepts_justified_shares() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        let addr = serve(state.clone());
+        let mut ws = WsClient::connect(addr, "/admin/dkg_ws").await;
+        async fn exchange(ws: &mut WsClient, msg: DkgWsMessage) -> serde_json::Value {
+            ws.send(&serde_json::to_string(&msg).unwrap()).await;
+            serde_json::from_str(&ws.recv().await).unwrap()
+        }
+
+        let good = deal("sid-1", 3, 2, state.generators.g1).remove(0);
+        let reply = exchange(&mut ws, DkgWsMessage::Share(good.clone())).await;
+        assert_eq!(reply, serde_json::json!({ "type": "ack", "sid": "sid-1", "i": 1 }));
+        assert_eq!(state.dkg.lock_or_recover().as_ref().unwrap().dealers.len(), 1);
+
+        let honest = deal("sid-1", 3, 2, state.generators.g1).remove(0);
+        let mut bad = honest.clone();
+        bad.f_i = good.f_i.clone();
+        let reply = exchange(&mut ws, DkgWsMessage::Share(bad)).await;
+        assert_eq!(reply["type"], "complaint");
+        assert_eq!(reply["reason"], "g^f_i does not match Ai_all[0]");
+        assert_eq!(state.dkg.lock_or_recover().as_ref().unwrap().dealers.len(), 1);
+
+        let reply = exchange(&mut ws, DkgWsMessage::Justify { sid: "sid-1".into(), i: 1, f_i: honest.f_i.clone() }).await;
+        assert_eq!(reply["type"], "ack");
+        assert_eq!(state.dkg.lock_or_recover().as_ref().unwrap().dealers.len(), 2);
+
+        let reply = exchange(&mut ws, DkgWsMessage::Justify { sid: "sid-2".into(), i: 1, f_i: honest.f_i }).await;
+        assert_eq!(reply["type"], "error");
+        let reply = exchange(&mut ws, DkgWsMessage::Ack { sid: "sid-1".into(), i: 1 }).await;
+        assert_eq!(reply["message"], "issuers only accept share and justify");
+        ws.send("not json").await;
+        let reply: serde_json::Value = serde_json::from_str(&ws.recv().await).unwrap();
+        assert!(reply["message"].as_str().unwrap().starts_with("malformed frame"), "{reply}");
+    }
+
+    /// Once a dealt share is accepted, keys are derived from `isk_i`; they
+    /// must verify against the ivk returned with them and the one `/ivk`
+    /// publishes, revocation witness included, and no longer against the
+    /// local `ivk_hat`.
+    #[actix_web::test]
+    async fn keys_issued_from_a_dkg_share_verify_against_the_returned_ivk() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        let app = test_app!(state.clone());
+        for _dealer in 0..3 {
+            let share = &deal("sid-1", 3, 2, state.generators.g1)[0];
+            let req = test::TestRequest::post()
+                .uri("/admin/receive_dkg")
+                .insert_header(admin_auth())
+                .set_json(share)
+                .to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 200);
+        }
+
+        let resp = test::call_service(&app, keygen_request(&keygen_body(ALICE)).to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let ivk = IvkBundle::from_hex_string(body["ivk"].as_str().unwrap()).unwrap();
+        let usk = g1_from_hex(body["usk"].as_str().unwrap()).unwrap();
+        let usk_hat = g2_from_hex(body["usk_hat"].as_str().unwrap()).unwrap();
+        let s = s_from_sub(ALICE);
+        assert!(credential::verify_credential_pair(&ivk, s, &usk, &usk_hat));
+
+        let req = test::TestRequest::get().uri("/ivk").to_request();
+        let published: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(published["ivk"], body["ivk"]);
+
+        let req = test::TestRequest::get().uri(&format!("/revocation_status?user_id={ALICE}")).to_request();
+        let status: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let accumulator = g1_from_hex(status["accumulator"].as_str().unwrap()).unwrap();
+        let witness = credential::NonMembershipWitness {
+            C: g1_from_hex(status["witness"]["C"].as_str().unwrap()).unwrap(),
+            d: syra_login::codec::fr_from_hex(status["witness"]["d"].as_str().unwrap()).unwrap(),
+        };
+        assert!(credential::verify_credential(&ivk, s, &usk, &accumulator, &witness));
+
+        let local = state.issuer_keys.lock_or_recover().as_ref().unwrap().ivk_bundle();
+        assert!(!credential::verify_user_key(&local, s, &usk));
+    }
+
+    /// Every rotation bumps the version and persists the new set next to all
+    /// retired ones, so a restart keeps verifying old credentials.
+    #[actix_web::test]
+    async fn rotation_persists_retired_keys_beside_the_new_ones() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        let app = test_app!(state.clone());
+        let mut ivks = vec![state.issuer_keys.lock_or_recover().as_ref().unwrap().ivk_bundle()];
+
+        for version in [2, 3] {
+            let req = test::TestRequest::post().uri("/admin/rotate_keys").insert_header(admin_auth()).to_request();
+            let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(body["version"], version);
+
+            let current = StoredIssuerKeys::load_from_path(&state.issuer_keys_path, None).unwrap();
+            assert_eq!(current.version, version);
+            assert_eq!(body["ivk"], current.ivk_bundle().to_hex_string().unwrap());
+            let retired = StoredIssuerKeys::load_retired(&state.retired_keys_path, None).unwrap();
+            assert_eq!(retired.iter().map(StoredIssuerKeys::ivk_bundle).collect::<Vec<_>>(), ivks);
+            ivks.push(current.ivk_bundle());
+        }
+        let in_memory = state.retired_issuer_keys.lock_or_recover();
+        assert_eq!(in_memory.iter().map(|k| k.version).collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[actix_web::test]
+    async fn ivk_versions_lists_retired_keys_and_marks_the_current_one() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        let app = test_app!(state.clone());
+        let first = state.issuer_keys.lock_or_recover().as_ref().unwrap().ivk_bundle();
+
+        let req = test::TestRequest::get().uri("/ivk/versions").to_request();
+        let versions: Vec<serde_json::Value> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(versions, [serde_json::json!({ "version": 1, "ivk": first.to_hex_string().unwrap(), "active": true })]);
+
+        let req = test::TestRequest::post().uri("/admin/rotate_keys").insert_header(admin_auth()).to_request();
+        let rotated: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        let req = test::TestRequest::get().uri("/ivk/versions").to_request();
+        let versions: Vec<serde_json::Value> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            versions,
+            [
+                serde_json::json!({ "version": 1, "ivk": first.to_hex_string().unwrap(), "active": false }),
+                serde_json::json!({ "version": 2, "ivk": rotated["ivk"], "active": true }),
+            ]
+        );
+        let req = test::TestRequest::get().uri("/ivk").to_request();
+        let current: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(current["ivk"], rotated["ivk"]);
+    }
+
+    #[actix_web::test]
+    async fn generate_user_key_rejects_an_invalid_proof() {
+        let state = test_state(Arc::new(MockVerifier::rejecting(&[ALICE])));
+        let app = test_app!(state.clone());
+
+        let resp = test::call_service(&app, keygen_request(&keygen_body(ALICE)).to_request()).await;
+        assert_eq!(resp.status(), 401);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "INVALID_PROOF");
+        assert_eq!(state.metrics.proof_verify_failures.get(), 1);
+
+        // A proof that does not even decode is the same answer.
+        let mut garbled = keygen_body(ALICE);
+        garbled["proof"] = "not a proof".into();
+        let resp = test::call_service(&app, keygen_request(&garbled).to_request()).await;
+        assert_eq!(resp.status(), 401);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "INVALID_PROOF");
+    }
+
+    /// Handlers see only the trait: whatever the verifier scripts is the
+    /// verdict, with no Groth16 key or JWKS involved.
+    #[actix_web::test]
+    async fn handlers_follow_a_scripted_verifier() {
+        const BOB: &str = "42";
+        let mock = Arc::new(MockVerifier::rejecting(&[BOB]));
+        let verifier: &dyn ProofVerifier = mock.as_ref();
+        for (user, expected) in [(ALICE, true), (BOB, false)] {
+            let req: GenerateKeyRequest = serde_json::from_value(keygen_body(user)).unwrap();
+            assert_eq!(check_proof(verifier, &req, user, None).await.unwrap(), expected);
+        }
+        assert_eq!(mock.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        let app = test_app!(test_state(mock.clone()));
+        let req = test::TestRequest::post().uri("/verify_proof").set_json(keygen_body(BOB)).to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["valid"], false);
+        assert_eq!(body["reason"], VerifyFailure::Proof { inputs: 19 }.to_string());
+        assert_eq!(mock.calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    /// Both single-proof routes keep `--max-proof-bytes`, including the one
+    /// nested in the `/admin` scope.
+    #[actix_web::test]
+    async fn proof_routes_cap_their_bodies() {
+        let app = test_app!(test_state(Arc::new(MockVerifier::accepting())));
+        let mut body = keygen_body(ALICE);
+        body["proof"] = "A".repeat(crate::test_support::MAX_PROOF_BYTES).into();
+        for uri in ["/admin/generate_user_key", "/verify_proof"] {
+            let req = test::TestRequest::post()
+                .uri(uri)
+                .insert_header(admin_auth())
+                .set_json(&body)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 413, "{uri}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn issue_user_key_releases_the_key_lock_while_verifying() {
+        let gate = Arc::new(tokio::sync::Notify::new());
+        let mock = Arc::new(MockVerifier::gated(gate.clone()));
+        let state = test_state(mock.clone());
+        let req: GenerateKeyRequest = serde_json::from_value(keygen_body(ALICE)).unwrap();
+        let task = actix_web::rt::spawn({
+            let state = state.clone();
+            async move { issue_user_key(&state, &req, Compress::Yes).await.map(|r| r.version) }
+        });
+
+        while mock.calls.load(std::sync::atomic::Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+        // Verification is parked on the gate; the keys must be free meanwhile.
+        assert!(state.issuer_keys.try_lock().is_ok());
+        gate.notify_one();
+        assert_eq!(task.await.unwrap().unwrap(), 1);
+    }
+
+    #[actix_web::test]
+    async fn batch_reports_failures_in_place() {
+        const REJECTED: &str = "666";
+        let state = test_state(Arc::new(MockVerifier::rejecting(&[REJECTED])));
+        let app = test_app!(state.clone());
+        let body = serde_json::json!([
+            keygen_body(ALICE),
+            keygen_body(REJECTED),
+            keygen_body("not decimal"),
+            keygen_body("42"),
+        ]);
+        let req = test::TestRequest::post()
+            .uri("/admin/generate_user_keys_batch")
+            .insert_header(admin_auth())
+            .set_json(&body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let results: Vec<serde_json::Value> = test::read_body_json(resp).await;
+
+        assert_eq!(results.len(), 4);
+        for (i, user) in [(0, ALICE), (3, "42")] {
+            assert_eq!(results[i]["s"], fr_to_hex(&s_from_sub(user)), "item {i}");
+            assert!(results[i]["usk"].is_string(), "item {i}");
+        }
+        assert_eq!(results[1]["code"], "INVALID_PROOF");
+        assert_eq!(results[2]["code"], "INVALID_SUBJECT");
+        assert!(results[1].get("usk").is_none() && results[2].get("usk").is_none());
+    }
+
+    /// `s + isk == 0` has no inverse; the request fails cleanly instead of
+    /// taking the worker down.
+    #[actix_web::test]
+    async fn s_equal_to_minus_isk_is_inversion_failed() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        state.issuer_keys.lock_or_recover().as_mut().unwrap().isk = -s_from_sub(ALICE);
+        let app = test_app!(state.clone());
+
+        let resp = test::call_service(&app, keygen_request(&keygen_body(ALICE)).to_request()).await;
+        assert_eq!(resp.status(), 422);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "INVERSION_FAILED");
+
+        // Only that user: the worker is still up and others get keys.
+        let resp = test::call_service(&app, keygen_request(&keygen_body("42")).to_request()).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn native_jwt_for_another_audience_is_unauthorized() {
+        let issuers = IssuerConfig { audiences: vec![CLIENT_ID.to_string()], ..IssuerConfig::default() };
+        let jwks_uri = issuers.jwks_uri(GOOGLE_ISSUER).unwrap().to_string();
+        let verifier = Verifier::new(issuers).await.unwrap();
+        verifier.cache_jwks(&jwks_uri, vec![signing_jwk("google-kid")]).await;
+        let mut state = test_app_state(Arc::new(verifier));
+        state.allow_native_jwt = true;
+        let app = test_app!(web::Data::new(state));
+        let request = |claims: &serde_json::Value| {
+            test::TestRequest::post()
+                .uri("/admin/generate_user_key_jwt")
+                .insert_header(admin_auth())
+                .set_json(serde_json::json!({ "token": sign_id_token("google-kid", claims), "kid": "google-kid" }))
+                .to_request()
+        };
+
+        let mut foreign = id_token_claims(ALICE);
+        foreign["aud"] = "someone-elses-app.apps.googleusercontent.com".into();
+        foreign["azp"] = "someone-elses-app.apps.googleusercontent.com".into();
+        let resp = test::call_service(&app, request(&foreign)).await;
+        assert_eq!(resp.status(), 401);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "INVALID_TOKEN");
+
+        let resp = test::call_service(&app, request(&id_token_claims(ALICE))).await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    /// A provider whose JWKS endpoint refuses connections is the provider's
+    /// problem, not the client's: 503, and no failed verification counted.
+    #[actix_web::test]
+    async fn unreachable_jwks_is_provider_unavailable() {
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let jwks_uri = format!("http://{}/certs", closed.local_addr().unwrap());
+        drop(closed);
+        const ISSUER: &str = "https://issuer.test";
+        let issuers = IssuerConfig { providers: vec![(ISSUER.to_string(), jwks_uri.clone())], ..IssuerConfig::default() };
+        let state = web::Data::new(test_app_state(Arc::new(Verifier::new(issuers).await.unwrap())));
+        let app = test_app!(state.clone());
+
+        let mut body = keygen_body(ALICE);
+        body["iss"] = ISSUER.into();
+        let resp = test::call_service(&app, keygen_request(&body).to_request()).await;
+        assert_eq!(resp.status(), 503);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "PROVIDER_UNAVAILABLE");
+        assert!(body["message"].as_str().unwrap().contains(&jwks_uri), "{body}");
+        assert_eq!(state.metrics.proof_verify_failures.get(), 0);
+    }
+
+    /// With two verifying keys registered, `circuit_id` picks the one a
+    /// proof is checked against; an id nobody registered is the client's
+    /// mistake, not a failed proof.
+    #[actix_web::test]
+    async fn circuit_id_routes_each_proof_to_its_verifying_key() {
+        const KID: &str = "google-kid";
+        let (v1, v2) = (TestCircuit::rsa(), TestCircuit::rsa());
+        let issuers = IssuerConfig::default();
+        let jwks_uri = issuers.jwks_uri(GOOGLE_ISSUER).unwrap().to_string();
+        let verifier = Verifier::new(issuers)
+            .await
+            .and_then(|v| v.with_circuit(RSA_CIRCUIT_ID, &v1.vk_json()))
+            .and_then(|v| v.with_circuit("rsa-v2", &v2.vk_json()))
+            .unwrap();
+        let mut modulus = [0xa5u8; 256];
+        modulus[0] |= 0x80;
+        let n = base64::Engine::encode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, modulus);
+        verifier.cache_jwks(&jwks_uri, vec![Jwk::rsa(KID, n.clone(), "AQAB")]).await;
+        let state = web::Data::new(test_app_state(Arc::new(verifier)));
+        let app = test_app!(state.clone());
+        let request = |circuit: &TestCircuit, circuit_id: Option<&str>| {
+            let proof = proof_to_base64_compressed(&circuit.prove_rsa(ALICE, &n).unwrap()).unwrap();
+            let body = serde_json::json!({ "user_id": ALICE, "kid": KID, "proof": proof, "circuit_id": circuit_id });
+            keygen_request(&body).to_request()
+        };
+
+        for (circuit, id) in [(&v1, None), (&v1, Some(RSA_CIRCUIT_ID)), (&v2, Some("rsa-v2"))] {
+            assert_eq!(test::call_service(&app, request(circuit, id)).await.status(), 200, "{id:?}");
+        }
+        for (circuit, id) in [(&v2, None), (&v1, Some("rsa-v2"))] {
+            assert_eq!(test::call_service(&app, request(circuit, id)).await.status(), 401, "{id:?}");
+        }
+
+        let resp = test::call_service(&app, request(&v2, Some("rsa-v3"))).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "UNKNOWN_CIRCUIT");
+        assert!(body["message"].as_str().unwrap().contains("rsa-v3"), "{body}");
+        assert_eq!(state.metrics.proof_verify_failures.get(), 2);
+    }
+}
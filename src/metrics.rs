@@ -0,0 +1,113 @@
+use actix_web::{get, http::header, web, HttpResponse};
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, Registry, TextEncoder};
+
+use crate::AppState;
+
+/// Prometheus collectors, registered in their own registry.
+pub struct Metrics {
+    registry: Registry,
+    /// Every `/admin/generate_user_key` request, before any checks.
+    pub keygen_requests: IntCounter,
+    /// Proofs that failed to verify or could not be verified at all.
+    pub proof_verify_failures: IntCounter,
+    /// Wall-clock time of proof verification, JWKS lookup included.
+    pub verify_duration: Histogram,
+}
+
+impl Metrics {
+    pub fn new() -> prometheus::Result<Self> {
+        let registry = Registry::new();
+
+        let keygen_requests = IntCounter::new(
+            "syra_keygen_requests_total",
+            "Requests to /admin/generate_user_key",
+        )?;
+        let proof_verify_failures = IntCounter::new(
+            "syra_proof_verify_failures_total",
+            "Groth16 proofs rejected by generate_user_key",
+        )?;
+        let verify_duration = Histogram::with_opts(HistogramOpts::new(
+            "syra_verify_duration_seconds",
+            "Time spent verifying a proof, JWKS lookup included",
+        ))?;
+
+        registry.register(Box::new(keygen_requests.clone()))?;
+        registry.register(Box::new(proof_verify_failures.clone()))?;
+        registry.register(Box::new(verify_duration.clone()))?;
+
+        Ok(Metrics { registry, keygen_requests, proof_verify_failures, verify_duration })
+    }
+}
+
+/// Prometheus text exposition of [`Metrics`].
+#[get("/metrics")]
+pub async fn metrics(state: web::Data<AppState>) -> HttpResponse {
+    let encoder = TextEncoder::new();
+    let mut buf = Vec::new();
+    if let Err(e) = encoder.encode(&state.metrics.registry.gather(), &mut buf) {
+        tracing::error!(error = %e, "failed to encode metrics");
+        return HttpResponse::InternalServerError().finish();
+    }
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, encoder.format_type()))
+        .body(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::test;
+
+    use crate::test_support::{admin_auth, keygen_body, test_app, test_state, MockVerifier};
+
+    /// The value of the sample `name` in a text exposition.
+    fn sample(exposition: &str, name: &str) -> f64 {
+        exposition
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .unwrap_or_else(|| panic!("{name} missing from\n{exposition}"))
+            .parse()
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn metrics_count_keygen_requests_failures_and_verify_time() {
+        let state = test_state(Arc::new(MockVerifier::rejecting(&["2"])));
+        let app = test_app!(state);
+        let scrape = || async {
+            let resp = test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+            assert_eq!(resp.status(), 200);
+            let content_type = resp.headers().get("content-type").unwrap().to_str().unwrap().to_string();
+            assert!(content_type.starts_with("text/plain; version=0.0.4"), "{content_type}");
+            String::from_utf8(test::read_body(resp).await.to_vec()).unwrap()
+        };
+
+        let before = scrape().await;
+        for line in [
+            "# TYPE syra_keygen_requests_total counter",
+            "# TYPE syra_proof_verify_failures_total counter",
+            "# TYPE syra_verify_duration_seconds histogram",
+        ] {
+            assert!(before.contains(line), "{line:?} missing from\n{before}");
+        }
+        assert_eq!(sample(&before, "syra_keygen_requests_total"), 0.0);
+        assert_eq!(sample(&before, "syra_verify_duration_seconds_count"), 0.0);
+
+        for user_id in ["1", "2", "3"] {
+            let req = test::TestRequest::post()
+                .uri("/admin/generate_user_key")
+                .insert_header(admin_auth())
+                .set_json(keygen_body(user_id))
+                .to_request();
+            let expected = if user_id == "2" { 401 } else { 200 };
+            assert_eq!(test::call_service(&app, req).await.status(), expected);
+        }
+
+        let after = scrape().await;
+        assert_eq!(sample(&after, "syra_keygen_requests_total"), 3.0);
+        assert_eq!(sample(&after, "syra_proof_verify_failures_total"), 1.0);
+        assert_eq!(sample(&after, "syra_verify_duration_seconds_count"), 3.0);
+        assert_eq!(sample(&after, "syra_verify_duration_seconds_bucket{le=\"+Inf\"}"), 3.0);
+    }
+}
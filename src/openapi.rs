@@ -0,0 +1,124 @@
+use actix_web::{get, HttpResponse};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use syra_login::jwt_proof_verifier::{JwtClaims, SubEncoding};
+use syra_login::proof::ProofFormat;
+
+use crate::challenge::ChallengeResponse;
+use crate::dkg::{DkgPointMessage, DkgStatusResponse};
+use crate::error::ErrorBody;
+use crate::revocation::{RevokeRequest, RevokeResponse, StatusResponse, WitnessResponse};
+use crate::{
+    BatchKeyResult, Compression, GenerateKeyRequest, GenerateKeyResponse, IvkResponse, IvkVersion, NativeJwtRequest,
+    PseudonymRequest, PseudonymResponse, RecoverKeyRequest, RotateKeysResponse, VerifyProofResponse,
+};
+
+/// OpenAPI description of the public and `/admin/*` endpoints; the
+/// operational ones (`/healthz`, `/readyz`, `/metrics`, the spec itself)
+/// are left out.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        crate::generate_user_key,
+        crate::generate_user_keys_batch,
+        crate::recover_user_key,
+        crate::generate_user_key_jwt,
+        crate::rotate_keys,
+        crate::revocation::revoke,
+        crate::dkg::receive_dkg,
+        crate::dkg::receive_dkg_batch,
+        crate::dkg::dkg_ws,
+        crate::dkg::dkg_status,
+        crate::get_ivk,
+        crate::get_ivk_versions,
+        crate::get_vk,
+        crate::verify_proof,
+        crate::pseudonym,
+        crate::revocation::revocation_status,
+        crate::challenge::challenge,
+    ),
+    components(schemas(
+        GenerateKeyRequest,
+        GenerateKeyResponse,
+        RecoverKeyRequest,
+        NativeJwtRequest,
+        BatchKeyResult,
+        Compression,
+        IvkResponse,
+        IvkVersion,
+        VerifyProofResponse,
+        PseudonymRequest,
+        PseudonymResponse,
+        ChallengeResponse,
+        RotateKeysResponse,
+        RevokeRequest,
+        RevokeResponse,
+        StatusResponse,
+        WitnessResponse,
+        DkgPointMessage,
+        DkgStatusResponse,
+        JwtClaims,
+        ProofFormat,
+        SubEncoding,
+        ErrorBody,
+    )),
+    modifiers(&AdminToken),
+)]
+pub struct ApiDoc;
+
+/// Declares the `admin_token` bearer scheme required on `/admin/*`.
+struct AdminToken;
+
+impl Modify for AdminToken {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "admin_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+/// The generated spec, as JSON.
+#[get("/api-docs/openapi.json")]
+pub async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::test;
+
+    use crate::fallback::ROUTES;
+    use crate::test_support::{test_app, test_state, MockVerifier};
+
+    /// Routes for operators rather than integrators, left out of the spec.
+    const UNDOCUMENTED: &[&str] = &["/healthz", "/readyz", "/metrics", "/api-docs/openapi.json"];
+
+    #[actix_web::test]
+    async fn served_spec_documents_every_route_but_the_operational_ones() {
+        let app = test_app!(test_state(Arc::new(MockVerifier::accepting())));
+        let req = test::TestRequest::get().uri("/api-docs/openapi.json").to_request();
+        let spec: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        let paths = spec["paths"].as_object().unwrap();
+        for (method, path) in ROUTES.iter().filter(|(_, path)| !UNDOCUMENTED.contains(path)) {
+            assert!(paths.get(*path).and_then(|p| p.get(method.to_lowercase())).is_some(), "{method} {path} missing");
+        }
+        assert_eq!(paths.len(), ROUTES.len() - UNDOCUMENTED.len());
+
+        let generate = &paths["/admin/generate_user_key"]["post"];
+        assert_eq!(generate["security"][0]["admin_token"], serde_json::json!([]));
+        assert_eq!(
+            generate["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/GenerateKeyResponse"
+        );
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        for name in ["GenerateKeyResponse", "ErrorBody", "RevokeRequest", "DkgPointMessage", "DkgStatusResponse"] {
+            assert!(schemas.contains_key(name), "schema {name} missing");
+        }
+        assert_eq!(spec["components"]["securitySchemes"]["admin_token"]["scheme"], "bearer");
+    }
+}
@@ -0,0 +1,333 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use argon2::Argon2;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::rand::{rngs::OsRng, RngCore};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use zeroize::Zeroizing;
+
+use crate::revocation::RevocationList;
+use syra_login::keys::Bp;
+
+use crate::StoredIssuerKeys;
+
+impl StoredIssuerKeys {
+    /// Serialize as
+    /// version ∥ g1 ∥ g2 ∥ isk ∥ ivk_hat ∥ W ∥ W_hat ∥ issuer_id
+    /// where `version` is a little-endian u32, each element is in its
+    /// compressed form and `issuer_id` is length-prefixed UTF-8, as in
+    /// [`IvkBundle::to_bytes`](syra_login::keys::IvkBundle::to_bytes).
+    fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut buf = Vec::new();
+        self.version.serialize_compressed(&mut buf)?;
+        self.bp.g1.serialize_compressed(&mut buf)?;
+        self.bp.g2.serialize_compressed(&mut buf)?;
+        self.isk.serialize_compressed(&mut buf)?;
+        self.ivk_hat.serialize_compressed(&mut buf)?;
+        self.W.serialize_compressed(&mut buf)?;
+        self.W_hat.serialize_compressed(&mut buf)?;
+        self.issuer_id.serialize_compressed(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Inverse of [`to_bytes`](Self::to_bytes), advancing `rd` past the keys.
+    /// Key files written before issuer ids existed end before `issuer_id`.
+    fn read(rd: &mut &[u8]) -> Result<Self> {
+        Ok(StoredIssuerKeys {
+            version: CanonicalDeserialize::deserialize_compressed(&mut *rd).context("version")?,
+            bp: Bp {
+                g1: CanonicalDeserialize::deserialize_compressed(&mut *rd).context("g1")?,
+                g2: CanonicalDeserialize::deserialize_compressed(&mut *rd).context("g2")?,
+            },
+            isk: CanonicalDeserialize::deserialize_compressed(&mut *rd).context("isk")?,
+            ivk_hat: CanonicalDeserialize::deserialize_compressed(&mut *rd).context("ivk_hat")?,
+            W: CanonicalDeserialize::deserialize_compressed(&mut *rd).context("W")?,
+            W_hat: CanonicalDeserialize::deserialize_compressed(&mut *rd).context("W_hat")?,
+            issuer_id: if rd.is_empty() {
+                String::new()
+            } else {
+                CanonicalDeserialize::deserialize_compressed(&mut *rd).context("issuer_id")?
+            },
+        })
+    }
+
+    /// Write the keys to `path` with [`write_atomic`], so a crash never
+    /// leaves a truncated key file behind and the file is owner-only. With a `passphrase` the file is
+    /// [`seal`]ed, so `isk` never touches the disk in the clear.
+    pub fn save_to_path(&self, path: &Path, passphrase: Option<&str>) -> io::Result<()> {
+        let plain = Zeroizing::new(self.to_bytes().map_err(|e| invalid_data(e.into()))?);
+        write_secret(path, &plain, passphrase)
+    }
+
+    /// Inverse of [`save_to_path`](Self::save_to_path). A sealed file needs
+    /// the passphrase it was written with; a plaintext one loads either way
+    /// (and is sealed on the next save if a passphrase is set).
+    ///
+    /// # Errors
+    /// - Fails if the file is sealed and `passphrase` is missing or wrong.
+    /// - Fails if the file is truncated or not a key file.
+    pub fn load_from_path(path: impl AsRef<Path>, passphrase: Option<&str>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = read_secret(path, passphrase)?;
+        let mut rd = &bytes[..];
+        let keys = StoredIssuerKeys::read(&mut rd)?;
+        ensure!(rd.is_empty(), "{} trailing bytes in {}", rd.len(), path.display());
+        Ok(keys)
+    }
+
+    /// Write retired key sets, oldest first, as one [`to_bytes`](Self::to_bytes)
+    /// record after another; written and sealed like [`save_to_path`](Self::save_to_path).
+    pub fn save_retired(retired: &[StoredIssuerKeys], path: &Path, passphrase: Option<&str>) -> io::Result<()> {
+        let mut plain = Zeroizing::new(Vec::new());
+        for keys in retired {
+            plain.extend_from_slice(&Zeroizing::new(keys.to_bytes().map_err(|e| invalid_data(e.into()))?));
+        }
+        write_secret(path, &plain, passphrase)
+    }
+
+    /// Inverse of [`save_retired`](Self::save_retired).
+    ///
+    /// # Errors
+    /// As [`load_from_path`](Self::load_from_path), naming the record that
+    /// failed to parse.
+    pub fn load_retired(path: impl AsRef<Path>, passphrase: Option<&str>) -> Result<Vec<Self>> {
+        let path = path.as_ref();
+        let bytes = read_secret(path, passphrase)?;
+        let mut rd = &bytes[..];
+        let mut retired = Vec::new();
+        while !rd.is_empty() {
+            let keys = StoredIssuerKeys::read(&mut rd)
+                .with_context(|| format!("retired key set {} in {}", retired.len(), path.display()))?;
+            retired.push(keys);
+        }
+        Ok(retired)
+    }
+}
+
+fn invalid_data(e: anyhow::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// [`write_atomic`] `plain`, [`seal`]ed first when there is a `passphrase`.
+fn write_secret(path: &Path, plain: &[u8], passphrase: Option<&str>) -> io::Result<()> {
+    let bytes = match passphrase {
+        Some(pass) => seal(plain, pass).map_err(invalid_data)?,
+        None => plain.to_vec(),
+    };
+    write_atomic(path, &bytes)
+}
+
+/// Inverse of [`write_secret`]: a sealed file is [`open`]ed, a plaintext one
+/// read as is.
+fn read_secret(path: &Path, passphrase: Option<&str>) -> Result<Zeroizing<Vec<u8>>> {
+    let raw = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    if raw.starts_with(SEALED_MAGIC) {
+        let pass = passphrase.ok_or_else(|| {
+            anyhow!("{} is encrypted; set SYRA_KEYS_PASSPHRASE", path.display())
+        })?;
+        open(&raw, pass).with_context(|| format!("decrypting {}", path.display()))
+    } else {
+        Ok(Zeroizing::new(raw))
+    }
+}
+
+/// Replace `path` with `bytes`: write a sibling `.tmp` file created with
+/// mode `0600` (so `isk` is never readable by other users, whatever the
+/// umask), fsync it, rename it over `path` and fsync the directory so the
+/// rename itself survives a power cut.
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    // A leftover from a crashed write may have looser permissions;
+    // `create_new` below must create the file itself for the mode to apply.
+    match fs::remove_file(&tmp) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&tmp, path)?;
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Prefix of an encrypted key file; never the start of a plaintext one,
+/// whose first bytes are a small little-endian version.
+const SEALED_MAGIC: &[u8] = b"SYRAENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// `magic ∥ salt ∥ nonce ∥ ChaCha20-Poly1305(key, nonce, plain)` with
+/// `key = Argon2id(passphrase, salt)` and fresh random salt and nonce.
+fn seal(plain: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let cipher = cipher_for(passphrase, &salt)?;
+    let ct = cipher
+        .encrypt(Nonce::from_slice(&nonce), plain)
+        .map_err(|_| anyhow!("encryption failed"))?;
+
+    let mut out = Vec::with_capacity(SEALED_MAGIC.len() + SALT_LEN + NONCE_LEN + ct.len());
+    out.extend_from_slice(SEALED_MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ct);
+    Ok(out)
+}
+
+/// Inverse of [`seal`]. A wrong passphrase and a tampered file are the same
+/// failure: the Poly1305 tag does not match.
+fn open(sealed: &[u8], passphrase: &str) -> Result<Zeroizing<Vec<u8>>> {
+    let body = &sealed[SEALED_MAGIC.len()..];
+    ensure!(body.len() > SALT_LEN + NONCE_LEN, "encrypted key file is truncated");
+    let (salt, rest) = body.split_at(SALT_LEN);
+    let (nonce, ct) = rest.split_at(NONCE_LEN);
+
+    let cipher = cipher_for(passphrase, salt)?;
+    match cipher.decrypt(Nonce::from_slice(nonce), ct) {
+        Ok(plain) => Ok(Zeroizing::new(plain)),
+        Err(_) => bail!("wrong passphrase or corrupted key file"),
+    }
+}
+
+fn cipher_for(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut *key)
+        .map_err(|e| anyhow!("deriving key from passphrase: {e}"))?;
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&*key)))
+}
+
+impl RevocationList {
+    /// Write the revoked `s` values, each compressed, with [`write_atomic`].
+    pub fn save_to_path(&self, path: &Path) -> io::Result<()> {
+        let mut buf = Vec::new();
+        for s in &self.revoked {
+            s.serialize_compressed(&mut buf)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+        write_atomic(path, &buf)
+    }
+
+    /// Inverse of [`save_to_path`](Self::save_to_path).
+    pub fn load_from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut rd = &bytes[..];
+
+        let mut list = RevocationList::default();
+        while !rd.is_empty() {
+            let s = CanonicalDeserialize::deserialize_compressed(&mut rd)
+                .with_context(|| format!("revoked entry {}", list.len()))?;
+            list.revoke(s);
+        }
+        Ok(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("syra-persistence-{}-{name}", std::process::id()))
+    }
+
+    #[test]
+    fn issuer_keys_round_trip_sealed_and_plain() {
+        let keys = crate::sample_issuer_keys(3, &Bp::default(), "issuer-a");
+        for pass in [None, Some("correct horse")] {
+            let path = temp_path(&format!("keys-{}", pass.is_some()));
+            keys.save_to_path(&path, pass).unwrap();
+            let loaded = StoredIssuerKeys::load_from_path(&path, pass).unwrap();
+            assert_eq!(loaded.version, 3);
+            assert_eq!(loaded.isk, keys.isk);
+            assert_eq!(loaded.ivk_bundle(), keys.ivk_bundle());
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn retired_keys_round_trip_in_order() {
+        let retired: Vec<_> = (1..=3).map(|v| crate::sample_issuer_keys(v, &Bp::default(), "issuer-a")).collect();
+        for pass in [None, Some("correct horse")] {
+            let path = temp_path(&format!("retired-{}", pass.is_some()));
+            StoredIssuerKeys::save_retired(&retired, &path, pass).unwrap();
+            let loaded = StoredIssuerKeys::load_retired(&path, pass).unwrap();
+            assert_eq!(loaded.iter().map(|k| k.version).collect::<Vec<_>>(), [1, 2, 3]);
+            for (loaded, keys) in loaded.iter().zip(&retired) {
+                assert_eq!(loaded.isk, keys.isk);
+                assert_eq!(loaded.ivk_bundle(), keys.ivk_bundle());
+            }
+            fs::remove_file(&path).unwrap();
+        }
+
+        let path = temp_path("retired-none");
+        StoredIssuerKeys::save_retired(&[], &path, None).unwrap();
+        assert!(StoredIssuerKeys::load_retired(&path, None).unwrap().is_empty());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn sealed_key_file_rejects_wrong_passphrase() {
+        let keys = crate::sample_issuer_keys(1, &Bp::default(), "");
+        let path = temp_path("keys-wrong-pass");
+        keys.save_to_path(&path, Some("right")).unwrap();
+        assert!(StoredIssuerKeys::load_from_path(&path, Some("wrong")).is_err());
+        assert!(StoredIssuerKeys::load_from_path(&path, None).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn key_file_is_owner_only_even_over_a_stale_tmp() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = temp_path("keys-mode");
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, b"stale").unwrap();
+        fs::set_permissions(&tmp, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let keys = crate::sample_issuer_keys(1, &Bp::default(), "");
+        keys.save_to_path(&path, None).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!tmp.exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn revocation_list_round_trips() {
+        let mut list = RevocationList::default();
+        list.revoke(syra_login::keys::s_from_sub("alice"));
+        list.revoke(syra_login::keys::s_from_sub("bob"));
+        let path = temp_path("revoked");
+        list.save_to_path(&path).unwrap();
+        let loaded = RevocationList::load_from_path(&path).unwrap();
+        assert_eq!(loaded.revoked, list.revoked);
+        fs::remove_file(&path).unwrap();
+    }
+}
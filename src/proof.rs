@@ -1,13 +1,17 @@
 use anyhow::{anyhow, bail, ensure, Context, Result};
 use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
 use ark_ec::AffineRepr;
-use ark_ff::PrimeField;
+use ark_ff::{PrimeField, Zero};
 use ark_groth16::Proof;
-use base64;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
 use num_bigint::BigUint;
 use serde::Deserialize;
+use serde_json::Value;
+use utoipa::ToSchema;
 use std::io::Cursor;
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress};
 
 fn str_to_fq(s: &str) -> Result<Fq> {
     // accept decimal or 0x-hex
@@ -21,14 +25,37 @@ fn str_to_fq(s: &str) -> Result<Fq> {
     Ok(Fq::from_be_bytes_mod_order(&n.to_bytes_be()))
 }
 
+/// Reject points that are off the curve or outside the prime-order subgroup.
+fn ensure_valid_point<P: SWCurveConfig>(p: &Affine<P>, name: &str) -> Result<()> {
+    ensure!(p.is_on_curve(), "proof point {name} is not on the curve");
+    ensure!(
+        p.is_in_correct_subgroup_assuming_on_curve(),
+        "proof point {name} is not in the prime-order subgroup"
+    );
+    Ok(())
+}
+
+/// The JSON parsers build points unchecked; validate them before use.
+fn validated(proof: Proof<Bn254>) -> Result<Proof<Bn254>> {
+    ensure_valid_point(&proof.a, "A")?;
+    ensure_valid_point(&proof.b, "B")?;
+    ensure_valid_point(&proof.c, "C")?;
+    Ok(proof)
+}
+
+/// SnarkJS / rapidsnark `proof.json`. Points are `[X, Y, Z]` in Jacobian
+/// coordinates, the representation ffjavascript computes in, i.e. the affine
+/// point is `(X / Z², Y / Z³)`. Both provers normalize before writing, so
+/// `Z` is `1` in practice, but any non-zero `Z` is accepted.
 #[derive(Debug, Deserialize)]
 struct JsProof {
     pi_a: [String; 3],
-    pi_b: [[String; 2]; 3], // row0=x, row1=y, row2=[1,0]
+    pi_b: [[String; 2]; 3], // row0=X, row1=Y, row2=Z (usually [1,0])
     pi_c: [String; 3],
 }
 
-
+/// arkworks' `Projective` is Jacobian as well, so `[X, Y, Z]` goes in as-is
+/// and `into()` divides by `Z²` / `Z³`; no separate affine case is needed.
 fn proof_from_snarkjs_json(json: &str) -> Result<Proof<Bn254>> {
     let p: JsProof = serde_json::from_str(json)?;
 
@@ -62,31 +89,106 @@ fn proof_from_snarkjs_json(json: &str) -> Result<Proof<Bn254>> {
     )
         .into();
 
-    Ok(Proof { a: g1, b: g2, c: g3 })
+    validated(Proof { a: g1, b: g2, c: g3 })
 }
 
 
+/// Bytes of a serialized BN254 proof in the given form: A, C ∈ G1 (32 / 64
+/// bytes), B ∈ G2 (64 / 128 bytes), so 128 compressed and 256 uncompressed
+/// with arkworks 0.4. Asked of arkworks rather than hardcoded, so a change
+/// in its encoding cannot leave the size check behind.
+fn proof_len(compress: Compress) -> usize {
+    Proof::<Bn254>::default().serialized_size(compress)
+}
+
+/// gnark's JSON proof layout: affine points, `Bs` coordinates as `A0 + A1·u`.
+#[derive(Debug, Deserialize)]
+struct GnarkG1 {
+    #[serde(rename = "X")] x: String,
+    #[serde(rename = "Y")] y: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GnarkFq2 {
+    #[serde(rename = "A0")] a0: String,
+    #[serde(rename = "A1")] a1: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GnarkG2 {
+    #[serde(rename = "X")] x: GnarkFq2,
+    #[serde(rename = "Y")] y: GnarkFq2,
+}
+
+#[derive(Debug, Deserialize)]
+struct GnarkProof {
+    #[serde(rename = "Ar")] ar: GnarkG1,
+    #[serde(rename = "Bs")] bs: GnarkG2,
+    #[serde(rename = "Krs")] krs: GnarkG1,
+}
+
+fn gnark_g1(p: &GnarkG1) -> Result<G1Affine> {
+    let (x, y) = (str_to_fq(&p.x)?, str_to_fq(&p.y)?);
+    // gnark encodes the point at infinity as (0, 0)
+    if x.is_zero() && y.is_zero() {
+        return Ok(G1Affine::zero());
+    }
+    Ok(G1Affine::new_unchecked(x, y))
+}
+
+fn gnark_g2(p: &GnarkG2) -> Result<G2Affine> {
+    let x = Fq2::new(str_to_fq(&p.x.a0)?, str_to_fq(&p.x.a1)?);
+    let y = Fq2::new(str_to_fq(&p.y.a0)?, str_to_fq(&p.y.a1)?);
+    if x.is_zero() && y.is_zero() {
+        return Ok(G2Affine::zero());
+    }
+    Ok(G2Affine::new_unchecked(x, y))
+}
+
+fn proof_from_gnark_json(json: &str) -> Result<Proof<Bn254>> {
+    let p: GnarkProof = serde_json::from_str(json)?;
+    validated(Proof {
+        a: gnark_g1(&p.ar)?,
+        b: gnark_g2(&p.bs)?,
+        c: gnark_g1(&p.krs)?,
+    })
+}
+
+/// SnarkJS (`pi_a`, `pi_b`, `pi_c`) unless the object looks like gnark's
+/// (`Ar`, `Bs`, `Krs`).
+fn proof_from_json(json: &str) -> Result<Proof<Bn254>> {
+    let v: Value = serde_json::from_str(json)?;
+    if v.get("pi_a").is_none() && v.get("Ar").is_some() {
+        proof_from_gnark_json(json)
+    } else {
+        proof_from_snarkjs_json(json)
+    }
+}
+
 fn proof_from_ark_bytes(raw: &[u8]) -> Result<Proof<Bn254>> {
     let mut cur = Cursor::new(raw);
+    let (compressed, uncompressed) = (proof_len(Compress::Yes), proof_len(Compress::No));
     match raw.len() {
-        259 => Ok(Proof::<Bn254>::deserialize_uncompressed(&mut cur)
+        n if n == uncompressed => Ok(Proof::<Bn254>::deserialize_uncompressed(&mut cur)
             .context("uncompressed deserialize failed")?),
-        192 => Ok(Proof::<Bn254>::deserialize_compressed(&mut cur)
+        n if n == compressed => Ok(Proof::<Bn254>::deserialize_compressed(&mut cur)
             .context("compressed deserialize failed")?),
-        n => bail!("unknown proof binary size: {n} bytes"),
+        n => bail!(
+            "unknown proof binary size: {n} bytes (expected {compressed} compressed or {uncompressed} uncompressed)"
+        ),
     }
 }
 
 /// Unified entry-point: give it the **base-64 string** you receive from the
-/// client (could be raw Ark bytes, could be SnarkJS JSON). It returns an Ark
-/// `Proof<Bn254>` or an error.
+/// client (could be raw Ark bytes, SnarkJS JSON or gnark JSON). It returns an
+/// Ark `Proof<Bn254>` or an error.
 pub fn base64_to_proof(b64: &str) -> Result<Proof<Bn254>> {
-    let bytes = base64::decode(b64.trim())?;
+    let bytes = STANDARD.decode(b64.trim())?;
 
     // branch A: looks like UTF-8 JSON
     if let Ok(txt) = std::str::from_utf8(&bytes) {
         if txt.trim_start().starts_with('{') {
-            return proof_from_snarkjs_json(txt);
+            return proof_from_json(txt);
         }
     }
 
@@ -94,9 +196,242 @@ pub fn base64_to_proof(b64: &str) -> Result<Proof<Bn254>> {
     proof_from_ark_bytes(&bytes)
 }
 
+/// How the client encoded `proof`. Without it, a JSON object is read as a
+/// SnarkJS (or gnark) proof and a string as base-64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProofFormat {
+    /// Base-64 of Ark bytes or of a JSON proof (the original encoding).
+    Base64,
+    /// The SnarkJS `proof.json` object (or its text) as-is.
+    SnarkjsJson,
+}
+
+/// Decode the `proof` field of a request, which is either a base-64 string
+/// or, to spare clients the double encoding, the SnarkJS JSON object itself.
+pub fn decode_proof(proof: &Value, format: Option<ProofFormat>) -> Result<Proof<Bn254>> {
+    match (proof, format) {
+        (Value::String(b64), None | Some(ProofFormat::Base64)) => base64_to_proof(b64),
+        (Value::String(json), Some(ProofFormat::SnarkjsJson)) => proof_from_json(json),
+        (Value::Object(_), None | Some(ProofFormat::SnarkjsJson)) => proof_from_json(&proof.to_string()),
+        (Value::Object(_), Some(ProofFormat::Base64)) => {
+            bail!("proof_format is base64 but proof is a JSON object")
+        }
+        _ => bail!("proof must be a base-64 string or a JSON object"),
+    }
+}
+
 // (optional) Ark proof → base-64 (uncompressed)
 pub fn proof_to_base64_uncompressed(p: &Proof<Bn254>) -> Result<String> {
-    let mut buf = Vec::with_capacity(259);
+    let mut buf = Vec::with_capacity(proof_len(Compress::No));
     p.serialize_uncompressed(&mut buf)?;
-    Ok(base64::encode(buf))
+    Ok(STANDARD.encode(buf))
+}
+
+/// Ark proof → base-64 (compressed, 128 bytes before encoding). Half the size
+/// of the uncompressed form; `base64_to_proof` reads either.
+pub fn proof_to_base64_compressed(p: &Proof<Bn254>) -> Result<String> {
+    let mut buf = Vec::with_capacity(proof_len(Compress::Yes));
+    p.serialize_compressed(&mut buf)?;
+    Ok(STANDARD.encode(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_circuit::TestCircuit;
+    use ark_bn254::Fr;
+    use ark_ff::One;
+
+    fn sample_proof() -> Proof<Bn254> {
+        TestCircuit::new(2).prove(&[Fr::from(7u64), Fr::from(11u64)])
+    }
+
+    fn dec(f: &Fq) -> String {
+        BigUint::from(f.into_bigint()).to_string()
+    }
+
+    /// `proof` as rapidsnark writes it, with each point in Jacobian
+    /// coordinates `(x·z², y·z³, z)` for the given `z` (`1` is affine).
+    fn rapidsnark_json(proof: &Proof<Bn254>, z1: Fq, z2: Fq2) -> String {
+        let g1 = |p: &G1Affine| {
+            let (x, y) = p.xy().unwrap();
+            let z1_2 = z1 * z1;
+            [dec(&(*x * z1_2)), dec(&(*y * z1_2 * z1)), dec(&z1)]
+        };
+        let (x, y) = proof.b.xy().unwrap();
+        let z2_2 = z2 * z2;
+        let (bx, by) = (*x * z2_2, *y * z2_2 * z2);
+        serde_json::json!({
+            "pi_a": g1(&proof.a),
+            "pi_b": [[dec(&bx.c0), dec(&bx.c1)], [dec(&by.c0), dec(&by.c1)], [dec(&z2.c0), dec(&z2.c1)]],
+            "pi_c": g1(&proof.c),
+            "protocol": "groth16",
+            "curve": "bn128",
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn rapidsnark_proofs_verify_with_affine_and_jacobian_points() {
+        use ark_groth16::Groth16;
+        use ark_snark::SNARK;
+
+        let circuit = TestCircuit::new(2);
+        let inputs = [Fr::from(7u64), Fr::from(11u64)];
+        let proof = circuit.prove(&inputs);
+        for (z1, z2) in [
+            (Fq::from(1u64), Fq2::new(Fq::from(1u64), Fq::zero())),
+            (Fq::from(5u64), Fq2::new(Fq::from(3u64), Fq::from(2u64))),
+        ] {
+            let json = rapidsnark_json(&proof, z1, z2);
+            let parsed = proof_from_snarkjs_json(&json).unwrap();
+            assert_eq!(parsed, proof, "z1 = {z1}, z2 = {z2}");
+            assert!(Groth16::<Bn254>::verify(&circuit.vk, &inputs, &parsed).unwrap());
+            // and through the base-64 entry point clients use
+            assert_eq!(base64_to_proof(&STANDARD.encode(&json)).unwrap(), proof);
+        }
+    }
+
+    #[test]
+    fn proof_round_trips_compressed_and_uncompressed() {
+        let proof = sample_proof();
+        for (b64, compress) in [
+            (proof_to_base64_compressed(&proof).unwrap(), Compress::Yes),
+            (proof_to_base64_uncompressed(&proof).unwrap(), Compress::No),
+        ] {
+            assert_eq!(STANDARD.decode(&b64).unwrap().len(), proof_len(compress));
+            assert_eq!(base64_to_proof(&b64).unwrap(), proof);
+        }
+    }
+
+    /// A SnarkJS verifying key for two public inputs, and a proof of
+    /// `[7, 11]` under it written out by SnarkJS (decimal, Jacobian with
+    /// `Z = 1`) and by gnark (affine, `0x` hex).
+    const FIXTURE_VK: &str = include_str!("../tests/fixtures/groth16_vk.json");
+    const SNARKJS_FIXTURE: &str = include_str!("../tests/fixtures/proof_snarkjs.json");
+    const GNARK_FIXTURE: &str = include_str!("../tests/fixtures/proof_gnark.json");
+
+    fn fixture_inputs() -> [Fr; 2] {
+        [Fr::from(7u64), Fr::from(11u64)]
+    }
+
+    #[test]
+    fn gnark_and_snarkjs_fixtures_parse_to_the_same_verifying_proof() {
+        use ark_groth16::Groth16;
+        use ark_snark::SNARK;
+
+        let vk = crate::jwt_proof_verifier::parse_vk_json(FIXTURE_VK).unwrap();
+        let snarkjs = proof_from_json(SNARKJS_FIXTURE).unwrap();
+        let gnark = proof_from_json(GNARK_FIXTURE).unwrap();
+        assert_eq!(gnark, snarkjs);
+        assert!(Groth16::<Bn254>::verify(&vk, &fixture_inputs(), &snarkjs).unwrap());
+        assert!(!Groth16::<Bn254>::verify(&vk, &[Fr::from(7u64), Fr::from(12u64)], &gnark).unwrap());
+
+        // Clients send either one base-64 encoded, too.
+        for json in [SNARKJS_FIXTURE, GNARK_FIXTURE] {
+            assert_eq!(base64_to_proof(&STANDARD.encode(json)).unwrap(), snarkjs);
+        }
+    }
+
+    #[test]
+    fn gnark_coordinates_may_be_decimal_or_hex_and_zero_is_infinity() {
+        let mut gnark: Value = serde_json::from_str(GNARK_FIXTURE).unwrap();
+        let expected = proof_from_json(GNARK_FIXTURE).unwrap();
+
+        // `0X` and decimal spell the same coordinates.
+        let x = gnark["Ar"]["X"].as_str().unwrap().to_string();
+        gnark["Ar"]["X"] = x.replacen("0x", "0X", 1).into();
+        let decimal = BigUint::parse_bytes(&gnark["Ar"]["Y"].as_str().unwrap().as_bytes()[2..], 16).unwrap();
+        gnark["Ar"]["Y"] = decimal.to_string().into();
+        assert_eq!(proof_from_json(&gnark.to_string()).unwrap(), expected);
+
+        // gnark writes the identity as (0, 0), in either base.
+        gnark["Krs"] = serde_json::json!({ "X": "0", "Y": "0x0" });
+        gnark["Bs"] = serde_json::json!({ "X": { "A0": "0", "A1": "0" }, "Y": { "A0": "0x00", "A1": "0" } });
+        let proof = proof_from_json(&gnark.to_string()).unwrap();
+        assert!(proof.c.is_zero() && proof.b.is_zero());
+        assert_eq!(proof.a, expected.a);
+
+        gnark["Krs"]["X"] = "0xnot-hex".into();
+        let err = proof_from_json(&gnark.to_string()).unwrap_err();
+        assert!(err.to_string().contains("invalid integer"), "{err:#}");
+    }
+
+    /// A point on BN254's twist outside its order-`r` subgroup; the twist
+    /// has a large cofactor, so nearly any `x` with a square root gives one.
+    fn g2_outside_subgroup() -> G2Affine {
+        (1u64..)
+            .map(|i| Fq2::new(Fq::from(i), Fq::one()))
+            .find_map(|x| G2Affine::get_point_from_x_unchecked(x, false))
+            .filter(|p| !p.is_in_correct_subgroup_assuming_on_curve())
+            .expect("a point outside the subgroup")
+    }
+
+    #[test]
+    fn points_off_the_curve_or_outside_the_subgroup_are_rejected() {
+        let b = g2_outside_subgroup();
+        assert!(b.is_on_curve());
+        let err = ensure_valid_point(&b, "B").unwrap_err();
+        assert_eq!(err.to_string(), "proof point B is not in the prime-order subgroup");
+
+        // Through a JSON proof, which is where such points come from.
+        let hex = |f: &Fq| format!("0x{}", BigUint::from(f.into_bigint()).to_str_radix(16));
+        let mut gnark: Value = serde_json::from_str(GNARK_FIXTURE).unwrap();
+        let (x, y) = b.xy().unwrap();
+        gnark["Bs"] = serde_json::json!({
+            "X": { "A0": hex(&x.c0), "A1": hex(&x.c1) },
+            "Y": { "A0": hex(&y.c0), "A1": hex(&y.c1) },
+        });
+        let err = proof_from_json(&gnark.to_string()).unwrap_err();
+        assert_eq!(err.to_string(), "proof point B is not in the prime-order subgroup");
+
+        let off_curve = G1Affine::new_unchecked(Fq::one(), Fq::one());
+        let err = ensure_valid_point(&off_curve, "A").unwrap_err();
+        assert_eq!(err.to_string(), "proof point A is not on the curve");
+    }
+
+    #[test]
+    fn decode_proof_follows_proof_format_or_the_json_type() {
+        let expected = proof_from_json(SNARKJS_FIXTURE).unwrap();
+        let object: Value = serde_json::from_str(SNARKJS_FIXTURE).unwrap();
+        let text = Value::String(SNARKJS_FIXTURE.to_string());
+        let b64 = Value::String(proof_to_base64_compressed(&expected).unwrap());
+
+        // the object itself, or its text when the format says so
+        assert_eq!(decode_proof(&object, None).unwrap(), expected);
+        assert_eq!(decode_proof(&object, Some(ProofFormat::SnarkjsJson)).unwrap(), expected);
+        assert_eq!(decode_proof(&text, Some(ProofFormat::SnarkjsJson)).unwrap(), expected);
+        // the legacy base-64 string, with or without the format
+        assert_eq!(decode_proof(&b64, None).unwrap(), expected);
+        assert_eq!(decode_proof(&b64, Some(ProofFormat::Base64)).unwrap(), expected);
+
+        let err = decode_proof(&object, Some(ProofFormat::Base64)).unwrap_err();
+        assert_eq!(err.to_string(), "proof_format is base64 but proof is a JSON object");
+        assert!(decode_proof(&b64, Some(ProofFormat::SnarkjsJson)).is_err());
+        assert!(decode_proof(&text, None).is_err(), "JSON text is not base-64");
+        for other in [Value::Null, serde_json::json!(42), serde_json::json!([1, 2])] {
+            let err = decode_proof(&other, None).unwrap_err();
+            assert_eq!(err.to_string(), "proof must be a base-64 string or a JSON object");
+        }
+        assert_eq!(
+            serde_json::from_str::<ProofFormat>("\"snarkjs-json\"").unwrap(),
+            ProofFormat::SnarkjsJson
+        );
+    }
+
+    #[test]
+    fn proof_len_matches_the_arkworks_encodings() {
+        assert_eq!(proof_len(Compress::Yes), 128);
+        assert_eq!(proof_len(Compress::No), 256);
+
+        let proof = sample_proof();
+        let mut bytes = Vec::new();
+        proof.serialize_compressed(&mut bytes).unwrap();
+        let err = proof_from_ark_bytes(&bytes[..127]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown proof binary size: 127 bytes (expected 128 compressed or 256 uncompressed)"
+        );
+    }
 }
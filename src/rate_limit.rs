@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::lock::LockExt;
+
+/// Buckets untouched for this long are full again and get dropped.
+const IDLE_EVICTION: Duration = Duration::from_secs(60);
+
+/// One client's token bucket.
+pub struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+/// Per-IP token bucket: each client may burst up to `rate` requests and then
+/// gets `rate` new tokens per second.
+pub struct RateLimiter {
+    /// Tokens per second (and bucket capacity); `0` disables the limiter.
+    rate: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+    last_sweep: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(per_sec: u32) -> Self {
+        RateLimiter {
+            rate: per_sec as f64,
+            buckets: Mutex::new(HashMap::new()),
+            last_sweep: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Take one token for `ip`. On refusal returns how long until the next
+    /// token is available.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(ip, Instant::now())
+    }
+
+    /// [`check`](Self::check) as of `now`.
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        if self.rate == 0.0 {
+            return Ok(());
+        }
+        self.evict_idle(now);
+
+        let mut buckets = self.buckets.lock_or_recover();
+        let bucket = buckets.entry(ip).or_insert(Bucket { tokens: self.rate, last: now });
+
+        let elapsed = now.duration_since(bucket.last).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.rate);
+        bucket.last = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        }
+    }
+
+    /// Drop buckets idle for longer than [`IDLE_EVICTION`], at most once per
+    /// eviction period, so the map does not grow with every address seen.
+    fn evict_idle(&self, now: Instant) {
+        let mut last_sweep = self.last_sweep.lock_or_recover();
+        if now.duration_since(*last_sweep) < IDLE_EVICTION {
+            return;
+        }
+        *last_sweep = now;
+        self.buckets
+            .lock_or_recover()
+            .retain(|_, b| now.duration_since(b.last) < IDLE_EVICTION);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use actix_web::http::header;
+    use actix_web::test::{call_service, read_body_json, TestRequest};
+    use actix_web::web;
+
+    use super::*;
+    use crate::test_support::{admin_auth, keygen_body, test_app, test_app_state, MockVerifier};
+
+    const ALICE: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 1));
+    const BOB: IpAddr = IpAddr::V4(std::net::Ipv4Addr::new(192, 0, 2, 2));
+
+    #[test]
+    fn buckets_burst_to_the_rate_and_refill_over_time() {
+        let limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        assert_eq!(limiter.check_at(ALICE, start), Ok(()));
+        assert_eq!(limiter.check_at(ALICE, start), Ok(()));
+        assert_eq!(limiter.check_at(ALICE, start), Err(Duration::from_millis(500)));
+        // Each address has its own bucket.
+        assert_eq!(limiter.check_at(BOB, start), Ok(()));
+
+        let half = start + Duration::from_millis(500);
+        assert_eq!(limiter.check_at(ALICE, half), Ok(()));
+        assert!(limiter.check_at(ALICE, half).is_err());
+
+        // A long pause refills the bucket to the rate, not beyond.
+        let later = start + Duration::from_secs(30);
+        assert_eq!(limiter.check_at(ALICE, later), Ok(()));
+        assert_eq!(limiter.check_at(ALICE, later), Ok(()));
+        assert!(limiter.check_at(ALICE, later).is_err());
+
+        let unlimited = RateLimiter::new(0);
+        assert!((0..100).all(|_| unlimited.check_at(ALICE, start).is_ok()));
+    }
+
+    #[test]
+    fn idle_buckets_are_evicted_once_per_period() {
+        let limiter = RateLimiter::new(1);
+        let start = Instant::now();
+        limiter.check_at(ALICE, start).unwrap();
+        limiter.check_at(BOB, start + IDLE_EVICTION / 2).unwrap();
+        assert_eq!(limiter.buckets.lock_or_recover().len(), 2);
+
+        // The first sweep drops Alice, idle for a full period, but not Bob.
+        let sweep = start + IDLE_EVICTION + Duration::from_secs(1);
+        limiter.check_at(BOB, sweep).unwrap();
+        assert!(!limiter.buckets.lock_or_recover().contains_key(&ALICE));
+
+        // No second sweep within the period: Alice's new bucket stays.
+        limiter.check_at(ALICE, sweep).unwrap();
+        let quiet = sweep + IDLE_EVICTION / 2;
+        limiter.check_at(BOB, quiet).unwrap();
+        assert_eq!(limiter.buckets.lock_or_recover().len(), 2);
+    }
+
+    #[actix_web::test]
+    async fn over_the_limit_is_429_with_retry_after_before_any_verification() {
+        let verifier = Arc::new(MockVerifier::accepting());
+        let mut state = test_app_state(verifier.clone());
+        state.rate_limiter = RateLimiter::new(1);
+        let app = test_app!(web::Data::new(state));
+        let request = |ip: IpAddr| {
+            TestRequest::post()
+                .uri("/admin/generate_user_key")
+                .insert_header(admin_auth())
+                .peer_addr((ip, 40000).into())
+                .set_json(keygen_body("1"))
+                .to_request()
+        };
+
+        assert_eq!(call_service(&app, request(ALICE)).await.status(), 200);
+        let resp = call_service(&app, request(ALICE)).await;
+        assert_eq!(resp.status(), 429);
+        assert_eq!(resp.headers().get(header::RETRY_AFTER).unwrap(), "1");
+        let body: serde_json::Value = read_body_json(resp).await;
+        assert_eq!(body["code"], "RATE_LIMITED");
+        assert_eq!(verifier.calls.load(Ordering::SeqCst), 1);
+
+        assert_eq!(call_service(&app, request(BOB)).await.status(), 200);
+    }
+}
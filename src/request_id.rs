@@ -0,0 +1,163 @@
+use std::fmt;
+
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    http::StatusCode,
+    middleware::Next,
+    Error, HttpMessage, HttpResponse, ResponseError,
+};
+use tracing_actix_web::RequestId;
+
+/// Response header carrying the request's correlation id.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Correlation id of the request being handled, if any; read by
+/// [`ErrorBody`](crate::error::ErrorBody) so error responses carry it.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Middleware for `middleware::from_fn`, mounted inside `TracingLogger`:
+/// takes the UUID `TracingLogger` assigned to the request (recorded as
+/// `request_id` on its root span, so on every log line of the request),
+/// makes it visible to error bodies while the request runs, and returns it
+/// in `X-Request-Id`. Errors from inner middleware get the id too, see
+/// [`WithRequestId`].
+pub async fn propagate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(id) = req.extensions().get::<RequestId>().map(ToString::to_string) else {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    };
+    match REQUEST_ID.scope(id.clone(), next.call(req)).await {
+        Ok(res) => {
+            let mut res = res.map_into_boxed_body();
+            res.headers_mut().insert(REQUEST_ID_HEADER, header_value(&id));
+            Ok(res)
+        }
+        Err(error) => Err(WithRequestId { error, id }.into()),
+    }
+}
+
+fn header_value(id: &str) -> HeaderValue {
+    HeaderValue::from_str(id).expect("a UUID is a valid header value")
+}
+
+/// An error from inner middleware. actix renders it only after [`propagate`]
+/// returned, so it carries the id to render with. Holding on to the request
+/// instead is not an option: routing needs the only reference to it.
+#[derive(Debug)]
+struct WithRequestId {
+    error: Error,
+    id: String,
+}
+
+impl fmt::Display for WithRequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl ResponseError for WithRequestId {
+    fn status_code(&self) -> StatusCode {
+        self.error.as_response_error().status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = REQUEST_ID.sync_scope(self.id.clone(), || self.error.error_response());
+        res.headers_mut().insert(REQUEST_ID_HEADER, header_value(&self.id));
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    use actix_web::http::header::HeaderMap;
+    use actix_web::{middleware, test, App};
+    use tracing_actix_web::TracingLogger;
+
+    use super::*;
+    use crate::test_support::{admin_auth, test_state, MockVerifier, MAX_PROOF_BYTES};
+
+    /// Log output, shared with the subscriber that writes it.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// The `X-Request-Id` of every response is the id `TracingLogger`
+    /// generated for it: fresh per request, in the error body, and on the
+    /// span of the request's log lines.
+    #[actix_web::test]
+    async fn each_request_id_is_returned_and_logged_with_the_request() {
+        let logs = Captured::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .json()
+            .flatten_event(true)
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let app = test::init_service(
+            App::new()
+                .wrap(middleware::from_fn(propagate))
+                .wrap(TracingLogger::default())
+                .app_data(test_state(Arc::new(MockVerifier::accepting())))
+                .configure(|cfg| crate::routes(cfg, MAX_PROOF_BYTES)),
+        )
+        .await;
+        let request_id = |headers: &HeaderMap| headers.get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+
+        let resp = test::call_service(&app, test::TestRequest::get().uri("/healthz").to_request()).await;
+        assert_eq!(resp.status(), 200);
+        let first = request_id(resp.headers());
+
+        // Rejected by the admin-token middleware, inside this one.
+        let req = test::TestRequest::get()
+            .uri("/admin/dkg_status")
+            .insert_header((REQUEST_ID_HEADER, "client-chosen"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+        let id = request_id(resp.headers());
+        assert_ne!(id, first);
+        assert_eq!(id.len(), 36, "{id}");
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["request_id"], id.as_str());
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let line = logs
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .find(|line| line["message"] == "rejected unauthenticated admin request")
+            .unwrap_or_else(|| panic!("no rejection logged in\n{logs}"));
+        assert_eq!(line["span"]["request_id"], id.as_str());
+
+        let req = test::TestRequest::get().uri("/admin/dkg_status").insert_header(admin_auth()).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert!(resp.headers().contains_key(REQUEST_ID_HEADER));
+    }
+}
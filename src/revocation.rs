@@ -0,0 +1,287 @@
+use std::collections::BTreeSet;
+
+use actix_web::{get, post, web, HttpResponse};
+use ark_bls12_381::{Fr, G1Affine, G1Projective};
+use ark_ec::CurveGroup;
+use ark_ff::{Field, One};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use syra_login::credential::NonMembershipWitness;
+use syra_login::codec::{fr_to_hex, g1_to_hex};
+use syra_login::jwt_proof_verifier::SubEncoding;
+use syra_login::keys::s_from_sub;
+
+use crate::error::ApiError;
+use crate::lock::LockExt;
+use crate::{AppState, StoredIssuerKeys};
+
+/// Revoked user elements `s`, accumulated under the key the issuer signs
+/// with (its DKG share once it has one, see `AppState::signing_keys`) as
+///
+/// ```text
+/// V = g1^{Π_i (s_i + isk)}
+/// ```
+///
+/// `V` depends on `isk`, so it (and every witness) changes when the keys are
+/// rotated; the set of revoked `s` carries over.
+#[derive(Default)]
+pub struct RevocationList {
+    pub(crate) revoked: BTreeSet<Fr>,
+}
+
+impl RevocationList {
+    /// Add `s` to the set; `false` if it was already revoked.
+    pub fn revoke(&mut self, s: Fr) -> bool {
+        self.revoked.insert(s)
+    }
+
+    pub fn is_revoked(&self, s: &Fr) -> bool {
+        self.revoked.contains(s)
+    }
+
+    pub fn len(&self) -> usize {
+        self.revoked.len()
+    }
+
+    /// `Π_i (s_i + isk)`; one for the empty set.
+    fn product(&self, isk: Fr) -> Fr {
+        self.revoked.iter().fold(Fr::one(), |acc, s_i| acc * (*s_i + isk))
+    }
+
+    /// The public accumulator `V` under `keys`.
+    pub fn accumulator(&self, keys: &StoredIssuerKeys) -> G1Affine {
+        (G1Projective::from(keys.bp.g1) * self.product(keys.isk)).into_affine()
+    }
+
+    /// Non-membership witness for `s` under `keys`, or `None` if `s` is revoked.
+    pub fn non_membership_witness(&self, keys: &StoredIssuerKeys, s: Fr) -> Option<NonMembershipWitness> {
+        if self.is_revoked(&s) {
+            return None;
+        }
+        let d = self.revoked.iter().fold(Fr::one(), |acc, s_i| acc * (*s_i - s));
+        let q = (self.product(keys.isk) - d) * (s + keys.isk).inverse()?;
+        Some(NonMembershipWitness {
+            C: (G1Projective::from(keys.bp.g1) * q).into_affine(),
+            d,
+        })
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RevokeRequest {
+    /// plain-text user identifier, as passed to `generate_user_key`
+    pub user_id: String,
+    /// how `user_id` is spelled, as for `generate_user_key`
+    #[serde(default)]
+    pub sub_encoding: SubEncoding,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RevokeResponse {
+    accumulator: String,
+    revoked_count: usize,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct StatusQuery {
+    pub user_id: String,
+    #[serde(default)]
+    pub sub_encoding: SubEncoding,
+}
+
+/// The `s` that `generate_user_key` derives for `user_id` spelled in
+/// `encoding`: the subject is rewritten in decimal first, so every spelling
+/// of one subject revokes (and is checked against) the same element.
+fn subject_element(user_id: &str, encoding: SubEncoding) -> Result<Fr, ApiError> {
+    let sub = encoding
+        .to_decimal(user_id)
+        .map_err(|e| ApiError::InvalidSubject(format!("{e:#}")))?;
+    Ok(s_from_sub(&sub))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct WitnessResponse {
+    C: String,
+    d: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct StatusResponse {
+    version: u32,
+    accumulator: String,
+    revoked: bool,
+    /// present iff `revoked` is false
+    witness: Option<WitnessResponse>,
+}
+
+/// # Arguments
+/// * `state: web::Data<AppState>` — shared state; the user's `s`, derived as
+///   `generate_user_key` derives it, is added to `state.revocation` and the
+///   list is persisted.
+/// * `req: web::Json<RevokeRequest>` — `{ user_id, sub_encoding? }`.
+///
+/// # Returns
+/// - `200 OK` with JSON `{ accumulator, revoked_count }`, the new accumulator in hex.
+/// - `400 Bad Request` if `user_id` is not valid in `sub_encoding`.
+/// - `404 Not Found` if issuer keys are not initialized yet.
+/// - `500 Internal Server Error` if the revocation list could not be persisted.
+#[utoipa::path(
+    post,
+    path = "/admin/revoke",
+    request_body = RevokeRequest,
+    responses(
+        (status = 200, body = RevokeResponse),
+        (status = 400, description = "user_id not valid in sub_encoding", body = crate::error::ErrorBody),
+        (status = 401, description = "invalid admin token", body = crate::error::ErrorBody),
+        (status = 404, description = "issuer keys not initialized", body = crate::error::ErrorBody),
+        (status = 500, description = "revocation list could not be persisted", body = crate::error::ErrorBody),
+    ),
+    security(("admin_token" = [])),
+)]
+#[post("/revoke")]
+pub async fn revoke(
+    state: web::Data<AppState>,
+    req: web::Json<RevokeRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let keys = state.issuer_keys.lock_or_recover();
+    let keys = &state.signing_keys(keys.as_ref().ok_or(ApiError::IssuerKeysNotInitialized)?);
+    let s = subject_element(&req.user_id, req.sub_encoding)?;
+
+    let mut list = state.revocation.lock_or_recover();
+    if list.revoke(s) {
+        list.save_to_path(&state.revocation_path).map_err(|e| {
+            tracing::error!(error = %e, "failed to persist revocation list");
+            ApiError::PersistenceFailed
+        })?;
+    }
+
+    Ok(HttpResponse::Ok().json(RevokeResponse {
+        accumulator: g1_to_hex(&list.accumulator(keys)),
+        revoked_count: list.len(),
+    }))
+}
+
+/// # Arguments
+/// * `query: web::Query<StatusQuery>` — `?user_id=<sub>&sub_encoding=<enc>`,
+///   the encoding defaulting to `decimal`.
+///
+/// # Returns
+/// - `200 OK` with JSON `{ version, accumulator, revoked, witness }`; `witness`
+///   is `{ C, d }` in hex for an unrevoked user and `null` otherwise.
+/// - `400 Bad Request` if `user_id` is not valid in `sub_encoding`.
+/// - `404 Not Found` if issuer keys are not initialized yet.
+#[utoipa::path(
+    get,
+    path = "/revocation_status",
+    params(StatusQuery),
+    responses(
+        (status = 200, body = StatusResponse),
+        (status = 400, description = "user_id not valid in sub_encoding", body = crate::error::ErrorBody),
+        (status = 404, description = "issuer keys not initialized", body = crate::error::ErrorBody),
+    ),
+)]
+#[get("/revocation_status")]
+pub async fn revocation_status(
+    state: web::Data<AppState>,
+    query: web::Query<StatusQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let keys = state.issuer_keys.lock_or_recover();
+    let keys = &state.signing_keys(keys.as_ref().ok_or(ApiError::IssuerKeysNotInitialized)?);
+    let s = subject_element(&query.user_id, query.sub_encoding)?;
+    let list = state.revocation.lock_or_recover();
+
+    let witness = list.non_membership_witness(keys, s);
+    Ok(HttpResponse::Ok().json(StatusResponse {
+        version: keys.version,
+        accumulator: g1_to_hex(&list.accumulator(keys)),
+        revoked: list.is_revoked(&s),
+        witness: witness.map(|w| WitnessResponse { C: g1_to_hex(&w.C), d: fr_to_hex(&w.d) }),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use actix_web::test;
+    use syra_login::codec::{fr_from_hex, g1_from_hex};
+    use syra_login::credential::verify_non_membership;
+
+    use super::*;
+    use crate::test_support::{admin_auth, test_app, test_state, MockVerifier};
+
+    const ALICE: &str = "113048723091228773641";
+
+    fn witness_of(status: &serde_json::Value) -> NonMembershipWitness {
+        NonMembershipWitness {
+            C: g1_from_hex(status["witness"]["C"].as_str().unwrap()).unwrap(),
+            d: fr_from_hex(status["witness"]["d"].as_str().unwrap()).unwrap(),
+        }
+    }
+
+    /// Revoking a hex-spelled subject revokes the decimal one: its witness
+    /// verifies before, `verify_non_membership` fails on it against the new
+    /// accumulator after, and `/revocation_status` stops handing one out.
+    #[actix_web::test]
+    async fn revoking_any_spelling_of_a_subject_invalidates_its_witness() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        let app = test_app!(state.clone());
+        let ivk = state.issuer_keys.lock_or_recover().as_ref().unwrap().ivk_bundle();
+        let s = s_from_sub(ALICE);
+        let hex = format!("0x{:x}", ALICE.parse::<u128>().unwrap());
+
+        let req = test::TestRequest::get().uri(&format!("/revocation_status?user_id={ALICE}")).to_request();
+        let before: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(before["revoked"], false);
+        let witness = witness_of(&before);
+        let accumulator = g1_from_hex(before["accumulator"].as_str().unwrap()).unwrap();
+        assert!(verify_non_membership(&ivk, &accumulator, s, &witness));
+
+        let req = test::TestRequest::post()
+            .uri("/admin/revoke")
+            .insert_header(admin_auth())
+            .set_json(serde_json::json!({ "user_id": hex, "sub_encoding": "hex" }))
+            .to_request();
+        let revoked: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(revoked["revoked_count"], 1);
+        let accumulator = g1_from_hex(revoked["accumulator"].as_str().unwrap()).unwrap();
+        assert!(!verify_non_membership(&ivk, &accumulator, s, &witness));
+        assert!(RevocationList::load_from_path(&state.revocation_path).unwrap().is_revoked(&s));
+
+        for uri in [
+            format!("/revocation_status?user_id={ALICE}"),
+            format!("/revocation_status?user_id={hex}&sub_encoding=hex"),
+        ] {
+            let status: serde_json::Value =
+                test::call_and_read_body_json(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+            assert_eq!(status["revoked"], true);
+            assert_eq!(status["witness"], serde_json::Value::Null);
+            assert_eq!(status["accumulator"], revoked["accumulator"]);
+        }
+
+        // Unrevoked users get a witness against the new accumulator.
+        let req = test::TestRequest::get().uri("/revocation_status?user_id=42").to_request();
+        let other: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(verify_non_membership(&ivk, &accumulator, s_from_sub("42"), &witness_of(&other)));
+    }
+
+    #[actix_web::test]
+    async fn subjects_not_in_their_encoding_are_rejected() {
+        let state = test_state(Arc::new(MockVerifier::accepting()));
+        let app = test_app!(state.clone());
+
+        let req = test::TestRequest::post()
+            .uri("/admin/revoke")
+            .insert_header(admin_auth())
+            .set_json(serde_json::json!({ "user_id": "0xzz", "sub_encoding": "hex" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["code"], "INVALID_SUBJECT");
+        assert_eq!(state.revocation.lock_or_recover().len(), 0);
+
+        let req = test::TestRequest::get().uri("/revocation_status?user_id=abc").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+}
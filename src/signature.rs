@@ -0,0 +1,200 @@
+//! SyRA signatures: a user signs a message under a per-context pseudonym,
+//! proving they hold a `usk` issued under `ivk` without revealing it.
+//!
+//! With `usk = g1^{1/(s+isk)}` and `H = hash_to_G2(PSEUDONYM_TAG ∥ context)`,
+//! the pseudonym is `nym = e(usk, H)`: the same user gets the same `nym`
+//! every time they sign in one context, and unrelated ones across contexts.
+//! The signer blinds the key with a random `r`,
+//!
+//! ```text
+//! U = usk^r,   Ū = U^isk = g1^r · U^{-s}
+//! ```
+//!
+//! and proves knowledge of `(r, s)` with `Ū = g1^r · U^{-s}` and
+//! `e(U, H) = nym^r`, made non-interactive over the message. The verifier
+//! additionally checks `e(U, ivk_hat) == e(Ū, g2)`, which holds only for a
+//! `U` that is a power of a key issued under `isk`.
+//!
+//! Signing happens where `usk` lives, i.e. on the client; the issuer never
+//! sees it. A signature says nothing about revocation: check the signer's
+//! non-membership separately if that matters.
+
+use anyhow::Context;
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::rand::Rng;
+use dock_crypto_utils::hashing_utils::affine_group_elem_from_try_and_incr;
+use zeroize::Zeroizing;
+
+use crate::constants::{GroupHash, PSEUDONYM_TAG, SIGNATURE_TAG};
+use crate::hash::hash_to_fr;
+use crate::keys::IvkBundle;
+
+/// A user's pseudonym in one context, `e(usk, H(context))`.
+pub type Pseudonym = PairingOutput<Bls12_381>;
+
+/// A signature of knowledge of a `usk` under `ivk`, bound to a message and
+/// carrying the signer's pseudonym for the signing context.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Signature {
+    pub nym: Pseudonym,
+    pub U: G1Affine,
+    pub U_bar: G1Affine,
+    /// Fiat–Shamir challenge.
+    pub c: Fr,
+    pub z_r: Fr,
+    pub z_s: Fr,
+}
+
+impl Signature {
+    /// `nym ∥ U ∥ U_bar ∥ c ∥ z_r ∥ z_s`, each compressed: 768 bytes.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SerializationError> {
+        let mut buf = Vec::new();
+        self.nym.serialize_compressed(&mut buf)?;
+        self.U.serialize_compressed(&mut buf)?;
+        self.U_bar.serialize_compressed(&mut buf)?;
+        self.c.serialize_compressed(&mut buf)?;
+        self.z_r.serialize_compressed(&mut buf)?;
+        self.z_s.serialize_compressed(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Hex of [`to_bytes`](Self::to_bytes).
+    pub fn to_hex_string(&self) -> Result<String, SerializationError> {
+        Ok(hex::encode(self.to_bytes()?))
+    }
+
+    /// Inverse of [`to_bytes`](Self::to_bytes). Points are checked to be in
+    /// their prime-order subgroups, and the input must be exactly one
+    /// signature long.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut rd = bytes;
+        let sig = Signature {
+            nym: CanonicalDeserialize::deserialize_compressed(&mut rd).context("nym")?,
+            U: CanonicalDeserialize::deserialize_compressed(&mut rd).context("U")?,
+            U_bar: CanonicalDeserialize::deserialize_compressed(&mut rd).context("U_bar")?,
+            c: CanonicalDeserialize::deserialize_compressed(&mut rd).context("c")?,
+            z_r: CanonicalDeserialize::deserialize_compressed(&mut rd).context("z_r")?,
+            z_s: CanonicalDeserialize::deserialize_compressed(&mut rd).context("z_s")?,
+        };
+        anyhow::ensure!(rd.is_empty(), "{} trailing bytes after signature", rd.len());
+        Ok(sig)
+    }
+
+    /// Inverse of [`to_hex_string`](Self::to_hex_string).
+    pub fn from_hex_string(s: &str) -> anyhow::Result<Self> {
+        let bytes = hex::decode(s.trim()).context("invalid hex")?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+/// The pseudonym `usk` signs under in `context`, `e(usk, H(context))`.
+///
+/// Deterministic, so one user always gets the same pseudonym in one context,
+/// while pseudonyms of one user in two contexts cannot be linked without
+/// `usk`. It lives in the pairing target group: a G1 value would need the
+/// exponent `1/(s+isk)` itself, which nobody but the issuer group holds.
+pub fn derive_pseudonym(usk: &G1Affine, context: &[u8]) -> Pseudonym {
+    Bls12_381::pairing(*usk, context_point(context))
+}
+
+/// Sign `message` in `context` with the credential `(s, usk)` issued under
+/// `ivk`. `s` is [`s_from_sub`](crate::keys::s_from_sub) of the user's `sub`.
+///
+/// The result only verifies if `usk` really is `g1^{1/(s+isk)}` for this
+/// `ivk`; check it with [`verify_user_key`](crate::credential::verify_user_key)
+/// first when in doubt.
+pub fn syra_sign<R: Rng>(
+    ivk: &IvkBundle,
+    s: Fr,
+    usk: &G1Affine,
+    context: &[u8],
+    message: &[u8],
+    rng: &mut R,
+) -> Signature {
+    let g1 = ivk.bp.g1;
+    let r = Zeroizing::new(loop {
+        let r = Fr::rand(rng);
+        if !r.is_zero() {
+            break r;
+        }
+    });
+    let U = (*usk * *r).into_affine();
+    let U_bar = (g1 * *r - U * s).into_affine();
+    let nym = derive_pseudonym(usk, context);
+
+    let rho_r = Zeroizing::new(Fr::rand(rng));
+    let rho_s = Zeroizing::new(Fr::rand(rng));
+    let T1 = (g1 * *rho_r - U * *rho_s).into_affine();
+    let T2 = nym * *rho_r;
+
+    let c = challenge(ivk, context, message, &nym, &U, &U_bar, &T1, &T2);
+    Signature {
+        nym,
+        U,
+        U_bar,
+        c,
+        z_r: *rho_r + c * *r,
+        z_s: *rho_s + c * s,
+    }
+}
+
+/// Check `sig` on `message` in `context` against `ivk`:
+///
+/// ```text
+/// U != 1,  e(U, ivk_hat) == e(Ū, g2),
+/// c == H(…, g1^{z_r} · U^{-z_s} · Ū^{-c},  nym^{z_r} · e(U, H(context))^{-c})
+/// ```
+///
+/// On success `sig.nym` is the signer's pseudonym in `context`.
+pub fn syra_verify_signature(ivk: &IvkBundle, context: &[u8], message: &[u8], sig: &Signature) -> bool {
+    if sig.U.is_zero() {
+        return false;
+    }
+    if Bls12_381::pairing(sig.U, ivk.ivk_hat) != Bls12_381::pairing(sig.U_bar, ivk.bp.g2) {
+        return false;
+    }
+    let T1 = (ivk.bp.g1 * sig.z_r - sig.U * sig.z_s - sig.U_bar * sig.c).into_affine();
+    let T2 = sig.nym * sig.z_r - Bls12_381::pairing(sig.U, context_point(context)) * sig.c;
+    challenge(ivk, context, message, &sig.nym, &sig.U, &sig.U_bar, &T1, &T2) == sig.c
+}
+
+/// `H(context)` in G2, by try-and-increment like the generators.
+fn context_point(context: &[u8]) -> G2Affine {
+    let mut msg = Vec::with_capacity(PSEUDONYM_TAG.len() + context.len());
+    msg.extend_from_slice(PSEUDONYM_TAG.as_bytes());
+    msg.extend_from_slice(context);
+    affine_group_elem_from_try_and_incr::<G2Affine, GroupHash>(&msg)
+}
+
+/// Fiat–Shamir challenge over the issuer key, the statement and the
+/// commitments. `context` is length-prefixed so it cannot run into `message`.
+#[allow(clippy::too_many_arguments)]
+fn challenge(
+    ivk: &IvkBundle,
+    context: &[u8],
+    message: &[u8],
+    nym: &Pseudonym,
+    U: &G1Affine,
+    U_bar: &G1Affine,
+    T1: &G1Affine,
+    T2: &Pseudonym,
+) -> Fr {
+    const OK: &str = "serializing into a Vec cannot fail";
+    let mut buf = Vec::new();
+    ivk.bp.g1.serialize_compressed(&mut buf).expect(OK);
+    ivk.bp.g2.serialize_compressed(&mut buf).expect(OK);
+    ivk.ivk_hat.serialize_compressed(&mut buf).expect(OK);
+    buf.extend_from_slice(&(context.len() as u64).to_le_bytes());
+    buf.extend_from_slice(context);
+    nym.serialize_compressed(&mut buf).expect(OK);
+    U.serialize_compressed(&mut buf).expect(OK);
+    U_bar.serialize_compressed(&mut buf).expect(OK);
+    T1.serialize_compressed(&mut buf).expect(OK);
+    T2.serialize_compressed(&mut buf).expect(OK);
+    buf.extend_from_slice(message);
+    hash_to_fr(SIGNATURE_TAG.as_bytes(), &buf)
+}
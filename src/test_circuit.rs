@@ -0,0 +1,91 @@
+//! A Groth16 verifying key built from known exponents instead of a
+//! constraint system, for tests. Knowing every discrete log of the key, a
+//! proof for any public inputs can be solved for directly, so the verifier
+//! runs exactly as it would against a real circuit without a proving key or
+//! a multi-minute setup.
+
+use anyhow::Result;
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::{Field, UniformRand};
+use ark_groth16::{Proof, VerifyingKey};
+use ark_std::rand::rngs::OsRng;
+
+use crate::constants::CHUNK_BITS;
+use crate::jwt_proof_verifier::{
+    rsa_public_inputs, vk_to_snarkjs_json, RSA_CIRCUIT_EXTRA_INPUTS, RSA_MODULUS_BITS,
+};
+
+pub struct TestCircuit {
+    pub vk: VerifyingKey<Bn254>,
+    alpha: Fr,
+    beta: Fr,
+    gamma: Fr,
+    delta: Fr,
+    /// Exponents of `vk.gamma_abc_g1`.
+    ic: Vec<Fr>,
+}
+
+impl TestCircuit {
+    /// A fresh verifying key taking `n_inputs` public inputs.
+    pub fn new(n_inputs: usize) -> Self {
+        let mut rng = OsRng;
+        let [alpha, beta, gamma, delta] = [(); 4].map(|_| Fr::rand(&mut rng));
+        let ic: Vec<Fr> = (0..=n_inputs).map(|_| Fr::rand(&mut rng)).collect();
+        let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+        let vk = VerifyingKey {
+            alpha_g1: (g1 * alpha).into_affine(),
+            beta_g2: (g2 * beta).into_affine(),
+            gamma_g2: (g2 * gamma).into_affine(),
+            delta_g2: (g2 * delta).into_affine(),
+            gamma_abc_g1: ic.iter().map(|x| (g1 * x).into_affine()).collect(),
+        };
+        TestCircuit { vk, alpha, beta, gamma, delta, ic }
+    }
+
+    /// A fresh key in the default RSA layout: `sub`, the limbs of a
+    /// 2048-bit modulus, `subStatement`.
+    pub fn rsa() -> Self {
+        Self::new(RSA_MODULUS_BITS.div_ceil(CHUNK_BITS) + RSA_CIRCUIT_EXTRA_INPUTS)
+    }
+
+    pub fn n_inputs(&self) -> usize {
+        self.ic.len() - 1
+    }
+
+    /// A proof that verifies for exactly `inputs`. With random `a`, `b`,
+    /// `C = g1^c` is chosen so that
+    ///
+    /// ```text
+    /// a·b == α·β + L·γ + c·δ,   L = ic_0 + Σ x_i·ic_i
+    /// ```
+    ///
+    /// which is the Groth16 equation in the exponent.
+    pub fn prove(&self, inputs: &[Fr]) -> Proof<Bn254> {
+        assert_eq!(inputs.len(), self.n_inputs(), "public input count");
+        let mut rng = OsRng;
+        let (a, b) = (Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let l = self.ic[0] + inputs.iter().zip(&self.ic[1..]).map(|(x, ic)| *x * ic).sum::<Fr>();
+        let c = (a * b - self.alpha * self.beta - l * self.gamma)
+            * self.delta.inverse().expect("delta is non-zero");
+        let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+        Proof {
+            a: (g1 * a).into_affine(),
+            b: (g2 * b).into_affine(),
+            c: (g1 * c).into_affine(),
+        }
+    }
+
+    /// A proof for `sub` under the base64url RSA modulus `n`, over the
+    /// public inputs `Verifier::verify` builds for a key in [`rsa`](Self::rsa)
+    /// layout.
+    pub fn prove_rsa(&self, sub: &str, n: &str) -> Result<Proof<Bn254>> {
+        let limbs = self.n_inputs() - RSA_CIRCUIT_EXTRA_INPUTS;
+        Ok(self.prove(&rsa_public_inputs(sub, n, CHUNK_BITS, limbs)?))
+    }
+
+    /// `vk` as SnarkJS's `verification_key.json`.
+    pub fn vk_json(&self) -> String {
+        vk_to_snarkjs_json(&self.vk).expect("vk serializes")
+    }
+}
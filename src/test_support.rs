@@ -0,0 +1,306 @@
+//! Fixtures for the handler tests: an [`AppState`] with fresh issuer keys
+//! and a scripted [`ProofVerifier`], served through [`routes`] like `main`
+//! does.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_web::web;
+use anyhow::bail;
+use ark_bls12_381::{Fr, G1Affine};
+use ark_bn254::{Bn254, Fr as BnFr};
+use ark_ec::CurveGroup;
+use ark_ff::Zero;
+use ark_groth16::{Proof, VerifyingKey};
+use ark_std::rand::rngs::OsRng;
+use ark_std::UniformRand;
+use async_trait::async_trait;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Notify;
+
+use crate::audit::AuditLog;
+use crate::challenge::ChallengeStore;
+use crate::dkg::DkgPointMessage;
+use syra_login::jwt_proof_verifier::{
+    BatchItem, IdTokenClaims, JwtClaims, ProofVerifier, VerifyDiagnostics, VerifyFailure, DEFAULT_CLOCK_SKEW,
+};
+use crate::metrics::Metrics;
+use crate::rate_limit::RateLimiter;
+use crate::revocation::RevocationList;
+use crate::{sample_issuer_keys, AppState};
+use syra_login::codec::{fr_to_hex, g1_to_hex};
+use syra_login::keys::{Bp, SchemeParams};
+use syra_login::proof::proof_to_base64_compressed;
+use syra_login::test_circuit::TestCircuit;
+
+pub const ADMIN_TOKEN: &str = "test-admin-token";
+
+/// `--max-proof-bytes` of the test app.
+pub const MAX_PROOF_BYTES: usize = 8192;
+
+/// A [`ProofVerifier`] whose verdicts are scripted: every proof verifies
+/// unless its `sub` is listed in `rejected`.
+#[derive(Default)]
+pub struct MockVerifier {
+    rejected: Vec<String>,
+    /// When set, `verify` waits for a permit before answering, so a test
+    /// can look at the state while a verification is in flight.
+    gate: Option<Arc<Notify>>,
+    /// Calls to `verify` so far.
+    pub calls: AtomicUsize,
+}
+
+impl MockVerifier {
+    pub fn accepting() -> Self {
+        Self::default()
+    }
+
+    pub fn rejecting(subs: &[&str]) -> Self {
+        MockVerifier { rejected: subs.iter().map(|s| s.to_string()).collect(), ..Self::default() }
+    }
+
+    pub fn gated(gate: Arc<Notify>) -> Self {
+        MockVerifier { gate: Some(gate), ..Self::default() }
+    }
+
+    fn accepts(&self, sub: &str) -> bool {
+        !self.rejected.iter().any(|r| r == sub)
+    }
+}
+
+#[async_trait]
+impl ProofVerifier for MockVerifier {
+    async fn verify(
+        &self,
+        _iss: &str,
+        sub: &str,
+        _kid: &str,
+        _circuit_id: Option<&str>,
+        _proof: Proof<Bn254>,
+        _statement: Option<BnFr>,
+    ) -> anyhow::Result<bool> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if let Some(gate) = &self.gate {
+            gate.notified().await;
+        }
+        Ok(self.accepts(sub))
+    }
+
+    async fn verify_verbose(
+        &self,
+        iss: &str,
+        sub: &str,
+        kid: &str,
+        circuit_id: Option<&str>,
+        proof: Proof<Bn254>,
+        statement: Option<BnFr>,
+    ) -> anyhow::Result<VerifyDiagnostics> {
+        let valid = self.verify(iss, sub, kid, circuit_id, proof, statement).await?;
+        Ok(VerifyDiagnostics {
+            circuit: Some("RSA"),
+            failure: (!valid).then_some(VerifyFailure::Proof { inputs: 19 }),
+        })
+    }
+
+    fn check_claims(&self, claims: &JwtClaims) -> anyhow::Result<()> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        claims.check(now, DEFAULT_CLOCK_SKEW)?;
+        Ok(())
+    }
+
+    fn verifying_key(&self, _circuit_id: &str) -> Option<VerifyingKey<Bn254>> {
+        None
+    }
+
+    async fn verify_jwt_rs256(&self, _token: &str, _kid: &str) -> anyhow::Result<IdTokenClaims> {
+        bail!("MockVerifier does not verify ID tokens")
+    }
+
+    async fn verify_batch(&self, iss: &str, items: &[BatchItem]) -> anyhow::Result<Vec<bool>> {
+        let mut verdicts = Vec::with_capacity(items.len());
+        for item in items {
+            let proof = item.proof.clone();
+            verdicts.push(self.verify(iss, &item.sub, &item.kid, None, proof, item.statement).await?);
+        }
+        Ok(verdicts)
+    }
+}
+
+/// A path under the temp dir no other test uses.
+pub fn temp_path(name: &str) -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let n = NEXT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("syra-test-{}-{n}-{name}", std::process::id()))
+}
+
+/// State as `main` builds it, with version-1 issuer keys over the default
+/// generators, no audit log and no rate limit.
+pub fn test_state(verifier: Arc<dyn ProofVerifier>) -> web::Data<AppState> {
+    web::Data::new(test_app_state(verifier))
+}
+
+/// [`test_state`] before it is shared, for tests that change a setting.
+pub fn test_app_state(verifier: Arc<dyn ProofVerifier>) -> AppState {
+    let generators = Bp::default();
+    AppState {
+        issuer_keys: Mutex::new(Some(sample_issuer_keys(1, &generators, "test-issuer"))),
+        retired_issuer_keys: Mutex::new(Vec::new()),
+        issuer_keys_path: temp_path("issuer_keys.bin"),
+        retired_keys_path: temp_path("retired_issuer_keys.bin"),
+        keys_passphrase: None,
+        rate_limiter: RateLimiter::new(0),
+        revocation: Mutex::new(RevocationList::default()),
+        revocation_path: temp_path("revoked.bin"),
+        metrics: Metrics::new().expect("fresh registry"),
+        admin_token: ADMIN_TOKEN.to_string(),
+        dkg: Mutex::new(None),
+        party_index: 1,
+        require_dkg: false,
+        challenges: ChallengeStore::new(Duration::from_secs(120)),
+        require_challenge: false,
+        allow_native_jwt: false,
+        generators,
+        scheme: SchemeParams::default(),
+        issuer_id: "test-issuer".to_string(),
+        audit: AuditLog::open(None, true),
+        verifier,
+    }
+}
+
+/// The service `main` runs on `state`, minus CORS, request ids, compression
+/// and logging. A macro since `init_service`'s return type cannot be named.
+macro_rules! test_app {
+    ($state:expr) => {
+        actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data($state)
+                .configure(|cfg| $crate::routes(cfg, $crate::test_support::MAX_PROOF_BYTES)),
+        )
+        .await
+    };
+}
+pub(crate) use test_app;
+
+/// A well-formed proof, base-64; what it proves is up to the verifier.
+pub fn proof_b64() -> String {
+    let proof = TestCircuit::new(1).prove(&[BnFr::from(1u64)]);
+    proof_to_base64_compressed(&proof).expect("proof serializes")
+}
+
+/// A `generate_user_key` body for `user_id`.
+pub fn keygen_body(user_id: &str) -> serde_json::Value {
+    serde_json::json!({ "user_id": user_id, "kid": "test-kid", "proof": proof_b64() })
+}
+
+/// `Authorization` header with the admin token.
+pub fn admin_auth() -> (actix_web::http::header::HeaderName, String) {
+    (actix_web::http::header::AUTHORIZATION, format!("Bearer {ADMIN_TOKEN}"))
+}
+
+/// One dealer's messages to parties `1..=n` for a random polynomial of
+/// degree `t - 1` over `g`, built like the `dkg/` coordinator builds them.
+pub fn deal(sid: &str, n: u64, t: usize, g: G1Affine) -> Vec<DkgPointMessage> {
+    let coeffs: Vec<Fr> = (0..t).map(|_| Fr::rand(&mut OsRng)).collect();
+    let f = |x: u64| coeffs.iter().rev().fold(Fr::zero(), |acc, c| acc * Fr::from(x) + c);
+    let commit = |e: Fr| g1_to_hex(&(g * e).into_affine());
+    let Ai_all: Vec<String> = (1..=n).map(|j| commit(f(j))).collect();
+    let C_all: Vec<String> = coeffs.iter().map(|&c| commit(c)).collect();
+    (1..=n)
+        .map(|i| DkgPointMessage {
+            sid: sid.to_string(),
+            i,
+            n,
+            A: C_all[0].clone(),
+            f_i: fr_to_hex(&f(i)),
+            Ai_all: Ai_all.clone(),
+            C_all: C_all.clone(),
+        })
+        .collect()
+}
+
+/// Runs the test app on `state` behind a real listener on an ephemeral port,
+/// for what `init_service` cannot drive, such as a WebSocket upgrade.
+pub fn serve(state: web::Data<AppState>) -> SocketAddr {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind an ephemeral port");
+    let addr = listener.local_addr().expect("listener has an address");
+    let server = actix_web::HttpServer::new(move || {
+        actix_web::App::new()
+            .app_data(state.clone())
+            .configure(|cfg| crate::routes(cfg, MAX_PROOF_BYTES))
+    })
+    .workers(1)
+    .disable_signals()
+    .listen(listener)
+    .expect("listen")
+    .run();
+    actix_web::rt::spawn(server);
+    addr
+}
+
+/// Just enough of an RFC 6455 client to exchange text frames with
+/// `/admin/dkg_ws`; there is no WebSocket client among the dependencies.
+pub struct WsClient {
+    stream: TcpStream,
+}
+
+impl WsClient {
+    /// Opens the upgrade on `path` with the admin token and checks for `101`.
+    pub async fn connect(addr: SocketAddr, path: &str) -> Self {
+        let mut stream = TcpStream::connect(addr).await.expect("connect");
+        let request = format!(
+            "GET {path} HTTP/1.1\r\nHost: {addr}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\
+             Authorization: Bearer {ADMIN_TOKEN}\r\n\r\n"
+        );
+        stream.write_all(request.as_bytes()).await.expect("send upgrade");
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            head.push(stream.read_u8().await.expect("read upgrade response"));
+        }
+        let head = String::from_utf8_lossy(&head);
+        assert!(head.starts_with("HTTP/1.1 101"), "upgrade refused: {head}");
+        WsClient { stream }
+    }
+
+    /// Sends `text` as one masked text frame.
+    pub async fn send(&mut self, text: &str) {
+        const MASK: [u8; 4] = [0x12, 0x34, 0x56, 0x78];
+        let payload = text.as_bytes();
+        let mut frame = vec![0x81];
+        match payload.len() {
+            len @ 0..=125 => frame.push(0x80 | len as u8),
+            len @ 126..=0xffff => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(&MASK);
+        frame.extend(payload.iter().enumerate().map(|(k, b)| b ^ MASK[k % 4]));
+        self.stream.write_all(&frame).await.expect("send frame");
+    }
+
+    /// The next text frame from the server, skipping any other kind.
+    pub async fn recv(&mut self) -> String {
+        loop {
+            let opcode = self.stream.read_u8().await.expect("read frame") & 0x0f;
+            let len = match self.stream.read_u8().await.expect("read frame") & 0x7f {
+                126 => self.stream.read_u16().await.expect("read length") as usize,
+                127 => self.stream.read_u64().await.expect("read length") as usize,
+                len => len as usize,
+            };
+            let mut payload = vec![0; len];
+            self.stream.read_exact(&mut payload).await.expect("read payload");
+            if opcode == 0x1 {
+                return String::from_utf8(payload).expect("text frame is UTF-8");
+            }
+        }
+    }
+}
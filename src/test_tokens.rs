@@ -0,0 +1,61 @@
+//! RS256 ID tokens signed with a key generated on first use, and that key as
+//! a JWK, for tests of the native-JWT path.
+
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ark_std::rand::rngs::OsRng;
+use base64::{engine::general_purpose as b64, Engine as _};
+use rsa::traits::PublicKeyParts;
+use rsa::{Pkcs1v15Sign, RsaPrivateKey};
+use sha2::{Digest, Sha256};
+
+use crate::jwt_proof_verifier::Jwk;
+
+/// Client id [`id_token_claims`] issues tokens to.
+pub const CLIENT_ID: &str = "test-client.apps.googleusercontent.com";
+
+/// One RSA key for every signed test token; generating it is slow.
+fn signing_key() -> &'static RsaPrivateKey {
+    static KEY: OnceLock<RsaPrivateKey> = OnceLock::new();
+    KEY.get_or_init(|| RsaPrivateKey::new(&mut OsRng, 1024).expect("RSA key generates"))
+}
+
+/// The public half of the [`sign_id_token`] key, as JWKS entry `kid`.
+pub fn signing_jwk(kid: &str) -> Jwk {
+    let key = signing_key();
+    Jwk::rsa(
+        kid,
+        b64::URL_SAFE_NO_PAD.encode(key.n().to_bytes_be()),
+        b64::URL_SAFE_NO_PAD.encode(key.e().to_bytes_be()),
+    )
+}
+
+/// Claims of a Google ID token for `sub` and [`CLIENT_ID`], valid for the
+/// next hour.
+pub fn id_token_claims(sub: &str) -> serde_json::Value {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    serde_json::json!({
+        "iss": "https://accounts.google.com",
+        "sub": sub,
+        "aud": CLIENT_ID,
+        "azp": CLIENT_ID,
+        "iat": now,
+        "exp": now + 3600,
+    })
+}
+
+/// `claims` as a compact RS256 JWS under `kid`, signed with the
+/// [`signing_jwk`] key.
+pub fn sign_id_token(kid: &str, claims: &serde_json::Value) -> String {
+    let header = serde_json::json!({ "alg": "RS256", "kid": kid });
+    let signing_input = format!(
+        "{}.{}",
+        b64::URL_SAFE_NO_PAD.encode(header.to_string()),
+        b64::URL_SAFE_NO_PAD.encode(claims.to_string()),
+    );
+    let signature = signing_key()
+        .sign(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(&signing_input))
+        .expect("token signs");
+    format!("{signing_input}.{}", b64::URL_SAFE_NO_PAD.encode(signature))
+}
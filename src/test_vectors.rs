@@ -0,0 +1,105 @@
+//! Cross-implementation test vectors: `s` for fixed `user_id`s and `usk` /
+//! `usk_hat` under fixed issuer secrets. The checked-in
+//! `test_vectors.json` is [`vectors`] serialized, and the TypeScript side
+//! reads the same file; regenerate it with
+//!
+//! ```text
+//! cargo run --example test_vectors > test_vectors.json
+//! ```
+//!
+//! Inputs are fixed, so the output only changes if `s_from_sub`, the
+//! generator tags or the key derivation change, which is exactly what both
+//! sides need to notice.
+
+use ark_bls12_381::{Bls12_381, Fr};
+use serde::Serialize;
+
+use crate::codec::{fr_to_hex, g1_to_hex, g2_to_hex};
+use crate::constants::{G1_GENERATOR_TAG, G2_GENERATOR_TAG, USER_ID_TAG};
+use crate::curve;
+use crate::keys::{s_from_sub, Bp};
+
+/// `user_id`s covering plain digits, the empty string and both Unicode
+/// normal forms of the same identifier.
+pub const USER_IDS: &[&str] = &["113048723091228773641", "", "alice", "caf\u{e9}", "cafe\u{301}"];
+
+/// Issuer secrets the `usk` vectors are derived under. Public test values.
+pub const ISKS: &[u64] = &[1, 42, 0xdead_beef];
+
+#[derive(Serialize)]
+pub struct SVector {
+    pub user_id: String,
+    pub s: String,
+}
+
+#[derive(Serialize)]
+pub struct UskVector {
+    pub isk: String,
+    pub user_id: String,
+    pub s: String,
+    pub usk: String,
+    pub usk_hat: String,
+}
+
+#[derive(Serialize)]
+pub struct Vectors {
+    pub user_id_tag: &'static str,
+    pub g1_tag: &'static str,
+    pub g2_tag: &'static str,
+    pub g1: String,
+    pub g2: String,
+    pub s: Vec<SVector>,
+    pub usk: Vec<UskVector>,
+}
+
+/// The vectors for [`USER_IDS`] and [`ISKS`] over the default generators,
+/// every value in the wire hex of [`codec`](crate::codec).
+pub fn vectors() -> Vectors {
+    let bp = Bp::default();
+
+    let s = USER_IDS
+        .iter()
+        .map(|id| SVector { user_id: id.to_string(), s: fr_to_hex(&s_from_sub(id)) })
+        .collect();
+
+    let mut usk = Vec::new();
+    for &isk in ISKS {
+        let isk = Fr::from(isk);
+        for id in USER_IDS {
+            let s = s_from_sub(id);
+            let (usk_pt, usk_hat_pt) = curve::derive_user_key::<Bls12_381>(bp.g1, bp.g2, isk, s)
+                .expect("s + isk != 0 for the fixed vectors");
+            usk.push(UskVector {
+                isk: fr_to_hex(&isk),
+                user_id: id.to_string(),
+                s: fr_to_hex(&s),
+                usk: g1_to_hex(&usk_pt),
+                usk_hat: g2_to_hex(&usk_hat_pt),
+            });
+        }
+    }
+
+    Vectors {
+        user_id_tag: USER_ID_TAG,
+        g1_tag: G1_GENERATOR_TAG,
+        g2_tag: G2_GENERATOR_TAG,
+        g1: g1_to_hex(&bp.g1),
+        g2: g2_to_hex(&bp.g2),
+        s,
+        usk,
+    }
+}
+
+// The fixture pins the default generators, so it does not apply to
+// `sha256-generators` builds.
+#[cfg(all(test, not(feature = "sha256-generators")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vectors_match_the_checked_in_fixture() {
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../test_vectors.json")).unwrap();
+        assert_eq!(serde_json::to_value(vectors()).unwrap(), fixture);
+    }
+}
@@ -0,0 +1,97 @@
+use wasm_bindgen::prelude::*;
+
+use crate::credential::{verify_credential, NonMembershipWitness};
+use crate::codec::{fr_from_hex, fr_to_hex, g1_from_hex};
+use crate::keys::{s_from_sub, IvkBundle};
+
+fn js_err(e: impl std::fmt::Display) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// `s` for a `sub`, hex of the compressed field element.
+#[wasm_bindgen(js_name = sFromSub)]
+pub fn s_from_sub_hex(sub: &str) -> Result<String, JsError> {
+    Ok(fr_to_hex(&s_from_sub(sub)))
+}
+
+/// [`verify_credential`] over hex inputs: `ivk` as returned by `/ivk`, `s`
+/// from [`s_from_sub_hex`], `usk` from `generate_user_key`, and
+/// `accumulator` / `witness_c` / `witness_d` from `/revocation_status`.
+#[wasm_bindgen(js_name = verifyCredential)]
+pub fn verify_credential_hex(
+    ivk: &str,
+    s: &str,
+    usk: &str,
+    accumulator: &str,
+    witness_c: &str,
+    witness_d: &str,
+) -> Result<bool, JsError> {
+    let ivk = IvkBundle::from_hex_string(ivk).map_err(js_err)?;
+    let s = fr_from_hex(s).map_err(js_err)?;
+    let usk = g1_from_hex(usk).map_err(js_err)?;
+    let accumulator = g1_from_hex(accumulator).map_err(js_err)?;
+    let witness = NonMembershipWitness {
+        C: g1_from_hex(witness_c).map_err(js_err)?,
+        d: fr_from_hex(witness_d).map_err(js_err)?,
+    };
+    Ok(verify_credential(&ivk, s, &usk, &accumulator, &witness))
+}
+
+// `JsError` calls into JS, so natively only the success paths can run; these
+// cover the same decoding and verification the browser build does.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use ark_bls12_381::{Bls12_381, Fr, G1Affine};
+    use ark_ec::AffineRepr;
+    use ark_ff::One;
+
+    use crate::codec::g1_to_hex;
+    use crate::curve;
+    use crate::keys::Bp;
+
+    /// `(ivk, s, usk, accumulator, witness_c, witness_d)` as hex, for a
+    /// credential issued to `sub` with nothing revoked: `V = g1`, `d = 1`,
+    /// `C = 0`.
+    fn credential(sub: &str) -> [String; 6] {
+        let bp = Bp::default();
+        let keys = curve::sample_issuer_keys::<Bls12_381, _>(bp.g1, bp.g2, &mut ark_std::test_rng());
+        let s = s_from_sub(sub);
+        let (usk, _) = curve::derive_user_key::<Bls12_381>(bp.g1, bp.g2, keys.isk, s).unwrap();
+        let ivk = IvkBundle {
+            version: 1,
+            bp: bp.clone(),
+            ivk_hat: keys.ivk_hat,
+            W: keys.W,
+            W_hat: keys.W_hat,
+            issuer_id: "wasm-test".into(),
+        };
+        [
+            ivk.to_hex_string().unwrap(),
+            fr_to_hex(&s),
+            g1_to_hex(&usk),
+            g1_to_hex(&bp.g1),
+            g1_to_hex(&G1Affine::zero()),
+            fr_to_hex(&Fr::one()),
+        ]
+    }
+
+    #[test]
+    fn s_from_sub_hex_matches_the_library() {
+        assert_eq!(s_from_sub_hex("alice").unwrap(), fr_to_hex(&s_from_sub("alice")));
+    }
+
+    #[test]
+    fn verify_credential_hex_accepts_an_issued_credential() {
+        let [ivk, s, usk, acc, c, d] = credential("alice");
+        assert_eq!(s, s_from_sub_hex("alice").unwrap());
+        assert!(verify_credential_hex(&ivk, &s, &usk, &acc, &c, &d).unwrap());
+    }
+
+    #[test]
+    fn verify_credential_hex_rejects_another_users_s() {
+        let [ivk, _, usk, acc, c, d] = credential("alice");
+        let bob = s_from_sub_hex("bob").unwrap();
+        assert!(!verify_credential_hex(&ivk, &bob, &usk, &acc, &c, &d).unwrap());
+    }
+}
@@ -0,0 +1,106 @@
+//! `syra-verify` as a user runs it: exit 0 and `VALID` for a good
+//! credential, 1 and `INVALID` for a bad one, 2 for input it cannot parse.
+
+use std::process::{Command, Output};
+
+use ark_bls12_381::{Bls12_381, Fr, G1Affine};
+use ark_ec::CurveGroup;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use syra_login::codec::{fr_to_hex, g1_to_hex};
+use syra_login::curve::{derive_user_key, sample_issuer_keys};
+use syra_login::keys::{s_from_sub, Bp, IvkBundle};
+
+const SUB: &str = "113048723091228773641";
+const REVOKED_SUB: &str = "42";
+
+/// An issuer's IVK bundle (hex) and `usk` (hex) for `sub` under it, plus a
+/// revocation state with only [`REVOKED_SUB`] in it: the accumulator
+/// `V = g1^{r + isk}` and `sub`'s witness `(C, d) = (g1, r - s)`.
+struct Credential {
+    ivk: String,
+    usk: String,
+    accumulator: String,
+    witness_c: String,
+    witness_d: String,
+}
+
+fn credential(sub: &str) -> Credential {
+    let mut rng = StdRng::seed_from_u64(7);
+    let bp = Bp::default();
+    let keys = sample_issuer_keys::<Bls12_381, _>(bp.g1, bp.g2, &mut rng);
+    let s = s_from_sub(sub);
+    let (usk, _) = derive_user_key::<Bls12_381>(bp.g1, bp.g2, keys.isk, s).expect("s + isk != 0");
+    let r: Fr = s_from_sub(REVOKED_SUB);
+    let accumulator: G1Affine = (bp.g1 * (r + keys.isk)).into_affine();
+    let ivk = IvkBundle {
+        version: 1,
+        bp: bp.clone(),
+        ivk_hat: keys.ivk_hat,
+        W: keys.W,
+        W_hat: keys.W_hat,
+        issuer_id: "syra-verify-test".into(),
+    };
+    Credential {
+        ivk: ivk.to_hex_string().unwrap(),
+        usk: g1_to_hex(&usk),
+        accumulator: g1_to_hex(&accumulator),
+        witness_c: g1_to_hex(&bp.g1),
+        witness_d: fr_to_hex(&(r - s)),
+    }
+}
+
+fn syra_verify(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_syra-verify")).args(args).output().expect("syra-verify runs")
+}
+
+fn assert_exit(out: &Output, code: i32, stdout: &str) {
+    assert_eq!(out.status.code(), Some(code), "stderr: {}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), stdout);
+}
+
+#[test]
+fn a_credential_for_the_sub_is_valid() {
+    let c = credential(SUB);
+    let s = fr_to_hex(&s_from_sub(SUB));
+    assert_exit(&syra_verify(&["--ivk", &c.ivk, "--sub", SUB, "--usk", &c.usk]), 0, "VALID");
+    assert_exit(&syra_verify(&["--ivk", &c.ivk, "--s", &s, "--usk", &c.usk]), 0, "VALID");
+    let out = syra_verify(&[
+        "--ivk", &c.ivk, "--sub", SUB, "--usk", &c.usk,
+        "--accumulator", &c.accumulator, "--witness-c", &c.witness_c, "--witness-d", &c.witness_d,
+    ]);
+    assert_exit(&out, 0, "VALID");
+}
+
+#[test]
+fn another_sub_or_a_revoked_one_is_invalid() {
+    let c = credential(SUB);
+    assert_exit(&syra_verify(&["--ivk", &c.ivk, "--sub", "43", "--usk", &c.usk]), 1, "INVALID");
+
+    // REVOKED_SUB's own key passes the pairing but not the revocation check:
+    // its `d = r - r` is zero, which no non-membership witness may have.
+    let revoked = credential(REVOKED_SUB);
+    assert_exit(&syra_verify(&["--ivk", &revoked.ivk, "--sub", REVOKED_SUB, "--usk", &revoked.usk]), 0, "VALID");
+    let out = syra_verify(&[
+        "--ivk", &revoked.ivk, "--sub", REVOKED_SUB, "--usk", &revoked.usk,
+        "--accumulator", &revoked.accumulator, "--witness-c", &revoked.witness_c, "--witness-d", &revoked.witness_d,
+    ]);
+    assert_exit(&out, 1, "INVALID");
+}
+
+#[test]
+fn malformed_input_exits_2() {
+    let c = credential(SUB);
+    for args in [
+        vec!["--ivk", "zz", "--sub", SUB, "--usk", &c.usk],
+        vec!["--ivk", &c.ivk, "--sub", SUB, "--usk", "00"],
+        vec!["--ivk", &c.ivk, "--s", "not-hex", "--usk", &c.usk],
+        // clap's own usage errors: a missing argument, a partial witness
+        vec!["--ivk", &c.ivk, "--usk", &c.usk],
+        vec!["--ivk", &c.ivk, "--sub", SUB, "--usk", &c.usk, "--accumulator", &c.accumulator],
+    ] {
+        let out = syra_verify(&args);
+        assert_eq!(out.status.code(), Some(2), "{args:?}");
+        assert!(out.stdout.is_empty(), "{args:?}");
+        assert!(!out.stderr.is_empty(), "{args:?}");
+    }
+}